@@ -0,0 +1,47 @@
+-- Qualify every manga/chapter reference with the source it belongs to.
+-- Existing rows all came from MangaDex. IDs are widened to TEXT because other
+-- sources do not use 36 character UUIDs.
+
+-- manga_cache
+ALTER TABLE manga_cache ADD COLUMN source VARCHAR(32) NOT NULL DEFAULT 'mangadex';
+ALTER TABLE manga_cache ALTER COLUMN mangadex_id TYPE TEXT;
+ALTER TABLE manga_cache DROP CONSTRAINT manga_cache_mangadex_id_key;
+ALTER TABLE manga_cache ADD CONSTRAINT manga_cache_source_mangadex_id_key UNIQUE (source, mangadex_id);
+
+DROP INDEX idx_manga_cache_mangadex_id;
+
+-- chapter_cache
+ALTER TABLE chapter_cache ADD COLUMN source VARCHAR(32) NOT NULL DEFAULT 'mangadex';
+ALTER TABLE chapter_cache ALTER COLUMN mangadex_id TYPE TEXT;
+ALTER TABLE chapter_cache ALTER COLUMN manga_mangadex_id TYPE TEXT;
+ALTER TABLE chapter_cache DROP CONSTRAINT chapter_cache_mangadex_id_key;
+ALTER TABLE chapter_cache ADD CONSTRAINT chapter_cache_source_mangadex_id_key UNIQUE (source, mangadex_id);
+
+DROP INDEX idx_chapter_manga;
+CREATE INDEX idx_chapter_manga ON chapter_cache(source, manga_mangadex_id);
+
+-- user_bookmarks
+ALTER TABLE user_bookmarks ADD COLUMN source VARCHAR(32) NOT NULL DEFAULT 'mangadex';
+ALTER TABLE user_bookmarks ALTER COLUMN manga_mangadex_id TYPE TEXT;
+ALTER TABLE user_bookmarks DROP CONSTRAINT user_bookmarks_user_id_manga_mangadex_id_key;
+ALTER TABLE user_bookmarks ADD CONSTRAINT user_bookmarks_user_id_source_manga_mangadex_id_key
+    UNIQUE (user_id, source, manga_mangadex_id);
+
+-- user_reading_progress
+ALTER TABLE user_reading_progress ADD COLUMN source VARCHAR(32) NOT NULL DEFAULT 'mangadex';
+ALTER TABLE user_reading_progress ALTER COLUMN manga_mangadex_id TYPE TEXT;
+ALTER TABLE user_reading_progress ALTER COLUMN chapter_mangadex_id TYPE TEXT;
+ALTER TABLE user_reading_progress DROP CONSTRAINT user_reading_progress_user_id_manga_mangadex_id_key;
+ALTER TABLE user_reading_progress ADD CONSTRAINT user_reading_progress_user_id_source_manga_mangadex_id_key
+    UNIQUE (user_id, source, manga_mangadex_id);
+
+-- reading_history
+ALTER TABLE reading_history ADD COLUMN source VARCHAR(32) NOT NULL DEFAULT 'mangadex';
+ALTER TABLE reading_history ALTER COLUMN manga_mangadex_id TYPE TEXT;
+ALTER TABLE reading_history ALTER COLUMN chapter_mangadex_id TYPE TEXT;
+ALTER TABLE reading_history DROP CONSTRAINT reading_history_user_id_chapter_mangadex_id_key;
+ALTER TABLE reading_history ADD CONSTRAINT reading_history_user_id_source_chapter_mangadex_id_key
+    UNIQUE (user_id, source, chapter_mangadex_id);
+
+DROP INDEX idx_reading_history_manga;
+CREATE INDEX idx_reading_history_manga ON reading_history(source, manga_mangadex_id);
@@ -0,0 +1,12 @@
+-- Tag catalogue mirrored from MangaDex /manga/tag, used to build search filters
+CREATE TABLE tags (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    source VARCHAR(32) NOT NULL DEFAULT 'mangadex',
+    mangadex_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    tag_group VARCHAR(20) NOT NULL,
+    cached_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (source, mangadex_id)
+);
+
+CREATE INDEX idx_tags_group ON tags(source, tag_group);
@@ -0,0 +1,12 @@
+-- Per-user reading preferences
+CREATE TYPE reading_direction AS ENUM ('ltr', 'rtl', 'vertical');
+
+CREATE TABLE user_preferences (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    translated_languages TEXT[] NOT NULL DEFAULT ARRAY['en'],
+    scanlation_groups TEXT[] NOT NULL DEFAULT '{}',
+    content_rating_ceiling VARCHAR(20),
+    data_saver BOOLEAN NOT NULL DEFAULT FALSE,
+    reading_direction reading_direction NOT NULL DEFAULT 'rtl',
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
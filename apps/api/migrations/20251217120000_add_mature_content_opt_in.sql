@@ -0,0 +1,3 @@
+-- Users must opt in before ratings beyond the server default are shown
+ALTER TABLE user_preferences
+    ADD COLUMN mature_content_opted_in_at TIMESTAMPTZ;
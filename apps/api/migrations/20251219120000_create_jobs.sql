@@ -0,0 +1,11 @@
+-- Schedule and last outcome of each periodic background job
+CREATE TABLE jobs (
+    name VARCHAR(64) PRIMARY KEY,
+    interval_secs BIGINT NOT NULL,
+    next_run_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_started_at TIMESTAMPTZ,
+    last_finished_at TIMESTAMPTZ,
+    last_status VARCHAR(16),
+    last_error TEXT,
+    run_count BIGINT NOT NULL DEFAULT 0
+);
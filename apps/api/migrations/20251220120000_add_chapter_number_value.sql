@@ -0,0 +1,7 @@
+-- Numeric value of a chapter number, NULL for oneshots and non-numeric labels
+CREATE FUNCTION chapter_number_value(chapter_number TEXT) RETURNS NUMERIC
+LANGUAGE SQL IMMUTABLE AS $$
+    SELECT CASE
+        WHEN trim(chapter_number) ~ '^[0-9]+(\.[0-9]+)?$' THEN trim(chapter_number)::numeric
+    END
+$$;
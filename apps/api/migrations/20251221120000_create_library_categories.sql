@@ -0,0 +1,21 @@
+-- User-defined library categories (shelves) and their bookmarks
+CREATE TABLE library_categories (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name VARCHAR(64) NOT NULL,
+    position INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (user_id, name)
+);
+
+CREATE INDEX idx_library_categories_user_position ON library_categories(user_id, position);
+
+CREATE TABLE bookmark_categories (
+    bookmark_id UUID NOT NULL REFERENCES user_bookmarks(id) ON DELETE CASCADE,
+    category_id UUID NOT NULL REFERENCES library_categories(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (bookmark_id, category_id)
+);
+
+CREATE INDEX idx_bookmark_categories_category ON bookmark_categories(category_id);
@@ -0,0 +1,21 @@
+-- Per-user reading status and rating for a manga
+CREATE TYPE reading_status AS ENUM (
+    'reading', 'completed', 'on_hold', 'dropped', 'plan_to_read', 'rereading'
+);
+
+CREATE TABLE user_manga_status (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    source VARCHAR(32) NOT NULL DEFAULT 'mangadex',
+    manga_mangadex_id TEXT NOT NULL,
+    status reading_status NOT NULL,
+    score SMALLINT CHECK (score BETWEEN 1 AND 10),
+    started_on DATE,
+    finished_on DATE,
+    notes TEXT,
+    reread_count INTEGER NOT NULL DEFAULT 0 CHECK (reread_count >= 0),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, source, manga_mangadex_id)
+);
+
+CREATE INDEX idx_user_manga_status_user_status ON user_manga_status(user_id, status);
@@ -0,0 +1,46 @@
+-- Linked tracker accounts (AniList, MyAnimeList)
+CREATE TABLE tracker_accounts (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    tracker VARCHAR(32) NOT NULL,
+    access_token TEXT NOT NULL,
+    refresh_token TEXT,
+    expires_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, tracker)
+);
+
+-- Pending OAuth authorizations, consumed by the callback
+CREATE TABLE tracker_oauth_states (
+    state TEXT PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    tracker VARCHAR(32) NOT NULL,
+    code_verifier TEXT NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL
+);
+
+-- Tracker IDs taken from the source's links; NULL when the source has none
+CREATE TABLE tracker_mappings (
+    source VARCHAR(32) NOT NULL,
+    manga_mangadex_id TEXT NOT NULL,
+    tracker VARCHAR(32) NOT NULL,
+    remote_id TEXT,
+    resolved_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (source, manga_mangadex_id, tracker)
+);
+
+-- Manga whose progress still has to be pushed to a tracker
+CREATE TABLE tracker_sync_queue (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    tracker VARCHAR(32) NOT NULL,
+    source VARCHAR(32) NOT NULL,
+    manga_mangadex_id TEXT NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    failed BOOLEAN NOT NULL DEFAULT FALSE,
+    last_error TEXT,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, tracker, source, manga_mangadex_id)
+);
+
+CREATE INDEX idx_tracker_sync_queue_due ON tracker_sync_queue(next_attempt_at) WHERE NOT failed;
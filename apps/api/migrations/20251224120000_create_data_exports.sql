@@ -0,0 +1,24 @@
+-- Account data exports, built in the background and downloaded by token
+CREATE TYPE export_format AS ENUM ('json', 'tachiyomi');
+CREATE TYPE export_status AS ENUM ('pending', 'ready', 'failed');
+
+CREATE TABLE data_exports (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    format export_format NOT NULL,
+    status export_status NOT NULL DEFAULT 'pending',
+    -- Secret part of the download link
+    download_token TEXT NOT NULL UNIQUE,
+    archive BYTEA,
+    size_bytes BIGINT,
+    error TEXT,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    -- A builder holds a pending export until then; after that it's retried
+    locked_until TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    completed_at TIMESTAMPTZ,
+    expires_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_data_exports_user ON data_exports(user_id, created_at DESC);
+CREATE INDEX idx_data_exports_pending ON data_exports(created_at) WHERE status = 'pending';
@@ -0,0 +1,5 @@
+-- Accounts scheduled for deletion; logging in before purge_after restores them
+ALTER TABLE users ADD COLUMN deleted_at TIMESTAMPTZ;
+ALTER TABLE users ADD COLUMN purge_after TIMESTAMPTZ;
+
+CREATE INDEX idx_users_purge_after ON users(purge_after) WHERE purge_after IS NOT NULL;
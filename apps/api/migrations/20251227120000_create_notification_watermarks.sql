@@ -0,0 +1,21 @@
+-- Newest chapter each bookmarked manga has been notified up to, per language.
+-- Kept apart from chapter_cache, which any request may refresh between polls.
+CREATE TABLE notification_watermarks (
+    source VARCHAR(32) NOT NULL,
+    manga_mangadex_id TEXT NOT NULL,
+    language TEXT NOT NULL,
+    last_published_at TIMESTAMPTZ,
+    polled_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (source, manga_mangadex_id, language)
+);
+
+-- Carry on from what the cache held so the upgrade doesn't re-notify
+INSERT INTO notification_watermarks (source, manga_mangadex_id, language, last_published_at)
+SELECT c.source, c.manga_mangadex_id, c.language, MAX(c.published_at)
+FROM chapter_cache c
+WHERE EXISTS (
+    SELECT 1 FROM user_bookmarks b
+    WHERE b.source = c.source AND b.manga_mangadex_id = c.manga_mangadex_id
+)
+GROUP BY c.source, c.manga_mangadex_id, c.language
+HAVING MAX(c.published_at) IS NOT NULL;
@@ -0,0 +1,21 @@
+-- Library imports, matched and applied in the background and polled by ID
+CREATE TYPE import_format AS ENUM ('mangadex', 'tachiyomi', 'myanimelist');
+CREATE TYPE import_status AS ENUM ('pending', 'done', 'failed');
+
+CREATE TABLE library_imports (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    format import_format NOT NULL,
+    status import_status NOT NULL DEFAULT 'pending',
+    total INTEGER NOT NULL DEFAULT 0,
+    processed INTEGER NOT NULL DEFAULT 0,
+    report JSONB,
+    error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    -- Bumped as entries are processed; a pending import that stops moving
+    -- lost its worker
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    completed_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_library_imports_user ON library_imports(user_id, created_at DESC);
@@ -0,0 +1,4 @@
+-- Download tokens are stored hashed like refresh and email tokens; links
+-- already handed out keep working since requests are hashed the same way
+UPDATE data_exports SET download_token = encode(sha256(convert_to(download_token, 'UTF8')), 'hex');
+ALTER TABLE data_exports RENAME COLUMN download_token TO download_token_hash;
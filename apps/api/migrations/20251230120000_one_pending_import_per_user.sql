@@ -0,0 +1,16 @@
+-- At most one running import per user, so concurrent requests can't both
+-- start one. Older duplicates are failed first
+UPDATE library_imports
+SET status = 'failed', error = 'Import was interrupted, please try again',
+    completed_at = NOW()
+WHERE status = 'pending'
+    AND id NOT IN (
+        SELECT DISTINCT ON (user_id) id
+        FROM library_imports
+        WHERE status = 'pending'
+        ORDER BY user_id, created_at DESC
+    );
+
+CREATE UNIQUE INDEX idx_library_imports_one_pending
+    ON library_imports(user_id)
+    WHERE status = 'pending';
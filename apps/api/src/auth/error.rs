@@ -28,9 +28,15 @@ pub enum AuthError {
     #[error("Invalid authorization header format")]
     InvalidAuthHeader,
 
+    #[error("Admin access required")]
+    Forbidden,
+
     #[error("Rate limit exceeded")]
     RateLimitExceeded,
 
+    #[error("Email delivery is not configured")]
+    MailUnavailable,
+
     #[error("Validation error: {0}")]
     ValidationError(String),
 
@@ -49,7 +55,9 @@ impl IntoResponse for AuthError {
             AuthError::TokenRevoked => (StatusCode::UNAUTHORIZED, "TOKEN_REVOKED"),
             AuthError::MissingAuthHeader => (StatusCode::UNAUTHORIZED, "MISSING_AUTH"),
             AuthError::InvalidAuthHeader => (StatusCode::UNAUTHORIZED, "INVALID_AUTH"),
+            AuthError::Forbidden => (StatusCode::FORBIDDEN, "FORBIDDEN"),
             AuthError::RateLimitExceeded => (StatusCode::TOO_MANY_REQUESTS, "RATE_LIMITED"),
+            AuthError::MailUnavailable => (StatusCode::SERVICE_UNAVAILABLE, "MAIL_UNAVAILABLE"),
             AuthError::ValidationError(_) => (StatusCode::BAD_REQUEST, "VALIDATION_ERROR"),
             AuthError::Internal(_) => (StatusCode::INTERNAL_SERVER_ERROR, "INTERNAL_ERROR"),
         };
@@ -7,6 +7,8 @@ use uuid::Uuid;
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AccessTokenClaims {
     pub sub: Uuid,
+    /// `jti` of the refresh token issued alongside, identifying the session.
+    pub sid: Uuid,
     pub email: String,
     pub role: String,
     pub exp: i64,
@@ -31,6 +33,7 @@ pub struct TokenPair {
 
 pub fn issue_access_token(
     user_id: Uuid,
+    session_id: Uuid,
     email: &str,
     role: &str,
     secret: &str,
@@ -39,6 +42,7 @@ pub fn issue_access_token(
     let now = Utc::now();
     let claims = AccessTokenClaims {
         sub: user_id,
+        sid: session_id,
         email: email.to_string(),
         role: role.to_string(),
         exp: (now + Duration::seconds(ttl_secs)).timestamp(),
@@ -109,9 +113,49 @@ pub fn verify_refresh_token(token: &str, secret: &str) -> Result<RefreshTokenCla
     })
 }
 
-pub fn hash_refresh_token(token: &str) -> String {
+/// SHA-256 of a bearer token, stored in its place so a database leak
+/// doesn't hand out working tokens.
+pub fn hash_token(token: &str) -> String {
     use sha2::{Digest, Sha256};
     let mut hasher = Sha256::new();
     hasher.update(token.as_bytes());
     format!("{:x}", hasher.finalize())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "test-secret";
+
+    #[test]
+    fn access_tokens_carry_their_session() {
+        let user_id = Uuid::new_v4();
+        let session_id = Uuid::new_v4();
+        let token =
+            issue_access_token(user_id, session_id, "a@example.com", "user", SECRET, 60).unwrap();
+
+        let claims = verify_access_token(&token, SECRET).unwrap();
+        assert_eq!(claims.sub, user_id);
+        assert_eq!(claims.sid, session_id);
+        assert_eq!(claims.token_type, "access");
+    }
+
+    #[test]
+    fn rejects_tokens_signed_with_another_secret() {
+        let token = issue_access_token(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            "a@example.com",
+            "user",
+            SECRET,
+            60,
+        )
+        .unwrap();
+
+        assert!(matches!(
+            verify_access_token(&token, "other-secret"),
+            Err(AuthError::TokenInvalid)
+        ));
+    }
+}
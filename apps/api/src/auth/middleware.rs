@@ -1,5 +1,5 @@
 use axum::{
-    extract::{FromRef, FromRequestParts},
+    extract::{FromRef, FromRequestParts, OptionalFromRequestParts},
     http::request::Parts,
 };
 use uuid::Uuid;
@@ -9,6 +9,8 @@ use crate::AppState;
 #[derive(Debug, Clone)]
 pub struct CurrentUser {
     pub id: Uuid,
+    /// The session's refresh token ID.
+    pub session_id: Uuid,
     pub email: String,
     pub role: String,
 }
@@ -42,10 +44,71 @@ where
             return Err(AuthError::TokenInvalid);
         }
 
+        // Deleting an account signs it out, but access tokens outlive that
+        let active: bool = sqlx::query_scalar(
+            "SELECT EXISTS (SELECT 1 FROM users WHERE id = $1 AND deleted_at IS NULL)",
+        )
+        .bind(claims.sub)
+        .fetch_one(&app_state.db_pool)
+        .await
+        .map_err(|e| AuthError::Internal(anyhow::anyhow!("Database error: {}", e)))?;
+        if !active {
+            return Err(AuthError::TokenRevoked);
+        }
+
         Ok(CurrentUser {
             id: claims.sub,
+            session_id: claims.sid,
             email: claims.email,
             role: claims.role,
         })
     }
 }
+
+/// Lets public routes take `Option<CurrentUser>`: requests without an
+/// `Authorization` header are anonymous, but a bad token is still rejected.
+impl<S> OptionalFromRequestParts<S> for CurrentUser
+where
+    S: Send + Sync,
+    AppState: FromRef<S>,
+{
+    type Rejection = AuthError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &S,
+    ) -> Result<Option<Self>, Self::Rejection> {
+        if !parts.headers.contains_key("Authorization") {
+            return Ok(None);
+        }
+
+        <CurrentUser as FromRequestParts<S>>::from_request_parts(parts, state)
+            .await
+            .map(Some)
+    }
+}
+
+/// A [`CurrentUser`] whose role is `admin`; anyone else gets a 403.
+#[derive(Debug, Clone)]
+pub struct AdminUser(pub CurrentUser);
+
+impl<S> FromRequestParts<S> for AdminUser
+where
+    S: Send + Sync,
+    AppState: FromRef<S>,
+{
+    type Rejection = AuthError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        let user = <CurrentUser as FromRequestParts<S>>::from_request_parts(parts, state).await?;
+
+        if user.role != "admin" {
+            return Err(AuthError::Forbidden);
+        }
+
+        Ok(AdminUser(user))
+    }
+}
@@ -5,5 +5,5 @@ pub mod password;
 
 pub use error::AuthError;
 pub use jwt::{AccessTokenClaims, RefreshTokenClaims, TokenPair};
-pub use middleware::CurrentUser;
+pub use middleware::{AdminUser, CurrentUser};
 pub use password::{hash_password, verify_password};
@@ -0,0 +1,34 @@
+//! Stand-in for the AniList and MyAnimeList APIs, for trying tracker sync
+//! locally without real accounts.
+//!
+//! Run with `cargo run --bin mock_tracker` and point the API at it:
+//!
+//! ```text
+//! ANILIST_CLIENT_ID=mock ANILIST_CLIENT_SECRET=mock ANILIST_REDIRECT_URI=http://localhost:3000/trackers/anilist
+//! ANILIST_AUTH_URL=http://127.0.0.1:4100/oauth/authorize
+//! ANILIST_TOKEN_URL=http://127.0.0.1:4100/oauth/token
+//! ANILIST_API_URL=http://127.0.0.1:4100/graphql
+//! MAL_CLIENT_ID=mock MAL_CLIENT_SECRET=mock MAL_REDIRECT_URI=http://localhost:3000/trackers/myanimelist
+//! MAL_AUTH_URL=http://127.0.0.1:4100/oauth/authorize
+//! MAL_TOKEN_URL=http://127.0.0.1:4100/oauth/token
+//! MAL_API_URL=http://127.0.0.1:4100/v2
+//! ```
+//!
+//! See [`api::trackers::mock`] for what it records and how to inject failures.
+
+use std::net::SocketAddr;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    tracing_subscriber::fmt().init();
+
+    let addr: SocketAddr = std::env::var("MOCK_TRACKER_ADDR")
+        .unwrap_or_else(|_| "127.0.0.1:4100".to_string())
+        .parse()?;
+
+    tracing::info!("Mock tracker listening on {}", addr);
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    axum::serve(listener, api::trackers::mock::router()).await?;
+
+    Ok(())
+}
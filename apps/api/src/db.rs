@@ -34,4 +34,22 @@ pub async fn create_pool(config: &AppConfig) -> Result<PgPool> {
         .context("Postgres probe failed")?;
 
     Ok(pool)
-}
\ No newline at end of file
+}
+
+/// Migrated pool for tests that need Postgres, from `DATABASE_URL`. Those
+/// tests skip themselves when it isn't set.
+#[cfg(test)]
+pub async fn test_pool() -> Option<PgPool> {
+    let url = std::env::var("DATABASE_URL").ok()?;
+    let pool = PgPoolOptions::new()
+        .max_connections(5)
+        .connect(&url)
+        .await
+        .expect("DATABASE_URL is set but unreachable");
+    sqlx::migrate!()
+        .run(&pool)
+        .await
+        .expect("Failed to migrate the test database");
+
+    Some(pool)
+}
@@ -1,6 +1,7 @@
 use axum::{
+    extract::DefaultBodyLimit,
     http::{header, Method},
-    routing::{delete, get, post, put},
+    routing::{delete, get, patch, post, put},
     Router,
 };
 use tower_http::{
@@ -16,14 +17,37 @@ pub fn build_router(config: &AppConfig, state: AppState) -> Router {
     // CORS configuration for frontend (localhost:3000)
     let cors = CorsLayer::new()
         .allow_origin("http://localhost:3000".parse::<axum::http::HeaderValue>().unwrap())
-        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE, Method::OPTIONS])
+        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::PATCH, Method::DELETE, Method::OPTIONS])
         .allow_headers([header::CONTENT_TYPE, header::AUTHORIZATION, header::ACCEPT])
         .allow_credentials(true);
 
     Router::new()
         .route("/health", get(routes::health::ping))
+        .route("/metrics/cache", get(routes::metrics::cache_metrics))
         .route("/users/{id}", get(routes::get_user_by_id::get_user_by_id))
-        .route("/users/me", get(routes::users::me::get_me))
+        .route(
+            "/users/me",
+            get(routes::users::me::get_me)
+                .patch(routes::users::me::update_me)
+                .delete(routes::users::me::delete_me),
+        )
+        .route(
+            "/users/me/password",
+            post(routes::users::me::change_password),
+        )
+        .route(
+            "/users/me/email",
+            post(routes::users::me::request_email_change),
+        )
+        .route(
+            "/users/me/email/verify",
+            post(routes::users::me::verify_email_change),
+        )
+        .route(
+            "/users/me/preferences",
+            get(routes::users::preferences::get_preferences)
+                .put(routes::users::preferences::update_preferences),
+        )
         .nest("/auth", routes::auth::auth_routes(&config.auth))
         .nest("/manga", routes::manga::manga_routes())
         .nest("/chapters", routes::chapters::chapter_routes())
@@ -40,10 +64,29 @@ pub fn build_router(config: &AppConfig, state: AppState) -> Router {
             "/users/me/bookmarks/{manga_id}",
             delete(routes::users::bookmarks::remove_bookmark),
         )
+        .route(
+            "/users/me/categories",
+            get(routes::users::categories::get_categories)
+                .post(routes::users::categories::create_category),
+        )
+        .route(
+            "/users/me/categories/{category_id}",
+            patch(routes::users::categories::update_category)
+                .delete(routes::users::categories::delete_category),
+        )
+        .route(
+            "/users/me/categories/{category_id}/manga/{manga_id}",
+            put(routes::users::categories::add_to_category)
+                .delete(routes::users::categories::remove_from_category),
+        )
         .route(
             "/users/me/library",
             get(routes::users::library::get_library),
         )
+        .route(
+            "/users/me/library/unread",
+            get(routes::users::library::get_unread_summary),
+        )
         .route(
             "/users/me/progress",
             get(routes::users::progress::get_all_progress),
@@ -56,6 +99,32 @@ pub fn build_router(config: &AppConfig, state: AppState) -> Router {
             "/users/me/progress/{manga_id}",
             put(routes::users::progress::update_progress),
         )
+        .route(
+            "/users/me/status",
+            get(routes::users::status::get_all_status),
+        )
+        .route(
+            "/users/me/status/{manga_id}",
+            get(routes::users::status::get_status)
+                .put(routes::users::status::update_status)
+                .delete(routes::users::status::remove_status),
+        )
+        .route(
+            "/users/me/trackers",
+            get(routes::users::trackers::get_trackers),
+        )
+        .route(
+            "/users/me/trackers/{tracker}",
+            delete(routes::users::trackers::unlink_tracker),
+        )
+        .route(
+            "/users/me/trackers/{tracker}/authorize",
+            get(routes::users::trackers::authorize_tracker),
+        )
+        .route(
+            "/users/me/trackers/{tracker}/callback",
+            post(routes::users::trackers::link_callback),
+        )
         .route(
             "/users/me/history",
             get(routes::users::history::get_history),
@@ -68,6 +137,33 @@ pub fn build_router(config: &AppConfig, state: AppState) -> Router {
             "/users/me/history/{chapter_id}",
             delete(routes::users::history::remove_from_history),
         )
+        .route(
+            "/users/me/export",
+            get(routes::users::export::request_export),
+        )
+        .route(
+            "/users/me/export/{export_id}",
+            get(routes::users::export::get_export),
+        )
+        .route(
+            "/exports/{token}",
+            get(routes::users::export::download_export),
+        )
+        .route(
+            "/users/me/import",
+            post(routes::users::import::import_library)
+                .layer(DefaultBodyLimit::max(routes::users::import::MAX_UPLOAD_BYTES)),
+        )
+        .route(
+            "/users/me/import/{import_id}",
+            get(routes::users::import::get_import),
+        )
+        .route("/users/me/feed", get(routes::users::feed::get_feed))
+        .route(
+            "/users/me/notifications",
+            get(routes::users::notifications::get_notifications)
+                .post(routes::users::notifications::update_notifications),
+        )
         .with_state(state)
         .layer(cors)
         .layer(TraceLayer::new_for_http())
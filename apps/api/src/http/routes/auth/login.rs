@@ -38,12 +38,12 @@ pub async fn login(
     State(state): State<AppState>,
     Json(req): Json<LoginRequest>,
 ) -> Result<Json<LoginResponse>, AuthError> {
-    // Find user by email
-    let user = sqlx::query_as::<_, (Uuid, String, String, String, String)>(
+    // Find user by email; accounts past their deletion grace period are gone
+    let user = sqlx::query_as::<_, (Uuid, String, String, String, String, bool)>(
         r#"
-        SELECT id, email, username, password, role::text
+        SELECT id, email, username, password, role::text, deleted_at IS NOT NULL
         FROM users
-        WHERE email = $1
+        WHERE email = $1 AND (purge_after IS NULL OR purge_after > NOW())
         "#,
     )
     .bind(&req.email)
@@ -52,17 +52,31 @@ pub async fn login(
     .map_err(|e| AuthError::Internal(anyhow::anyhow!("Database error: {}", e)))?
     .ok_or(AuthError::InvalidCredentials)?;
 
-    let (user_id, email, username, password_hash, role) = user;
+    let (user_id, email, username, password_hash, role, deleted) = user;
 
     // Verify password
     if !verify_password(&req.password, &password_hash)? {
         return Err(AuthError::InvalidCredentials);
     }
 
+    // Logging in during the grace period cancels a pending deletion
+    if deleted {
+        sqlx::query("UPDATE users SET deleted_at = NULL, purge_after = NULL WHERE id = $1")
+            .bind(user_id)
+            .execute(&state.db_pool)
+            .await
+            .map_err(|e| {
+                AuthError::Internal(anyhow::anyhow!("Failed to restore account: {}", e))
+            })?;
+
+        tracing::info!("account {} restored from pending deletion", user_id);
+    }
+
     // Issue tokens
     let refresh_token_id = Uuid::new_v4();
     let access_token = crate::auth::jwt::issue_access_token(
         user_id,
+        refresh_token_id,
         &email,
         &role,
         &state.auth_config.jwt_secret,
@@ -77,7 +91,7 @@ pub async fn login(
     )?;
 
     // Store refresh token hash in DB
-    let token_hash = crate::auth::jwt::hash_refresh_token(&refresh_token);
+    let token_hash = crate::auth::jwt::hash_token(&refresh_token);
     let expires_at = chrono::Utc::now()
         + chrono::Duration::days(state.auth_config.refresh_token_ttl_days);
 
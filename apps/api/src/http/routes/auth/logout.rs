@@ -17,7 +17,7 @@ pub async fn logout(
 ) -> Result<axum::http::StatusCode, AuthError> {
     // If refresh token provided, revoke it specifically
     if let Some(refresh_token) = req.refresh_token {
-        let token_hash = jwt::hash_refresh_token(&refresh_token);
+        let token_hash = jwt::hash_token(&refresh_token);
 
         sqlx::query(
             r#"
@@ -34,7 +34,7 @@ pub async fn refresh(
     }
 
     // Check if token is revoked
-    let token_hash = jwt::hash_refresh_token(&req.refresh_token);
+    let token_hash = jwt::hash_token(&req.refresh_token);
     let token_record = sqlx::query_as::<_, (bool, bool)>(
         r#"
         SELECT 
@@ -94,6 +94,7 @@ pub async fn refresh(
     let new_refresh_token_id = Uuid::new_v4();
     let access_token = jwt::issue_access_token(
         user_id,
+        new_refresh_token_id,
         &email,
         &role,
         &state.auth_config.jwt_secret,
@@ -108,7 +109,7 @@ pub async fn refresh(
     )?;
 
     // Store new refresh token
-    let new_token_hash = jwt::hash_refresh_token(&refresh_token);
+    let new_token_hash = jwt::hash_token(&refresh_token);
     let expires_at = chrono::Utc::now()
         + chrono::Duration::days(state.auth_config.refresh_token_ttl_days);
 
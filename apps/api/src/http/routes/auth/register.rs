@@ -32,7 +32,7 @@ pub struct RegisterRequest {
     pub password: String,
 }
 
-fn validate_username_format(username: &str) -> Result<(), validator::ValidationError> {
+pub(crate) fn validate_username_format(username: &str) -> Result<(), validator::ValidationError> {
     if !USERNAME_REGEX.is_match(username) {
         return Err(validator::ValidationError::new(
             "Username can only contain letters, numbers, and underscores",
@@ -41,7 +41,7 @@ fn validate_username_format(username: &str) -> Result<(), validator::ValidationE
     Ok(())
 }
 
-fn validate_password_strength(password: &str) -> Result<(), validator::ValidationError> {
+pub(crate) fn validate_password_strength(password: &str) -> Result<(), validator::ValidationError> {
     let has_uppercase = password.chars().any(|c| c.is_uppercase());
     let has_lowercase = password.chars().any(|c| c.is_lowercase());
     let has_digit = password.chars().any(|c| c.is_ascii_digit());
@@ -134,6 +134,7 @@ pub async fn register(
     let refresh_token_id = Uuid::new_v4();
     let access_token = crate::auth::jwt::issue_access_token(
         user_id,
+        refresh_token_id,
         &req.email,
         "user",
         &state.auth_config.jwt_secret,
@@ -148,7 +149,7 @@ pub async fn register(
     )?;
 
     // Store refresh token hash in DB
-    let token_hash = crate::auth::jwt::hash_refresh_token(&refresh_token);
+    let token_hash = crate::auth::jwt::hash_token(&refresh_token);
     let expires_at = chrono::Utc::now()
         + chrono::Duration::days(state.auth_config.refresh_token_ttl_days);
 
@@ -4,7 +4,11 @@ use axum::{
 };
 use serde::Serialize;
 
+use crate::auth::CurrentUser;
+use crate::http::routes::manga::get_manga::visible_manga;
+use crate::manga::Chapter;
 use crate::mangadex::{cache::get_chapters_with_cache, MangaDexError};
+use crate::users::UserPreferences;
 use crate::AppState;
 
 #[derive(Serialize)]
@@ -12,54 +16,289 @@ pub struct NavigationResponse {
     pub prev_chapter_id: Option<String>,
     pub next_chapter_id: Option<String>,
     pub current_chapter_id: String,
+    /// The previous chapter belongs to a different volume.
+    pub prev_volume_boundary: bool,
+    /// The next chapter belongs to a different volume.
+    pub next_volume_boundary: bool,
+    /// Whole chapter numbers missing between the previous chapter and this one.
+    pub gap_before: Option<ChapterGap>,
+    /// Whole chapter numbers missing between this chapter and the next one.
+    pub gap_after: Option<ChapterGap>,
+}
+
+#[derive(Serialize)]
+pub struct ChapterGap {
+    pub first_missing: u32,
+    pub last_missing: u32,
+    pub count: u32,
+}
+
+/// All scanlations of one chapter number, in list order.
+struct ChapterGroup<'a> {
+    number: f64,
+    chapters: Vec<&'a Chapter>,
 }
 
 pub async fn get_chapter_navigation(
     Path(chapter_id): Path<String>,
     State(state): State<AppState>,
+    user: Option<CurrentUser>,
 ) -> Result<Json<NavigationResponse>, MangaDexError> {
-    let chapter = state.mangadex_client.get_chapter(&chapter_id).await?;
+    let (source, local_chapter_id) = state.sources.resolve(&chapter_id);
 
-    let manga_id = chapter
-        .relationships
-        .iter()
-        .find(|r| r.rel_type == "manga")
-        .map(|r| r.id.clone())
-        .ok_or_else(|| {
-            MangaDexError::ApiError("Chapter does not have a manga relationship".to_string())
-        })?;
+    let chapter = source.get_chapter(&local_chapter_id).await?;
+
+    let preferences = UserPreferences::load_optional(&state.db_pool, user.map(|u| u.id))
+        .await
+        .map_err(|e| MangaDexError::Internal(e.into()))?;
+    visible_manga(&state, &preferences, &source, &chapter.manga_mangadex_id).await?;
 
     let chapters = get_chapters_with_cache(
-        &manga_id,
-        "en",
+        &chapter.manga_mangadex_id,
+        &chapter.language,
         &state.db_pool,
-        &state.mangadex_client,
+        &state.hot_cache,
+        &source,
         &state.mangadex_config,
     )
-    .await?;
+    .await?
+    .into_inner();
 
-    let current_index = chapters
+    let current = chapters
         .iter()
-        .position(|c| c.mangadex_id == chapter_id)
+        .find(|c| c.mangadex_id == local_chapter_id)
         .ok_or_else(|| {
             MangaDexError::ApiError("Chapter not found in manga chapter list".to_string())
         })?;
 
-    let prev_chapter_id = if current_index > 0 {
-        Some(chapters[current_index - 1].mangadex_id.clone())
-    } else {
-        None
+    let (prev, next) = match chapter_number(current) {
+        Some(number) => {
+            let groups = group_by_number(&chapters);
+            let position = groups.iter().position(|g| g.number == number);
+            let preferred = &preferences.scanlation_groups;
+
+            match position {
+                Some(i) => (
+                    i.checked_sub(1)
+                        .map(|p| pick_scanlation(&groups[p], current, preferred)),
+                    groups
+                        .get(i + 1)
+                        .map(|g| pick_scanlation(g, current, preferred)),
+                ),
+                None => (None, None),
+            }
+        }
+        // Unnumbered chapters (oneshots, extras) keep their list neighbours
+        None => {
+            let i = chapters
+                .iter()
+                .position(|c| c.mangadex_id == local_chapter_id)
+                .unwrap_or_default();
+            (
+                i.checked_sub(1).and_then(|p| chapters.get(p)),
+                chapters.get(i + 1),
+            )
+        }
     };
 
-    let next_chapter_id = if current_index < chapters.len() - 1 {
-        Some(chapters[current_index + 1].mangadex_id.clone())
-    } else {
-        None
+    let volume_boundary = |other: Option<&Chapter>| {
+        other.is_some_and(|other| normalize_volume(other) != normalize_volume(current))
     };
 
     Ok(Json(NavigationResponse {
-        prev_chapter_id,
-        next_chapter_id,
+        prev_volume_boundary: volume_boundary(prev),
+        next_volume_boundary: volume_boundary(next),
+        gap_before: prev.and_then(|prev| gap_between(prev, current)),
+        gap_after: next.and_then(|next| gap_between(current, next)),
+        prev_chapter_id: prev.map(|c| state.sources.qualify(source.id(), &c.mangadex_id)),
+        next_chapter_id: next.map(|c| state.sources.qualify(source.id(), &c.mangadex_id)),
         current_chapter_id: chapter_id,
     }))
 }
+
+fn chapter_number(chapter: &Chapter) -> Option<f64> {
+    chapter
+        .chapter_number
+        .as_deref()
+        .and_then(|n| n.trim().parse::<f64>().ok())
+        .filter(|n| n.is_finite())
+}
+
+fn normalize_volume(chapter: &Chapter) -> Option<&str> {
+    chapter
+        .volume
+        .as_deref()
+        .map(str::trim)
+        .filter(|v| !v.is_empty() && *v != "none")
+}
+
+/// Groups numbered chapters by chapter number, in ascending order.
+fn group_by_number(chapters: &[Chapter]) -> Vec<ChapterGroup<'_>> {
+    let mut groups: Vec<ChapterGroup<'_>> = Vec::new();
+
+    for chapter in chapters {
+        let Some(number) = chapter_number(chapter) else {
+            continue;
+        };
+
+        match groups.iter_mut().find(|g| g.number == number) {
+            Some(group) => group.chapters.push(chapter),
+            None => groups.push(ChapterGroup {
+                number,
+                chapters: vec![chapter],
+            }),
+        }
+    }
+
+    groups.sort_by(|a, b| a.number.total_cmp(&b.number));
+    groups
+}
+
+/// Picks which scanlation of a chapter to move to: the group the user is
+/// reading now, then their preferred groups in order, then the first one.
+fn pick_scanlation<'a>(
+    group: &ChapterGroup<'a>,
+    current: &Chapter,
+    preferred_groups: &[String],
+) -> &'a Chapter {
+    let by_group = |group_id: &str| {
+        group
+            .chapters
+            .iter()
+            .find(|c| c.scanlation_group_id.as_deref() == Some(group_id))
+            .copied()
+    };
+
+    current
+        .scanlation_group_id
+        .as_deref()
+        .and_then(by_group)
+        .or_else(|| preferred_groups.iter().find_map(|id| by_group(id)))
+        .unwrap_or(group.chapters[0])
+}
+
+/// Whole chapter numbers strictly between two chapters, if any.
+fn gap_between(earlier: &Chapter, later: &Chapter) -> Option<ChapterGap> {
+    let (from, to) = (chapter_number(earlier)?, chapter_number(later)?);
+
+    let first_missing = from.floor() as i64 + 1;
+    let last_missing = to.ceil() as i64 - 1;
+    if first_missing > last_missing || first_missing < 0 {
+        return None;
+    }
+
+    Some(ChapterGap {
+        first_missing: first_missing as u32,
+        last_missing: last_missing as u32,
+        count: (last_missing - first_missing + 1) as u32,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chapter(
+        id: &str,
+        number: Option<&str>,
+        volume: Option<&str>,
+        group: Option<&str>,
+    ) -> Chapter {
+        Chapter {
+            source: "mangadex".to_string(),
+            mangadex_id: id.to_string(),
+            manga_mangadex_id: "manga".to_string(),
+            chapter_number: number.map(str::to_string),
+            volume: volume.map(str::to_string),
+            title: None,
+            language: "en".to_string(),
+            scanlation_group_id: group.map(str::to_string),
+            scanlation_group_name: None,
+            page_count: 20,
+            published_at: None,
+        }
+    }
+
+    #[test]
+    fn groups_numbered_chapters_in_order() {
+        let chapters = vec![
+            chapter("b", Some("2"), None, Some("g1")),
+            chapter("extra", None, None, None),
+            chapter("a", Some("1"), None, Some("g1")),
+            chapter("b2", Some("2.0"), None, Some("g2")),
+        ];
+
+        let groups = group_by_number(&chapters);
+        let ids: Vec<Vec<&str>> = groups
+            .iter()
+            .map(|g| g.chapters.iter().map(|c| c.mangadex_id.as_str()).collect())
+            .collect();
+        assert_eq!(ids, vec![vec!["a"], vec!["b", "b2"]]);
+    }
+
+    #[test]
+    fn pick_scanlation_prefers_current_then_preferred_then_first() {
+        let chapters = vec![
+            chapter("g1", Some("2"), None, Some("group-1")),
+            chapter("g2", Some("2"), None, Some("group-2")),
+            chapter("g3", Some("2"), None, Some("group-3")),
+        ];
+        let groups = group_by_number(&chapters);
+        let group = &groups[0];
+
+        let current = chapter("cur", Some("1"), None, Some("group-3"));
+        let preferred = vec!["group-2".to_string()];
+        assert_eq!(
+            pick_scanlation(group, &current, &preferred).mangadex_id,
+            "g3"
+        );
+
+        let current = chapter("cur", Some("1"), None, Some("group-9"));
+        assert_eq!(
+            pick_scanlation(group, &current, &preferred).mangadex_id,
+            "g2"
+        );
+
+        let current = chapter("cur", Some("1"), None, None);
+        assert_eq!(pick_scanlation(group, &current, &[]).mangadex_id, "g1");
+    }
+
+    #[test]
+    fn gap_between_counts_missing_whole_chapters() {
+        let gap = gap_between(
+            &chapter("a", Some("3"), None, None),
+            &chapter("b", Some("7"), None, None),
+        )
+        .unwrap();
+        assert_eq!((gap.first_missing, gap.last_missing, gap.count), (4, 6, 3));
+
+        let gap = gap_between(
+            &chapter("a", Some("3.5"), None, None),
+            &chapter("b", Some("5.5"), None, None),
+        )
+        .unwrap();
+        assert_eq!((gap.first_missing, gap.last_missing, gap.count), (4, 5, 2));
+    }
+
+    #[test]
+    fn gap_between_ignores_adjacent_and_unnumbered_chapters() {
+        let one = chapter("a", Some("1"), None, None);
+        assert!(gap_between(&one, &chapter("b", Some("2"), None, None)).is_none());
+        assert!(gap_between(&one, &chapter("b", Some("1.5"), None, None)).is_none());
+        assert!(gap_between(&one, &chapter("b", None, None, None)).is_none());
+        assert!(gap_between(&one, &chapter("b", Some("NaN"), None, None)).is_none());
+    }
+
+    #[test]
+    fn normalize_volume_treats_none_as_missing() {
+        assert_eq!(
+            normalize_volume(&chapter("a", None, Some(" 3 "), None)),
+            Some("3")
+        );
+        assert_eq!(
+            normalize_volume(&chapter("a", None, Some("none"), None)),
+            None
+        );
+        assert_eq!(normalize_volume(&chapter("a", None, Some(""), None)), None);
+    }
+}
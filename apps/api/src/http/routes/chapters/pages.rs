@@ -1,97 +1,79 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     Json,
 };
+use serde::Deserialize;
 
-use crate::mangadex::{types::*, MangaDexError};
+use crate::auth::CurrentUser;
+use crate::http::routes::manga::get_manga::visible_manga;
+use crate::mangadex::{cache::get_chapter_manga_id, types::*, MangaDexError};
+use crate::users::UserPreferences;
 use crate::AppState;
 
+#[derive(Debug, Deserialize)]
+pub struct PagesQuery {
+    /// Overrides the user's data-saver preference for this request.
+    #[serde(default)]
+    pub data_saver: Option<bool>,
+}
+
 #[derive(serde::Serialize)]
 pub struct ChapterPagesResponse {
     pub chapter_id: String,
     pub base_url: String,
     pub hash: String,
+    /// Whether `pages[].url` points at the compressed data-saver images.
+    pub data_saver: bool,
     pub pages: Vec<PageInfo>,
 }
 
 pub async fn get_chapter_pages(
     Path(chapter_id): Path<String>,
+    Query(params): Query<PagesQuery>,
     State(state): State<AppState>,
+    user: Option<CurrentUser>,
 ) -> Result<Json<ChapterPagesResponse>, MangaDexError> {
-    let chapter = state.mangadex_client.get_chapter(&chapter_id).await?;
-
-    if chapter.attributes.external_url.is_some() {
-        return Err(MangaDexError::ApiError(
-            "This chapter is hosted externally and does not have pages available".to_string(),
-        ));
-    }
-
-    // Check if chapter has pages
-    if chapter.attributes.pages == 0 {
-        return Err(MangaDexError::ApiError(
-            "This chapter has no pages available".to_string(),
-        ));
-    }
+    let preferences = UserPreferences::load_optional(&state.db_pool, user.map(|u| u.id))
+        .await
+        .map_err(|e| MangaDexError::Internal(e.into()))?;
+    let data_saver = params.data_saver.unwrap_or(preferences.data_saver);
 
-    let response = state.mangadex_client.get_chapter_pages(&chapter_id).await?;
+    let (source, local_chapter_id) = state.sources.resolve(&chapter_id);
 
-    if response.chapter.data.is_empty() && response.chapter.data_saver.is_empty() {
-        return Err(MangaDexError::ApiError(format!(
-            "Chapter has {} pages but no image data available",
-            chapter.attributes.pages
-        )));
-    }
+    let manga_id = get_chapter_manga_id(&local_chapter_id, &state.db_pool, &source).await?;
+    visible_manga(&state, &preferences, &source, &manga_id).await?;
 
-    let page_filenames = if !response.chapter.data.is_empty() {
-        &response.chapter.data
-    } else {
-        &response.chapter.data_saver
+    let pages = match state
+        .page_store
+        .stored_chapter(source.id(), &local_chapter_id)
+        .await
+    {
+        Some(pages) => pages,
+        None => {
+            let pages = source.get_chapter_pages(&local_chapter_id).await?;
+            if let Err(e) = state
+                .page_store
+                .save_manifest(source.id(), &local_chapter_id, &pages)
+                .await
+            {
+                tracing::warn!("Failed to store page manifest for {}: {}", chapter_id, e);
+            }
+            pages
+        }
     };
 
-    let pages: Vec<PageInfo> = page_filenames
-        .iter()
-        .enumerate()
-        .map(|(idx, filename)| {
-            let page_number = idx + 1;
-
-            let (url, url_data_saver) = if !response.chapter.data.is_empty() {
-                let url = format!(
-                    "{}/data/{}/{}",
-                    response.base_url, response.chapter.hash, filename
-                );
-                let url_data_saver = response
-                    .chapter
-                    .data_saver
-                    .get(idx)
-                    .map(|ds_filename| {
-                        format!(
-                            "{}/data-saver/{}/{}",
-                            response.base_url, response.chapter.hash, ds_filename
-                        )
-                    })
-                    .unwrap_or_else(|| url.clone());
-                (url, url_data_saver)
-            } else {
-                let url_data_saver = format!(
-                    "{}/data-saver/{}/{}",
-                    response.base_url, response.chapter.hash, filename
-                );
-                (url_data_saver.clone(), url_data_saver)
-            };
-
-            PageInfo {
-                page_number: page_number as u32,
-                filename: filename.clone(),
-                url,
-                url_data_saver,
-            }
-        })
-        .collect();
+    let mut page_infos = pages.pages;
+    if data_saver {
+        for page in &mut page_infos {
+            page.url = page.url_data_saver.clone();
+        }
+    }
 
     Ok(Json(ChapterPagesResponse {
         chapter_id,
-        base_url: response.base_url,
-        hash: response.chapter.hash,
-        pages,
+        base_url: pages.base_url,
+        hash: pages.hash,
+        data_saver,
+        pages: page_infos,
     }))
 }
@@ -31,7 +31,7 @@ pub async fn get_user_by_id(
             created_at::text AS created_at,
             updated_at::text AS updated_at
         FROM users
-        WHERE id = $1
+        WHERE id = $1 AND deleted_at IS NULL
         "#,
     )
     .bind(user_id)
@@ -1,36 +1,73 @@
 use axum::{
     extract::{Path, Query, State},
+    response::IntoResponse,
     Json,
 };
-use serde::Deserialize;
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer};
 
+use super::get_manga::visible_manga;
+use crate::auth::CurrentUser;
 use crate::mangadex::{cache::get_chapters_with_cache, MangaDexError};
+use crate::users::{is_language_code, UserPreferences};
 use crate::AppState;
 
 #[derive(Debug, Deserialize)]
 pub struct ChaptersQuery {
-    #[serde(default = "default_lang")]
-    pub lang: String,
+    /// Explicit language. When omitted, the user's preferred languages are
+    /// tried in order and the first one with chapters wins.
+    #[serde(default, deserialize_with = "language_code")]
+    pub lang: Option<String>,
 }
 
-fn default_lang() -> String {
-    "en".to_string()
+/// Rejects the query (400) unless `lang` is a language code.
+fn language_code<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Option::<String>::deserialize(deserializer)? {
+        Some(lang) if !is_language_code(&lang) => Err(D::Error::custom("invalid language code")),
+        lang => Ok(lang),
+    }
 }
 
 pub async fn get_chapters(
-    Path(mangadex_id): Path<String>,
+    Path(manga_id): Path<String>,
     Query(params): Query<ChaptersQuery>,
     State(state): State<AppState>,
-) -> Result<Json<Vec<crate::manga::Chapter>>, MangaDexError> {
-    let chapters = get_chapters_with_cache(
-        &mangadex_id,
-        &params.lang,
-        &state.db_pool,
-        &state.mangadex_client,
-        &state.mangadex_config,
-    )
-    .await?;
-
-    Ok(Json(chapters))
-}
+    user: Option<CurrentUser>,
+) -> Result<impl IntoResponse, MangaDexError> {
+    let (source, mangadex_id) = state.sources.resolve(&manga_id);
+
+    let preferences = UserPreferences::load_optional(&state.db_pool, user.map(|u| u.id))
+        .await
+        .map_err(|e| MangaDexError::Internal(e.into()))?;
+    visible_manga(&state, &preferences, &source, &mangadex_id).await?;
+
+    let languages = match params.lang {
+        Some(lang) => vec![lang],
+        None => preferences.translated_languages,
+    };
 
+    let mut chapters = None;
+    for lang in &languages {
+        let result = get_chapters_with_cache(
+            &mangadex_id,
+            lang,
+            &state.db_pool,
+            &state.hot_cache,
+            &source,
+            &state.mangadex_config,
+        )
+        .await?;
+
+        let found = !result.value.is_empty();
+        chapters = Some(result);
+        if found {
+            break;
+        }
+    }
+
+    let chapters = chapters.ok_or(MangaDexError::NotFound)?;
+    Ok((chapters.staleness_headers(), Json(chapters.value)))
+}
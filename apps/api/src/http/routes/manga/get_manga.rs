@@ -1,23 +1,57 @@
+use std::sync::Arc;
+
 use axum::{
     extract::{Path, State},
+    response::IntoResponse,
     Json,
 };
 
-use crate::mangadex::{cache::get_manga_with_cache, MangaDexError};
+use crate::auth::CurrentUser;
+use crate::manga::Manga;
+use crate::mangadex::cache::{get_manga_with_cache, Cached};
+use crate::mangadex::MangaDexError;
+use crate::source::{ContentRating, MangaSource};
+use crate::users::UserPreferences;
 use crate::AppState;
 
-pub async fn get_manga(
-    Path(mangadex_id): Path<String>,
-    State(state): State<AppState>,
-) -> Result<Json<crate::manga::Manga>, MangaDexError> {
+/// Loads a manga and checks its content rating against what `preferences`
+/// allow. Hidden titles look the same as missing ones, here and on every
+/// route that serves their chapters.
+pub(crate) async fn visible_manga(
+    state: &AppState,
+    preferences: &UserPreferences,
+    source: &Arc<dyn MangaSource>,
+    mangadex_id: &str,
+) -> Result<Cached<Manga>, MangaDexError> {
     let manga = get_manga_with_cache(
-        &mangadex_id,
+        mangadex_id,
         &state.db_pool,
-        &state.mangadex_client,
+        &state.hot_cache,
+        source,
         &state.mangadex_config,
     )
     .await?;
 
-    Ok(Json(manga))
+    let allowed =
+        preferences.allowed_content_ratings(&state.mangadex_config.default_content_ratings);
+    if !ContentRating::is_allowed(&manga.value.content_rating, &allowed) {
+        return Err(MangaDexError::NotFound);
+    }
+
+    Ok(manga)
 }
 
+pub async fn get_manga(
+    Path(manga_id): Path<String>,
+    State(state): State<AppState>,
+    user: Option<CurrentUser>,
+) -> Result<impl IntoResponse, MangaDexError> {
+    let (source, mangadex_id) = state.sources.resolve(&manga_id);
+
+    let preferences = UserPreferences::load_optional(&state.db_pool, user.map(|u| u.id))
+        .await
+        .map_err(|e| MangaDexError::Internal(e.into()))?;
+    let manga = visible_manga(&state, &preferences, &source, &mangadex_id).await?;
+
+    Ok((manga.staleness_headers(), Json(manga.value)))
+}
@@ -4,7 +4,10 @@ use axum::{
 };
 use serde::Deserialize;
 
+use crate::auth::CurrentUser;
 use crate::mangadex::MangaDexError;
+use crate::source::{ContentRating, MangaPage};
+use crate::users::UserPreferences;
 use crate::AppState;
 
 #[derive(Debug, Deserialize)]
@@ -13,6 +16,8 @@ pub struct LatestQuery {
     pub limit: u32,
     #[serde(default)]
     pub offset: u32,
+    #[serde(default)]
+    pub source: Option<String>,
 }
 
 fn default_limit() -> u32 {
@@ -30,6 +35,7 @@ pub struct LatestResponse {
 #[derive(serde::Serialize)]
 pub struct MangaSummary {
     pub id: String,
+    pub source: String,
     pub mangadex_id: String,
     pub title: String,
     pub cover_url: String,
@@ -39,58 +45,38 @@ pub struct MangaSummary {
 pub async fn get_latest_manga(
     Query(params): Query<LatestQuery>,
     State(state): State<AppState>,
+    user: Option<CurrentUser>,
 ) -> Result<Json<LatestResponse>, MangaDexError> {
     let limit = params.limit.min(100);
 
-    let response = state
-        .mangadex_client
-        .get_latest_manga(limit, params.offset)
-        .await?;
+    let source = state.sources.get_or_default(params.source.as_deref())?;
+    let allowed = UserPreferences::load_optional(&state.db_pool, user.map(|u| u.id))
+        .await
+        .map_err(|e| MangaDexError::Internal(e.into()))?
+        .allowed_content_ratings(&state.mangadex_config.default_content_ratings);
+
+    let page = if allowed.is_empty() {
+        MangaPage::empty(limit, params.offset)
+    } else {
+        source.latest(&allowed, limit, params.offset).await?
+    };
 
-    let summaries: Vec<MangaSummary> = response
+    let summaries: Vec<MangaSummary> = page
         .data
         .into_iter()
-        .filter_map(|m| {
-            let attrs = &m.attributes;
-            // Try to get title from any available language
-            let title = attrs
-                .title
-                .en
-                .clone()
-                .or_else(|| attrs.title.ja.clone())
-                .or_else(|| {
-                    // Try to get first available title from other languages
-                    attrs
-                        .title
-                        .other
-                        .values()
-                        .find_map(|v| v.as_str().map(|s| s.to_string()))
-                })
-                .unwrap_or_else(|| "Untitled".to_string());
-
-            let cover_url = m
-                .relationships
-                .iter()
-                .find(|r| r.rel_type == "cover_art")
-                .and_then(|r| r.attributes.as_ref())
-                .and_then(|a| a.file_name.as_ref())
-                .map(|filename| {
-                    format!("https://uploads.mangadex.org/covers/{}/{}", m.id, filename)
-                })
-                .unwrap_or_default();
-
-            Some(MangaSummary {
-                id: m.id.clone(),
-                mangadex_id: m.id,
-                title,
-                cover_url,
-                status: attrs.status.clone(),
-            })
+        .filter(|m| ContentRating::is_allowed(&m.content_rating, &allowed))
+        .map(|m| MangaSummary {
+            id: state.sources.qualify(&m.source, &m.mangadex_id),
+            source: m.source,
+            mangadex_id: m.mangadex_id,
+            title: m.title,
+            cover_url: m.cover_url,
+            status: m.status,
         })
         .collect();
 
     Ok(Json(LatestResponse {
-        total: response.total.unwrap_or(summaries.len() as u32),
+        total: page.total,
         limit,
         offset: params.offset,
         data: summaries,
@@ -3,6 +3,7 @@ pub mod get_manga;
 pub mod latest;
 pub mod popular;
 pub mod search;
+pub mod tags;
 
 use axum::{routing::get, Router};
 
@@ -13,6 +14,7 @@ pub fn manga_routes() -> Router<AppState> {
         .route("/search", get(search::search_manga))
         .route("/popular", get(popular::get_popular_manga))
         .route("/latest", get(latest::get_latest_manga))
+        .route("/tags", get(tags::get_tags))
         .route("/{id}", get(get_manga::get_manga))
         .route("/{id}/chapters", get(chapters::get_chapters))
 }
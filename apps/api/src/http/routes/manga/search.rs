@@ -4,16 +4,20 @@ use axum::{
 };
 use serde::Deserialize;
 
+use crate::auth::CurrentUser;
 use crate::mangadex::MangaDexError;
+use crate::source::{ContentRating, MangaPage, SearchFilters};
+use crate::users::UserPreferences;
 use crate::AppState;
 
 #[derive(Debug, Deserialize)]
 pub struct SearchQuery {
-    pub q: String,
     #[serde(default = "default_limit")]
     pub limit: u32,
     #[serde(default)]
     pub offset: u32,
+    #[serde(default)]
+    pub source: Option<String>,
 }
 
 fn default_limit() -> u32 {
@@ -26,11 +30,13 @@ pub struct SearchResponse {
     pub total: u32,
     pub limit: u32,
     pub offset: u32,
+    pub filters: SearchFilters,
 }
 
 #[derive(serde::Serialize)]
 pub struct MangaSummary {
     pub id: String,
+    pub source: String,
     pub mangadex_id: String,
     pub title: String,
     pub cover_url: String,
@@ -39,59 +45,49 @@ pub struct MangaSummary {
 
 pub async fn search_manga(
     Query(params): Query<SearchQuery>,
+    Query(mut filters): Query<SearchFilters>,
     State(state): State<AppState>,
+    user: Option<CurrentUser>,
 ) -> Result<Json<SearchResponse>, MangaDexError> {
     let limit = params.limit.min(100);
 
-    let response = state
-        .mangadex_client
-        .search_manga(&params.q, limit, params.offset)
-        .await?;
+    let preferences = UserPreferences::load_optional(&state.db_pool, user.as_ref().map(|u| u.id))
+        .await
+        .map_err(|e| MangaDexError::Internal(e.into()))?;
 
-    let summaries: Vec<MangaSummary> = response
-        .data
-        .into_iter()
-        .filter_map(|m| {
-            let attrs = &m.attributes;
-            let title = attrs
-                .title
-                .en
-                .clone()
-                .or_else(|| attrs.title.ja.clone())
-                .or_else(|| {
-                    attrs
-                        .title
-                        .other
-                        .values()
-                        .find_map(|v| v.as_str().map(|s| s.to_string()))
-                })
-                .unwrap_or_else(|| "Untitled".to_string());
+    filters.restrict_content_rating(
+        &preferences.allowed_content_ratings(&state.mangadex_config.default_content_ratings),
+    );
+    if user.is_some() && filters.available_translated_language.is_empty() {
+        filters.available_translated_language = preferences.translated_languages;
+    }
 
-            let cover_url = m
-                .relationships
-                .iter()
-                .find(|r| r.rel_type == "cover_art")
-                .and_then(|r| r.attributes.as_ref())
-                .and_then(|a| a.file_name.as_ref())
-                .map(|filename| {
-                    format!("https://uploads.mangadex.org/covers/{}/{}", m.id, filename)
-                })
-                .unwrap_or_default();
+    let source = state.sources.get_or_default(params.source.as_deref())?;
+    let page = if filters.content_rating.is_empty() {
+        MangaPage::empty(limit, params.offset)
+    } else {
+        source.search(&filters, limit, params.offset).await?
+    };
 
-            Some(MangaSummary {
-                id: m.id.clone(),
-                mangadex_id: m.id,
-                title,
-                cover_url,
-                status: attrs.status.clone(),
-            })
+    let summaries: Vec<MangaSummary> = page
+        .data
+        .into_iter()
+        .filter(|m| ContentRating::is_allowed(&m.content_rating, &filters.content_rating))
+        .map(|m| MangaSummary {
+            id: state.sources.qualify(&m.source, &m.mangadex_id),
+            source: m.source,
+            mangadex_id: m.mangadex_id,
+            title: m.title,
+            cover_url: m.cover_url,
+            status: m.status,
         })
         .collect();
 
     Ok(Json(SearchResponse {
-        total: response.total.unwrap_or(summaries.len() as u32),
+        total: page.total,
         limit,
         offset: params.offset,
+        filters,
         data: summaries,
     }))
 }
@@ -0,0 +1,21 @@
+use std::collections::BTreeMap;
+
+use axum::{extract::State, Json};
+
+use crate::manga::Tag;
+use crate::mangadex::{cache::get_tags_with_cache, MangaDexError};
+use crate::AppState;
+
+/// Tags keyed by their group (`genre`, `theme`, `format`, `content`).
+pub type TagsResponse = BTreeMap<String, Vec<Tag>>;
+
+pub async fn get_tags(State(state): State<AppState>) -> Result<Json<TagsResponse>, MangaDexError> {
+    let tags = get_tags_with_cache(&state.db_pool, &state.mangadex_client).await?;
+
+    let mut grouped = TagsResponse::new();
+    for tag in tags {
+        grouped.entry(tag.group.clone()).or_default().push(tag);
+    }
+
+    Ok(Json(grouped))
+}
@@ -0,0 +1,12 @@
+use axum::{extract::State, Json};
+
+use crate::auth::AdminUser;
+use crate::mangadex::cache::HotCacheReport;
+use crate::AppState;
+
+pub async fn cache_metrics(
+    State(state): State<AppState>,
+    _admin: AdminUser,
+) -> Json<HotCacheReport> {
+    Json(state.hot_cache.stats())
+}
@@ -3,5 +3,6 @@ pub mod chapters;
 pub mod get_user_by_id;
 pub mod health;
 pub mod manga;
+pub mod metrics;
 pub mod proxy;
 pub mod users;
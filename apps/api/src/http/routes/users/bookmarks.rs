@@ -7,23 +7,31 @@ use serde::Serialize;
 use sqlx::FromRow;
 
 use crate::auth::CurrentUser;
+use crate::mangadex::cache::get_mangas_by_source;
+use crate::source::ContentRating;
+use crate::users::UserPreferences;
 use crate::AppState;
 
 #[derive(Serialize, FromRow)]
 pub struct BookmarkResponse {
     pub id: String,
+    pub source: String,
     pub manga_mangadex_id: String,
     pub created_at: String,
+    /// Cached details, absent if the source couldn't provide them.
+    #[sqlx(skip)]
+    pub manga: Option<crate::manga::Manga>,
 }
 
 pub async fn get_bookmarks(
     State(state): State<AppState>,
     user: CurrentUser,
 ) -> Result<Json<Vec<BookmarkResponse>>, StatusCode> {
-    let bookmarks = sqlx::query_as::<_, BookmarkResponse>(
+    let mut bookmarks = sqlx::query_as::<_, BookmarkResponse>(
         r#"
         SELECT 
             id::text AS id,
+            source,
             manga_mangadex_id,
             created_at::text AS created_at
         FROM user_bookmarks
@@ -36,22 +44,57 @@ pub async fn get_bookmarks(
     .await
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
+    let keys: Vec<(String, String)> = bookmarks
+        .iter()
+        .map(|b| (b.source.clone(), b.manga_mangadex_id.clone()))
+        .collect();
+    let mangas = get_mangas_by_source(
+        &keys,
+        &state.db_pool,
+        &state.hot_cache,
+        &state.sources,
+        &state.mangadex_config,
+    )
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let allowed = UserPreferences::load(&state.db_pool, user.id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .allowed_content_ratings(&state.mangadex_config.default_content_ratings);
+
+    for bookmark in &mut bookmarks {
+        let key = (bookmark.source.clone(), bookmark.manga_mangadex_id.clone());
+        bookmark.manga = mangas.get(&key).cloned();
+    }
+    // Bookmarks of titles the user may not see are hidden like the titles.
+    // Ones whose manga couldn't be loaded carry nothing but their own IDs.
+    bookmarks.retain(|bookmark| {
+        bookmark
+            .manga
+            .as_ref()
+            .is_none_or(|manga| ContentRating::is_allowed(&manga.content_rating, &allowed))
+    });
+
     Ok(Json(bookmarks))
 }
 
 pub async fn add_bookmark(
-    Path(manga_mangadex_id): Path<String>,
+    Path(manga_id): Path<String>,
     State(state): State<AppState>,
     user: CurrentUser,
 ) -> Result<StatusCode, StatusCode> {
+    let (source, manga_mangadex_id) = state.sources.resolve(&manga_id);
+
     sqlx::query(
         r#"
-        INSERT INTO user_bookmarks (user_id, manga_mangadex_id)
-        VALUES ($1, $2)
-        ON CONFLICT (user_id, manga_mangadex_id) DO NOTHING
+        INSERT INTO user_bookmarks (user_id, source, manga_mangadex_id)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (user_id, source, manga_mangadex_id) DO NOTHING
         "#,
     )
     .bind(user.id)
+    .bind(source.id())
     .bind(&manga_mangadex_id)
     .execute(&state.db_pool)
     .await
@@ -61,17 +104,20 @@ pub async fn add_bookmark(
 }
 
 pub async fn remove_bookmark(
-    Path(manga_mangadex_id): Path<String>,
+    Path(manga_id): Path<String>,
     State(state): State<AppState>,
     user: CurrentUser,
 ) -> Result<StatusCode, StatusCode> {
+    let (source, manga_mangadex_id) = state.sources.resolve(&manga_id);
+
     sqlx::query(
         r#"
         DELETE FROM user_bookmarks
-        WHERE user_id = $1 AND manga_mangadex_id = $2
+        WHERE user_id = $1 AND source = $2 AND manga_mangadex_id = $3
         "#,
     )
     .bind(user.id)
+    .bind(source.id())
     .bind(&manga_mangadex_id)
     .execute(&state.db_pool)
     .await
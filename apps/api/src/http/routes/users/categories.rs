@@ -0,0 +1,273 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::auth::CurrentUser;
+use crate::AppState;
+
+#[derive(Serialize, FromRow)]
+pub struct Category {
+    pub id: Uuid,
+    pub name: String,
+    pub position: i32,
+    pub manga_count: i64,
+    pub created_at: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateCategoryRequest {
+    #[validate(length(min = 1, max = 64))]
+    pub name: String,
+    /// Appended after the existing categories when omitted.
+    #[serde(default)]
+    pub position: Option<i32>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateCategoryRequest {
+    #[validate(length(min = 1, max = 64))]
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub position: Option<i32>,
+}
+
+const CATEGORY_COLUMNS: &str = r#"
+    c.id,
+    c.name,
+    c.position,
+    (SELECT COUNT(*) FROM bookmark_categories bc WHERE bc.category_id = c.id) AS manga_count,
+    c.created_at::text AS created_at
+"#;
+
+fn map_write_error(err: sqlx::Error) -> StatusCode {
+    match err {
+        sqlx::Error::Database(db) if db.is_unique_violation() => StatusCode::CONFLICT,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+async fn fetch_category(
+    state: &AppState,
+    user_id: Uuid,
+    category_id: Uuid,
+) -> Result<Category, StatusCode> {
+    sqlx::query_as::<_, Category>(&format!(
+        "SELECT {} FROM library_categories c WHERE c.id = $1 AND c.user_id = $2",
+        CATEGORY_COLUMNS
+    ))
+    .bind(category_id)
+    .bind(user_id)
+    .fetch_optional(&state.db_pool)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    .ok_or(StatusCode::NOT_FOUND)
+}
+
+pub async fn get_categories(
+    State(state): State<AppState>,
+    user: CurrentUser,
+) -> Result<Json<Vec<Category>>, StatusCode> {
+    let categories = sqlx::query_as::<_, Category>(&format!(
+        "SELECT {} FROM library_categories c WHERE c.user_id = $1 ORDER BY c.position, c.name",
+        CATEGORY_COLUMNS
+    ))
+    .bind(user.id)
+    .fetch_all(&state.db_pool)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(categories))
+}
+
+pub async fn create_category(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    Json(payload): Json<CreateCategoryRequest>,
+) -> Result<(StatusCode, Json<Category>), StatusCode> {
+    payload.validate().map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let name = payload.name.trim();
+    if name.is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let category_id: Uuid = sqlx::query_scalar(
+        r#"
+        INSERT INTO library_categories (user_id, name, position)
+        VALUES (
+            $1,
+            $2,
+            COALESCE(
+                $3,
+                (SELECT COALESCE(MAX(position) + 1, 0) FROM library_categories WHERE user_id = $1)
+            )
+        )
+        RETURNING id
+        "#,
+    )
+    .bind(user.id)
+    .bind(name)
+    .bind(payload.position)
+    .fetch_one(&state.db_pool)
+    .await
+    .map_err(map_write_error)?;
+
+    let category = fetch_category(&state, user.id, category_id).await?;
+
+    Ok((StatusCode::CREATED, Json(category)))
+}
+
+pub async fn update_category(
+    Path(category_id): Path<Uuid>,
+    State(state): State<AppState>,
+    user: CurrentUser,
+    Json(payload): Json<UpdateCategoryRequest>,
+) -> Result<Json<Category>, StatusCode> {
+    payload.validate().map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let name = payload.name.as_deref().map(str::trim);
+    if name.is_some_and(str::is_empty) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let result = sqlx::query(
+        r#"
+        UPDATE library_categories
+        SET name = COALESCE($3, name),
+            position = COALESCE($4, position),
+            updated_at = NOW()
+        WHERE id = $1 AND user_id = $2
+        "#,
+    )
+    .bind(category_id)
+    .bind(user.id)
+    .bind(name)
+    .bind(payload.position)
+    .execute(&state.db_pool)
+    .await
+    .map_err(map_write_error)?;
+
+    if result.rows_affected() == 0 {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let category = fetch_category(&state, user.id, category_id).await?;
+
+    Ok(Json(category))
+}
+
+pub async fn delete_category(
+    Path(category_id): Path<Uuid>,
+    State(state): State<AppState>,
+    user: CurrentUser,
+) -> Result<StatusCode, StatusCode> {
+    let result = sqlx::query("DELETE FROM library_categories WHERE id = $1 AND user_id = $2")
+        .bind(category_id)
+        .bind(user.id)
+        .execute(&state.db_pool)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if result.rows_affected() == 0 {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Files a bookmarked manga under a category. The manga must already be
+/// bookmarked.
+pub async fn add_to_category(
+    Path((category_id, manga_id)): Path<(Uuid, String)>,
+    State(state): State<AppState>,
+    user: CurrentUser,
+) -> Result<StatusCode, StatusCode> {
+    let (source, manga_mangadex_id) = state.sources.resolve(&manga_id);
+
+    let result = sqlx::query(
+        r#"
+        INSERT INTO bookmark_categories (bookmark_id, category_id)
+        SELECT b.id, c.id
+        FROM user_bookmarks b
+        JOIN library_categories c ON c.user_id = b.user_id
+        WHERE b.user_id = $1
+            AND b.source = $2
+            AND b.manga_mangadex_id = $3
+            AND c.id = $4
+        ON CONFLICT (bookmark_id, category_id) DO NOTHING
+        "#,
+    )
+    .bind(user.id)
+    .bind(source.id())
+    .bind(&manga_mangadex_id)
+    .bind(category_id)
+    .execute(&state.db_pool)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if result.rows_affected() == 0 {
+        // Either already filed there, or the bookmark or category is missing
+        let exists: bool = sqlx::query_scalar(
+            r#"
+            SELECT EXISTS (
+                SELECT 1
+                FROM bookmark_categories bc
+                JOIN user_bookmarks b ON b.id = bc.bookmark_id
+                WHERE b.user_id = $1
+                    AND b.source = $2
+                    AND b.manga_mangadex_id = $3
+                    AND bc.category_id = $4
+            )
+            "#,
+        )
+        .bind(user.id)
+        .bind(source.id())
+        .bind(&manga_mangadex_id)
+        .bind(category_id)
+        .fetch_one(&state.db_pool)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        if !exists {
+            return Err(StatusCode::NOT_FOUND);
+        }
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn remove_from_category(
+    Path((category_id, manga_id)): Path<(Uuid, String)>,
+    State(state): State<AppState>,
+    user: CurrentUser,
+) -> Result<StatusCode, StatusCode> {
+    let (source, manga_mangadex_id) = state.sources.resolve(&manga_id);
+
+    sqlx::query(
+        r#"
+        DELETE FROM bookmark_categories bc
+        USING user_bookmarks b
+        WHERE bc.bookmark_id = b.id
+            AND b.user_id = $1
+            AND b.source = $2
+            AND b.manga_mangadex_id = $3
+            AND bc.category_id = $4
+        "#,
+    )
+    .bind(user.id)
+    .bind(source.id())
+    .bind(&manga_mangadex_id)
+    .bind(category_id)
+    .execute(&state.db_pool)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,163 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use crate::auth::jwt::hash_token;
+use crate::auth::CurrentUser;
+use crate::users::export::{self, DataExport, ExportFormat};
+use crate::AppState;
+
+/// Exports a user may start per hour; each one reads their whole account.
+const MAX_EXPORTS_PER_HOUR: i64 = 5;
+
+#[derive(Deserialize)]
+pub struct ExportQuery {
+    #[serde(default)]
+    pub format: ExportFormat,
+}
+
+#[derive(Serialize)]
+pub struct ExportResponse {
+    #[serde(flatten)]
+    pub export: DataExport,
+    /// Only sent when the export is created; the server keeps just a hash
+    /// of the token in it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub download_url: Option<String>,
+}
+
+impl From<DataExport> for ExportResponse {
+    fn from(export: DataExport) -> Self {
+        Self {
+            export,
+            download_url: None,
+        }
+    }
+}
+
+/// Starts an export of the user's data and returns its download link, the
+/// only time the link is shown. Small accounts get a finished export (200);
+/// larger ones are built in the background (202), the link working once
+/// [`get_export`] reports it ready. Asking again while one is being built
+/// returns that one, without a link.
+pub async fn request_export(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    Query(query): Query<ExportQuery>,
+) -> Result<(StatusCode, Json<ExportResponse>), StatusCode> {
+    let db = &state.db_pool;
+
+    if let Some(pending) = DataExport::pending(db, user.id, query.format)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    {
+        return Ok((StatusCode::ACCEPTED, Json(pending.into())));
+    }
+
+    let recent = export::recent_count(db, user.id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if recent >= MAX_EXPORTS_PER_HOUR {
+        return Err(StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    let size = export::account_size(db, user.id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let (created, token) = DataExport::create(db, user.id, query.format)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let download_url = Some(export::download_url(&token));
+
+    if size > state.export_config.inline_max_rows {
+        export::spawn_build(&state, created.id);
+        let response = ExportResponse {
+            export: created,
+            download_url,
+        };
+        return Ok((StatusCode::ACCEPTED, Json(response)));
+    }
+
+    export::build_now(&state, created.id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let built = DataExport::load(db, user.id, created.id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let response = ExportResponse {
+        export: built,
+        download_url,
+    };
+    Ok((StatusCode::OK, Json(response)))
+}
+
+pub async fn get_export(
+    Path(export_id): Path<Uuid>,
+    State(state): State<AppState>,
+    user: CurrentUser,
+) -> Result<Json<ExportResponse>, StatusCode> {
+    let export = DataExport::load(&state.db_pool, user.id, export_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(export.into()))
+}
+
+#[derive(FromRow)]
+struct ExportDownload {
+    format: ExportFormat,
+    archive: Vec<u8>,
+    created_at: DateTime<Utc>,
+}
+
+/// Serves a finished export. The token in the link is the only credential,
+/// so the link works from a plain browser download; it is matched by hash.
+pub async fn download_export(
+    Path(token): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Response, StatusCode> {
+    let download = sqlx::query_as::<_, ExportDownload>(
+        r#"
+        SELECT format, archive, created_at
+        FROM data_exports
+        WHERE download_token_hash = $1
+            AND status = 'ready'
+            AND expires_at > NOW()
+            AND archive IS NOT NULL
+        "#,
+    )
+    .bind(hash_token(&token))
+    .fetch_optional(&state.db_pool)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    let disposition = format!(
+        "attachment; filename=\"{}\"",
+        download.format.file_name(download.created_at)
+    );
+
+    Ok((
+        [
+            (
+                header::CONTENT_TYPE,
+                download.format.content_type().to_string(),
+            ),
+            (header::CONTENT_DISPOSITION, disposition),
+            (header::CACHE_CONTROL, "private, no-store".to_string()),
+        ],
+        download.archive,
+    )
+        .into_response())
+}
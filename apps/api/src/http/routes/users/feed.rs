@@ -0,0 +1,120 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use crate::auth::CurrentUser;
+use crate::users::UserPreferences;
+use crate::AppState;
+
+#[derive(Serialize, FromRow)]
+pub struct FeedItem {
+    pub source: String,
+    pub manga_mangadex_id: String,
+    pub manga_title: Option<String>,
+    pub chapter_mangadex_id: String,
+    pub chapter_number: Option<String>,
+    pub volume: Option<String>,
+    pub title: Option<String>,
+    pub language: String,
+    pub scanlation_group_name: Option<String>,
+    pub published_at: Option<String>,
+    pub read: bool,
+    #[serde(skip)]
+    pub total: i64,
+}
+
+#[derive(Serialize)]
+pub struct FeedResponse {
+    pub data: Vec<FeedItem>,
+    pub total: i64,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+#[derive(Deserialize)]
+pub struct FeedQuery {
+    #[serde(default = "default_limit")]
+    pub limit: i64,
+    #[serde(default)]
+    pub offset: i64,
+}
+
+fn default_limit() -> i64 {
+    50
+}
+
+/// Latest chapters across the user's bookmarked manga, in their preferred
+/// languages, newest first. Manga without a cached rating the user may see
+/// are left out.
+pub async fn get_feed(
+    Query(params): Query<FeedQuery>,
+    State(state): State<AppState>,
+    user: CurrentUser,
+) -> Result<Json<FeedResponse>, StatusCode> {
+    let limit = params.limit.clamp(1, 100);
+    let offset = params.offset.max(0);
+
+    let allowed: Vec<&str> = UserPreferences::load(&state.db_pool, user.id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .allowed_content_ratings(&state.mangadex_config.default_content_ratings)
+        .iter()
+        .map(|rating| rating.as_str())
+        .collect();
+
+    let items = sqlx::query_as::<_, FeedItem>(
+        r#"
+        SELECT
+            c.source,
+            c.manga_mangadex_id,
+            m.title AS manga_title,
+            c.mangadex_id AS chapter_mangadex_id,
+            c.chapter_number,
+            c.volume,
+            c.title,
+            c.language,
+            c.scanlation_group_name,
+            c.published_at::text AS published_at,
+            h.id IS NOT NULL AS read,
+            COUNT(*) OVER () AS total
+        FROM user_bookmarks b
+        LEFT JOIN user_preferences p ON p.user_id = b.user_id
+        JOIN chapter_cache c
+            ON c.source = b.source
+            AND c.manga_mangadex_id = b.manga_mangadex_id
+            AND c.language = ANY(COALESCE(p.translated_languages, ARRAY['en']))
+        JOIN manga_cache m
+            ON m.source = c.source
+            AND m.mangadex_id = c.manga_mangadex_id
+            AND m.content_rating = ANY($4)
+        LEFT JOIN reading_history h
+            ON h.user_id = b.user_id
+            AND h.source = c.source
+            AND h.chapter_mangadex_id = c.mangadex_id
+        WHERE b.user_id = $1
+        ORDER BY c.published_at DESC NULLS LAST, c.mangadex_id
+        LIMIT $2 OFFSET $3
+        "#,
+    )
+    .bind(user.id)
+    .bind(limit)
+    .bind(offset)
+    .bind(&allowed)
+    .fetch_all(&state.db_pool)
+    .await
+    .map_err(|err| {
+        tracing::error!("failed to load feed for {}: {}", user.id, err);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(FeedResponse {
+        total: items.first().map(|item| item.total).unwrap_or(0),
+        limit,
+        offset,
+        data: items,
+    }))
+}
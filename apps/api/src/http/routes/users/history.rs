@@ -7,14 +7,22 @@ use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 
 use crate::auth::CurrentUser;
+use crate::mangadex::cache::get_mangas_by_source;
+use crate::source::ContentRating;
+use crate::trackers::sync::enqueue_and_push;
+use crate::users::UserPreferences;
 use crate::AppState;
 
 #[derive(Serialize, FromRow)]
 pub struct ReadingHistoryItem {
     pub id: String,
+    pub source: String,
     pub manga_mangadex_id: String,
     pub chapter_mangadex_id: String,
     pub read_at: String,
+    /// Cached details, absent if the source couldn't provide them.
+    #[sqlx(skip)]
+    pub manga: Option<crate::manga::Manga>,
 }
 
 #[derive(Deserialize)]
@@ -34,10 +42,11 @@ pub async fn get_history(
     State(state): State<AppState>,
     user: CurrentUser,
 ) -> Result<Json<Vec<ReadingHistoryItem>>, StatusCode> {
-    let history = sqlx::query_as::<_, ReadingHistoryItem>(
+    let mut history = sqlx::query_as::<_, ReadingHistoryItem>(
         r#"
         SELECT 
             id::text AS id,
+            source,
             manga_mangadex_id,
             chapter_mangadex_id,
             read_at::text AS read_at
@@ -54,6 +63,37 @@ pub async fn get_history(
     .await
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
+    let keys: Vec<(String, String)> = history
+        .iter()
+        .map(|h| (h.source.clone(), h.manga_mangadex_id.clone()))
+        .collect();
+    let mangas = get_mangas_by_source(
+        &keys,
+        &state.db_pool,
+        &state.hot_cache,
+        &state.sources,
+        &state.mangadex_config,
+    )
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let allowed = UserPreferences::load(&state.db_pool, user.id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .allowed_content_ratings(&state.mangadex_config.default_content_ratings);
+
+    for item in &mut history {
+        let key = (item.source.clone(), item.manga_mangadex_id.clone());
+        item.manga = mangas.get(&key).cloned();
+    }
+    // History of titles the user may not see is hidden like the titles.
+    // Entries whose manga couldn't be loaded carry nothing but their own IDs.
+    history.retain(|item| {
+        item.manga
+            .as_ref()
+            .is_none_or(|manga| ContentRating::is_allowed(&manga.content_rating, &allowed))
+    });
+
     Ok(Json(history))
 }
 
@@ -62,34 +102,31 @@ pub async fn mark_chapter_read(
     State(state): State<AppState>,
     user: CurrentUser,
 ) -> Result<StatusCode, StatusCode> {
-    let chapter = state
-        .mangadex_client
-        .get_chapter(&chapter_id)
+    let (source, chapter_mangadex_id) = state.sources.resolve(&chapter_id);
+
+    let chapter = source
+        .get_chapter(&chapter_mangadex_id)
         .await
         .map_err(|_| StatusCode::BAD_REQUEST)?;
 
-    let manga_id = chapter
-        .relationships
-        .iter()
-        .find(|r| r.rel_type == "manga")
-        .map(|r| r.id.clone())
-        .ok_or(StatusCode::BAD_REQUEST)?;
-
     sqlx::query(
         r#"
-        INSERT INTO reading_history (user_id, manga_mangadex_id, chapter_mangadex_id)
-        VALUES ($1, $2, $3)
-        ON CONFLICT (user_id, chapter_mangadex_id) 
+        INSERT INTO reading_history (user_id, source, manga_mangadex_id, chapter_mangadex_id)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (user_id, source, chapter_mangadex_id) 
         DO UPDATE SET read_at = NOW()
         "#,
     )
     .bind(user.id)
-    .bind(&manga_id)
-    .bind(&chapter_id)
+    .bind(source.id())
+    .bind(&chapter.manga_mangadex_id)
+    .bind(&chapter_mangadex_id)
     .execute(&state.db_pool)
     .await
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
+    enqueue_and_push(&state, user.id, source.id(), &chapter.manga_mangadex_id).await;
+
     Ok(StatusCode::CREATED)
 }
 
@@ -98,14 +135,17 @@ pub async fn remove_from_history(
     State(state): State<AppState>,
     user: CurrentUser,
 ) -> Result<StatusCode, StatusCode> {
+    let (source, chapter_mangadex_id) = state.sources.resolve(&chapter_id);
+
     sqlx::query(
         r#"
         DELETE FROM reading_history
-        WHERE user_id = $1 AND chapter_mangadex_id = $2
+        WHERE user_id = $1 AND source = $2 AND chapter_mangadex_id = $3
         "#,
     )
     .bind(user.id)
-    .bind(&chapter_id)
+    .bind(source.id())
+    .bind(&chapter_mangadex_id)
     .execute(&state.db_pool)
     .await
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
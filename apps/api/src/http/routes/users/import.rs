@@ -0,0 +1,110 @@
+use axum::{
+    extract::{Multipart, Path, State},
+    http::StatusCode,
+    Json,
+};
+use uuid::Uuid;
+
+use crate::auth::CurrentUser;
+use crate::users::import::{self, ImportFormat, ImportInput, LibraryImport};
+use crate::AppState;
+
+/// Largest export accepted; Tachiyomi backups with covers cached run big.
+pub const MAX_UPLOAD_BYTES: usize = 32 * 1024 * 1024;
+
+/// Imports a library from another app. A multipart form with:
+///
+/// - `format`: `mangadex`, `tachiyomi` or `myanimelist`
+/// - `file`: the backup or export, for `tachiyomi` and `myanimelist`
+/// - `token`: a MangaDex access token, for `mangadex`
+///
+/// Matching runs in the background (202) and is polled through
+/// [`get_import`]; only one import per user runs at a time (409). Matched
+/// entries are bookmarked with their read chapters, progress and status;
+/// the finished import's report lists what could not be matched.
+pub async fn import_library(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    mut multipart: Multipart,
+) -> Result<(StatusCode, Json<LibraryImport>), StatusCode> {
+    let mut format: Option<ImportFormat> = None;
+    let mut file: Option<Vec<u8>> = None;
+    let mut token: Option<String> = None;
+
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|_| StatusCode::BAD_REQUEST)?
+    {
+        match field.name() {
+            Some("format") => {
+                let value = field.text().await.map_err(|_| StatusCode::BAD_REQUEST)?;
+                format = Some(
+                    serde_json::from_value(serde_json::Value::String(value.trim().to_string()))
+                        .map_err(|_| StatusCode::BAD_REQUEST)?,
+                );
+            }
+            Some("file") => {
+                let bytes = field.bytes().await.map_err(|_| StatusCode::BAD_REQUEST)?;
+                file = Some(bytes.to_vec());
+            }
+            Some("token") => {
+                let value = field.text().await.map_err(|_| StatusCode::BAD_REQUEST)?;
+                token = Some(value.trim().to_string()).filter(|t| !t.is_empty());
+            }
+            _ => {}
+        }
+    }
+
+    let format = format.ok_or(StatusCode::BAD_REQUEST)?;
+    let input = match format {
+        ImportFormat::Mangadex => ImportInput::Mangadex(token.ok_or(StatusCode::BAD_REQUEST)?),
+        ImportFormat::Tachiyomi | ImportFormat::Myanimelist => {
+            let file = file.ok_or(StatusCode::BAD_REQUEST)?;
+            let parse = match format {
+                ImportFormat::Tachiyomi => import::tachiyomi::parse,
+                _ => import::myanimelist::parse,
+            };
+            // Inflating and decoding a large backup would stall the runtime
+            let entries = tokio::task::spawn_blocking(move || parse(file))
+                .await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+                .map_err(|_| StatusCode::BAD_REQUEST)?;
+            ImportInput::Parsed(entries)
+        }
+    };
+
+    let db = &state.db_pool;
+    if LibraryImport::pending(db, user.id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .is_some()
+    {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    // The index allowing one pending import per user settles a race with a
+    // concurrent request
+    let created = LibraryImport::create(db, user.id, format)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(db) if db.is_unique_violation() => StatusCode::CONFLICT,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        })?;
+    import::spawn_import(&state, &created, user.id, input);
+
+    Ok((StatusCode::ACCEPTED, Json(created)))
+}
+
+pub async fn get_import(
+    Path(import_id): Path<Uuid>,
+    State(state): State<AppState>,
+    user: CurrentUser,
+) -> Result<Json<LibraryImport>, StatusCode> {
+    let import = LibraryImport::load(&state.db_pool, user.id, import_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(import))
+}
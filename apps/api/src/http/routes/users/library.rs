@@ -1,25 +1,59 @@
-use axum::{extract::State, http::StatusCode, Json};
-use serde::Serialize;
-use sqlx::FromRow;
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    Json,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, Postgres, QueryBuilder};
+use uuid::Uuid;
 
 use crate::auth::CurrentUser;
-use crate::mangadex::cache::get_manga_with_cache;
+use crate::mangadex::cache::get_mangas_by_source;
+use crate::source::filters::{comma_separated, PublicationStatus, SortDirection};
+use crate::source::ContentRating;
+use crate::users::status::{MangaStatus, ReadingStatus};
+use crate::users::{library::push_library_cte, UserPreferences};
 use crate::AppState;
 
-#[derive(Serialize, FromRow)]
+#[derive(FromRow)]
 struct LibraryManga {
+    bookmark_id: Uuid,
+    source: String,
     manga_mangadex_id: String,
     bookmark_created_at: String,
     chapter_mangadex_id: Option<String>,
     page_number: Option<i32>,
     progress_updated_at: Option<String>,
+    added_at: DateTime<Utc>,
+    last_read_at: DateTime<Utc>,
+    latest_upload: DateTime<Utc>,
+    title: String,
+    unread_count: i64,
+    category_ids: Vec<Uuid>,
+    reading_status: Option<ReadingStatus>,
+    score: Option<i16>,
+    started_on: Option<NaiveDate>,
+    finished_on: Option<NaiveDate>,
+    notes: Option<String>,
+    reread_count: Option<i32>,
+    status_updated_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Serialize)]
 pub struct LibraryItem {
-    pub manga: crate::manga::Manga,
+    pub source: String,
+    pub manga_mangadex_id: String,
+    /// Cached details, absent if the source couldn't provide them.
+    pub manga: Option<crate::manga::Manga>,
     pub progress: Option<LibraryProgress>,
     pub bookmarked_at: String,
+    /// Chapters past the furthest one read, in the preferred languages.
+    pub unread_count: i64,
+    pub category_ids: Vec<Uuid>,
+    /// The user's status and rating, if they have recorded one.
+    pub reading_status: Option<MangaStatus>,
 }
 
 #[derive(Serialize)]
@@ -29,42 +63,289 @@ pub struct LibraryProgress {
     pub updated_at: String,
 }
 
+#[derive(Serialize)]
+pub struct LibraryResponse {
+    pub data: Vec<LibraryItem>,
+    /// Pass back as `cursor` to get the next page; absent on the last page.
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Serialize, FromRow)]
+pub struct UnreadManga {
+    pub source: String,
+    pub manga_mangadex_id: String,
+    pub title: String,
+    pub unread_count: i64,
+}
+
+#[derive(Serialize)]
+pub struct UnreadSummary {
+    pub total_unread: i64,
+    pub data: Vec<UnreadManga>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LibrarySort {
+    /// Most recently read or bookmarked first.
+    #[default]
+    LastRead,
+    /// Most recent upstream chapter upload first.
+    Updated,
+    Title,
+    /// Date the bookmark was added.
+    Added,
+    /// Most unread chapters first.
+    Unread,
+}
+
+impl LibrarySort {
+    fn column(&self) -> &'static str {
+        match self {
+            LibrarySort::LastRead => "last_read_at",
+            LibrarySort::Updated => "latest_upload",
+            LibrarySort::Title => "title",
+            LibrarySort::Added => "added_at",
+            LibrarySort::Unread => "unread_count",
+        }
+    }
+
+    fn default_direction(&self) -> SortDirection {
+        match self {
+            LibrarySort::Title => SortDirection::Asc,
+            _ => SortDirection::Desc,
+        }
+    }
+}
+
+/// How far the user is into a series, derived from their progress.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReadingState {
+    NotStarted,
+    Reading,
+    CaughtUp,
+}
+
+#[derive(Deserialize)]
+pub struct LibraryQuery {
+    #[serde(default = "default_limit")]
+    pub limit: i64,
+    #[serde(default)]
+    pub cursor: Option<String>,
+    #[serde(default)]
+    pub sort: LibrarySort,
+    #[serde(default)]
+    pub order: Option<SortDirection>,
+    #[serde(default, deserialize_with = "comma_separated")]
+    pub status: Vec<PublicationStatus>,
+    #[serde(default)]
+    pub has_unread: Option<bool>,
+    /// Tag ID the manga must have.
+    #[serde(default)]
+    pub tag: Option<String>,
+    #[serde(default)]
+    pub reading_state: Option<ReadingState>,
+    #[serde(default, deserialize_with = "comma_separated")]
+    pub reading_status: Vec<ReadingStatus>,
+    /// Only manga filed under this category.
+    #[serde(default)]
+    pub category: Option<Uuid>,
+}
+
+fn default_limit() -> i64 {
+    50
+}
+
+/// Position after the last item of a page, for keyset pagination.
+#[derive(Serialize, Deserialize)]
+struct LibraryCursor {
+    sort: LibrarySort,
+    key: String,
+    id: Uuid,
+}
+
+impl LibraryCursor {
+    fn after(sort: LibrarySort, row: &LibraryManga) -> Self {
+        let key = match sort {
+            LibrarySort::LastRead => row.last_read_at.to_rfc3339(),
+            LibrarySort::Updated => row.latest_upload.to_rfc3339(),
+            LibrarySort::Title => row.title.clone(),
+            LibrarySort::Added => row.added_at.to_rfc3339(),
+            LibrarySort::Unread => row.unread_count.to_string(),
+        };
+
+        Self {
+            sort,
+            key,
+            id: row.bookmark_id,
+        }
+    }
+
+    fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).unwrap_or_default())
+    }
+
+    fn decode(cursor: &str) -> Option<Self> {
+        let raw = URL_SAFE_NO_PAD.decode(cursor).ok()?;
+        serde_json::from_slice(&raw).ok()
+    }
+
+    /// Binds the key with the type of the sort column.
+    fn push_key(&self, query: &mut QueryBuilder<'_, Postgres>) -> Option<()> {
+        match self.sort {
+            LibrarySort::LastRead | LibrarySort::Updated | LibrarySort::Added => {
+                let key = DateTime::parse_from_rfc3339(&self.key).ok()?;
+                query.push_bind(key.with_timezone(&Utc));
+            }
+            LibrarySort::Title => {
+                query.push_bind(self.key.clone());
+            }
+            LibrarySort::Unread => {
+                query.push_bind(self.key.parse::<i64>().ok()?);
+            }
+        }
+        Some(())
+    }
+}
+
 pub async fn get_library(
+    Query(params): Query<LibraryQuery>,
     State(state): State<AppState>,
     user: CurrentUser,
-) -> Result<Json<Vec<LibraryItem>>, StatusCode> {
-    let library_data = sqlx::query_as::<_, LibraryManga>(
-        r#"
-        SELECT 
-            b.manga_mangadex_id,
-            b.created_at::text AS bookmark_created_at,
-            p.chapter_mangadex_id,
-            p.page_number,
-            p.updated_at::text AS progress_updated_at
-        FROM user_bookmarks b
-        LEFT JOIN user_reading_progress p 
-            ON b.user_id = p.user_id 
-            AND b.manga_mangadex_id = p.manga_mangadex_id
-        WHERE b.user_id = $1
-        ORDER BY COALESCE(p.updated_at, b.created_at) DESC
-        "#,
+) -> Result<Json<LibraryResponse>, StatusCode> {
+    let limit = params.limit.clamp(1, 100);
+    let sort = params.sort;
+    let direction = params.order.unwrap_or(sort.default_direction());
+
+    let cursor = match params.cursor.as_deref() {
+        Some(raw) => match LibraryCursor::decode(raw) {
+            Some(cursor) if cursor.sort == sort => Some(cursor),
+            _ => return Err(StatusCode::BAD_REQUEST),
+        },
+        None => None,
+    };
+
+    let allowed = UserPreferences::load(&state.db_pool, user.id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .allowed_content_ratings(&state.mangadex_config.default_content_ratings);
+    let allowed_names: Vec<&str> = allowed.iter().map(|r| r.as_str()).collect();
+
+    cache_bookmarked_manga(&state, user.id).await?;
+
+    let mut query = QueryBuilder::<Postgres>::new("");
+    push_library_cte(&mut query, user.id);
+    // Manga still missing from the cache are ones the source couldn't
+    // provide; they come back as placeholders without details
+    query.push("SELECT * FROM library WHERE (NOT cached OR content_rating = ANY(");
+    query.push_bind(allowed_names);
+    query.push("))");
+
+    if !params.status.is_empty() {
+        let status: Vec<&str> = params.status.iter().map(|s| s.as_str()).collect();
+        query.push(" AND status = ANY(");
+        query.push_bind(status);
+        query.push(")");
+    }
+    if let Some(has_unread) = params.has_unread {
+        query.push(" AND (unread_count > 0) = ");
+        query.push_bind(has_unread);
+    }
+    if let Some(tag) = params.tag {
+        query.push(" AND tags @> jsonb_build_array(jsonb_build_object('id', ");
+        query.push_bind(tag);
+        query.push("::text))");
+    }
+    if !params.reading_status.is_empty() {
+        let statuses: Vec<&str> = params.reading_status.iter().map(|s| s.as_str()).collect();
+        query.push(" AND reading_status::text = ANY(");
+        query.push_bind(statuses);
+        query.push(")");
+    }
+    if let Some(category) = params.category {
+        query.push(" AND ");
+        query.push_bind(category);
+        query.push(" = ANY(category_ids)");
+    }
+    match params.reading_state {
+        Some(ReadingState::NotStarted) => {
+            query.push(" AND NOT started");
+        }
+        Some(ReadingState::Reading) => {
+            query.push(" AND started AND unread_count > 0");
+        }
+        Some(ReadingState::CaughtUp) => {
+            query.push(" AND started AND unread_count = 0");
+        }
+        None => {}
+    }
+
+    let comparison = match direction {
+        SortDirection::Asc => ">",
+        SortDirection::Desc => "<",
+    };
+    if let Some(cursor) = &cursor {
+        query.push(format_args!(
+            " AND ({}, bookmark_id) {} (",
+            sort.column(),
+            comparison
+        ));
+        cursor.push_key(&mut query).ok_or(StatusCode::BAD_REQUEST)?;
+        query.push(", ");
+        query.push_bind(cursor.id);
+        query.push(")");
+    }
+
+    query.push(format_args!(
+        " ORDER BY {column} {dir}, bookmark_id {dir} LIMIT ",
+        column = sort.column(),
+        dir = direction.as_str()
+    ));
+    // One extra row tells us whether there is another page
+    query.push_bind(limit + 1);
+
+    let mut library_data = query
+        .build_query_as::<LibraryManga>()
+        .fetch_all(&state.db_pool)
+        .await
+        .map_err(|err| {
+            tracing::error!("failed to load library for {}: {}", user.id, err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let next_cursor = if library_data.len() as i64 > limit {
+        library_data.truncate(limit as usize);
+        library_data
+            .last()
+            .map(|row| LibraryCursor::after(sort, row).encode())
+    } else {
+        None
+    };
+
+    // Fetch manga details for every bookmark at once, mostly from the cache
+    let keys: Vec<(String, String)> = library_data
+        .iter()
+        .map(|item| (item.source.clone(), item.manga_mangadex_id.clone()))
+        .collect();
+    let mut mangas = get_mangas_by_source(
+        &keys,
+        &state.db_pool,
+        &state.hot_cache,
+        &state.sources,
+        &state.mangadex_config,
     )
-    .bind(user.id)
-    .fetch_all(&state.db_pool)
     .await
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    // Fetch manga details for each bookmark
     let mut library_items = Vec::new();
     for item in library_data {
-        let manga = get_manga_with_cache(
-            &item.manga_mangadex_id,
-            &state.db_pool,
-            &state.mangadex_client,
-            &state.mangadex_config,
-        )
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        // Bookmarks the source can't resolve right now stay in the list,
+        // carrying nothing but their IDs. So do ones that only resolved after
+        // the query ran, unless their rating is allowed.
+        let manga = mangas
+            .remove(&(item.source.clone(), item.manga_mangadex_id.clone()))
+            .filter(|manga| ContentRating::is_allowed(&manga.content_rating, &allowed));
 
         let progress = if let (Some(chapter_id), Some(page_number)) =
             (item.chapter_mangadex_id, item.page_number)
@@ -78,12 +359,149 @@ pub async fn get_library(
             None
         };
 
+        let reading_status = match (item.reading_status, item.status_updated_at) {
+            (Some(status), Some(updated_at)) => Some(MangaStatus {
+                status,
+                score: item.score,
+                started_on: item.started_on,
+                finished_on: item.finished_on,
+                notes: item.notes,
+                reread_count: item.reread_count.unwrap_or_default(),
+                updated_at,
+            }),
+            _ => None,
+        };
+
         library_items.push(LibraryItem {
+            source: item.source,
+            manga_mangadex_id: item.manga_mangadex_id,
             manga,
             progress,
             bookmarked_at: item.bookmark_created_at,
+            unread_count: item.unread_count,
+            category_ids: item.category_ids,
+            reading_status,
         });
     }
 
-    Ok(Json(library_items))
+    Ok(Json(LibraryResponse {
+        data: library_items,
+        next_cursor,
+    }))
+}
+
+/// Loads the user's bookmarked manga that aren't in `manga_cache` yet, so the
+/// library query can filter and sort on their details.
+async fn cache_bookmarked_manga(state: &AppState, user_id: Uuid) -> Result<(), StatusCode> {
+    let uncached = sqlx::query_as::<_, (String, String)>(
+        r#"
+        SELECT b.source, b.manga_mangadex_id
+        FROM user_bookmarks b
+        LEFT JOIN manga_cache m
+            ON m.source = b.source
+            AND m.mangadex_id = b.manga_mangadex_id
+        WHERE b.user_id = $1 AND m.id IS NULL
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(&state.db_pool)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if uncached.is_empty() {
+        return Ok(());
+    }
+
+    get_mangas_by_source(
+        &uncached,
+        &state.db_pool,
+        &state.hot_cache,
+        &state.sources,
+        &state.mangadex_config,
+    )
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(())
+}
+
+pub async fn get_unread_summary(
+    State(state): State<AppState>,
+    user: CurrentUser,
+) -> Result<Json<UnreadSummary>, StatusCode> {
+    let allowed = UserPreferences::load(&state.db_pool, user.id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .allowed_content_ratings(&state.mangadex_config.default_content_ratings);
+    let allowed_names: Vec<&str> = allowed.iter().map(|r| r.as_str()).collect();
+
+    let mut query = QueryBuilder::<Postgres>::new("");
+    push_library_cte(&mut query, user.id);
+    query.push(
+        "SELECT source, manga_mangadex_id, title, unread_count FROM library \
+         WHERE unread_count > 0 AND content_rating = ANY(",
+    );
+    query.push_bind(allowed_names);
+    query.push(") ORDER BY unread_count DESC, title ASC");
+
+    let data = query
+        .build_query_as::<UnreadManga>()
+        .fetch_all(&state.db_pool)
+        .await
+        .map_err(|err| {
+            tracing::error!("failed to count unread chapters for {}: {}", user.id, err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(UnreadSummary {
+        total_unread: data.iter().map(|m| m.unread_count).sum(),
+        data,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cursor(sort: LibrarySort, key: &str) -> LibraryCursor {
+        LibraryCursor {
+            sort,
+            key: key.to_string(),
+            id: Uuid::nil(),
+        }
+    }
+
+    #[test]
+    fn cursor_round_trips() {
+        let encoded = cursor(LibrarySort::Title, "Berserk").encode();
+        let decoded = LibraryCursor::decode(&encoded).unwrap();
+
+        assert_eq!(decoded.sort, LibrarySort::Title);
+        assert_eq!(decoded.key, "Berserk");
+        assert_eq!(decoded.id, Uuid::nil());
+    }
+
+    #[test]
+    fn cursor_rejects_garbage() {
+        assert!(LibraryCursor::decode("not a cursor").is_none());
+        assert!(LibraryCursor::decode(&URL_SAFE_NO_PAD.encode(b"{}")).is_none());
+    }
+
+    #[test]
+    fn cursor_key_must_match_sort_column() {
+        let mut query = QueryBuilder::<Postgres>::new("");
+
+        assert!(cursor(LibrarySort::Unread, "12")
+            .push_key(&mut query)
+            .is_some());
+        assert!(cursor(LibrarySort::Unread, "twelve")
+            .push_key(&mut query)
+            .is_none());
+        assert!(cursor(LibrarySort::Added, "2025-01-02T03:04:05+00:00")
+            .push_key(&mut query)
+            .is_some());
+        assert!(cursor(LibrarySort::Updated, "yesterday")
+            .push_key(&mut query)
+            .is_none());
+    }
 }
@@ -1,10 +1,18 @@
-use axum::{extract::State, Json};
-use serde::Serialize;
+use axum::{extract::State, http::StatusCode, Json};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use uuid::Uuid;
+use validator::Validate;
 
-use crate::auth::CurrentUser;
+use crate::auth::{hash_password, jwt, verify_password, AuthError, CurrentUser};
+use crate::http::routes::auth::register::{validate_password_strength, validate_username_format};
+use crate::mailer::Email;
 use crate::AppState;
 
+/// Hours an email change can be confirmed in.
+const EMAIL_CHANGE_TTL_HOURS: i64 = 24;
+
 #[derive(Serialize, FromRow)]
 pub struct UserResponse {
     pub id: String,
@@ -45,3 +53,368 @@ pub async fn get_me(
         None => Err(axum::http::StatusCode::NOT_FOUND),
     }
 }
+
+#[derive(Debug, Deserialize)]
+pub struct DeleteAccountRequest {
+    pub password: String,
+}
+
+#[derive(Serialize)]
+pub struct DeleteAccountResponse {
+    /// When the account is gone for good. Logging in before then restores it.
+    pub purge_after: DateTime<Utc>,
+}
+
+/// Schedules the account for deletion after the configured grace period and
+/// signs it out everywhere. Requires the current password.
+pub async fn delete_me(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    Json(req): Json<DeleteAccountRequest>,
+) -> Result<Json<DeleteAccountResponse>, AuthError> {
+    let password_hash: String =
+        sqlx::query_scalar("SELECT password FROM users WHERE id = $1 AND deleted_at IS NULL")
+            .bind(user.id)
+            .fetch_optional(&state.db_pool)
+            .await
+            .map_err(|e| AuthError::Internal(anyhow::anyhow!("Database error: {}", e)))?
+            .ok_or(AuthError::InvalidCredentials)?;
+
+    if !verify_password(&req.password, &password_hash)? {
+        return Err(AuthError::InvalidCredentials);
+    }
+
+    let mut tx = state
+        .db_pool
+        .begin()
+        .await
+        .map_err(|e| AuthError::Internal(anyhow::anyhow!("Database error: {}", e)))?;
+
+    let purge_after: DateTime<Utc> = sqlx::query_scalar(
+        r#"
+        UPDATE users
+        SET deleted_at = NOW(), purge_after = NOW() + $2 * INTERVAL '1 day'
+        WHERE id = $1
+        RETURNING purge_after
+        "#,
+    )
+    .bind(user.id)
+    .bind(state.auth_config.deletion_grace_days as f64)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| AuthError::Internal(anyhow::anyhow!("Failed to delete account: {}", e)))?;
+
+    sqlx::query(
+        r#"
+        UPDATE refresh_tokens
+        SET revoked_at = NOW()
+        WHERE user_id = $1 AND revoked_at IS NULL
+        "#,
+    )
+    .bind(user.id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| AuthError::Internal(anyhow::anyhow!("Failed to revoke tokens: {}", e)))?;
+
+    tx.commit()
+        .await
+        .map_err(|e| AuthError::Internal(anyhow::anyhow!("Database error: {}", e)))?;
+
+    tracing::info!(
+        "account {} scheduled for deletion at {}",
+        user.id,
+        purge_after
+    );
+
+    Ok(Json(DeleteAccountResponse { purge_after }))
+}
+
+fn database_error(e: sqlx::Error) -> AuthError {
+    AuthError::Internal(anyhow::anyhow!("Database error: {}", e))
+}
+
+/// Maps a unique violation on `users` to the same conflict errors as
+/// registration, for changes that race another account.
+fn conflict_error(e: sqlx::Error) -> AuthError {
+    match &e {
+        sqlx::Error::Database(db) if db.is_unique_violation() => match db.constraint() {
+            Some("users_email_key") => AuthError::EmailAlreadyExists,
+            _ => AuthError::UsernameAlreadyExists,
+        },
+        _ => database_error(e),
+    }
+}
+
+async fn password_hash(state: &AppState, user_id: Uuid) -> Result<String, AuthError> {
+    sqlx::query_scalar("SELECT password FROM users WHERE id = $1 AND deleted_at IS NULL")
+        .bind(user_id)
+        .fetch_optional(&state.db_pool)
+        .await
+        .map_err(database_error)?
+        .ok_or(AuthError::TokenInvalid)
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateProfileRequest {
+    #[validate(
+        length(min = 3, max = 30, message = "Username must be 3-30 characters"),
+        custom(function = "validate_username_format")
+    )]
+    pub username: Option<String>,
+}
+
+pub async fn update_me(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    Json(req): Json<UpdateProfileRequest>,
+) -> Result<Json<UserResponse>, AuthError> {
+    req.validate()
+        .map_err(|e| AuthError::ValidationError(e.to_string()))?;
+
+    if let Some(username) = &req.username {
+        let username_exists = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM users WHERE username = $1 AND id <> $2)",
+        )
+        .bind(username)
+        .bind(user.id)
+        .fetch_one(&state.db_pool)
+        .await
+        .map_err(database_error)?;
+
+        if username_exists {
+            return Err(AuthError::UsernameAlreadyExists);
+        }
+    }
+
+    let updated = sqlx::query_as::<_, UserResponse>(
+        r#"
+        UPDATE users
+        SET username = COALESCE($2, username), updated_at = NOW()
+        WHERE id = $1 AND deleted_at IS NULL
+        RETURNING
+            id::text AS id,
+            email,
+            username,
+            role::text AS role,
+            created_at::text AS created_at,
+            updated_at::text AS updated_at
+        "#,
+    )
+    .bind(user.id)
+    .bind(&req.username)
+    .fetch_optional(&state.db_pool)
+    .await
+    .map_err(conflict_error)?
+    .ok_or(AuthError::TokenInvalid)?;
+
+    Ok(Json(updated))
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ChangePasswordRequest {
+    pub current_password: String,
+
+    #[validate(
+        length(min = 8, message = "Password must be at least 8 characters"),
+        custom(function = "validate_password_strength")
+    )]
+    pub new_password: String,
+}
+
+/// Changes the password and signs out every session but the caller's.
+pub async fn change_password(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    Json(req): Json<ChangePasswordRequest>,
+) -> Result<StatusCode, AuthError> {
+    req.validate()
+        .map_err(|e| AuthError::ValidationError(e.to_string()))?;
+
+    let current_hash = password_hash(&state, user.id).await?;
+    if !verify_password(&req.current_password, &current_hash)? {
+        return Err(AuthError::InvalidCredentials);
+    }
+
+    let new_hash = hash_password(&req.new_password)?;
+
+    let mut tx = state.db_pool.begin().await.map_err(database_error)?;
+
+    sqlx::query("UPDATE users SET password = $2, updated_at = NOW() WHERE id = $1")
+        .bind(user.id)
+        .bind(&new_hash)
+        .execute(&mut *tx)
+        .await
+        .map_err(database_error)?;
+
+    sqlx::query(
+        r#"
+        UPDATE refresh_tokens
+        SET revoked_at = NOW()
+        WHERE user_id = $1 AND revoked_at IS NULL AND id <> $2
+        "#,
+    )
+    .bind(user.id)
+    .bind(user.session_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| AuthError::Internal(anyhow::anyhow!("Failed to revoke tokens: {}", e)))?;
+
+    tx.commit().await.map_err(database_error)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct EmailChangeRequest {
+    #[validate(email(message = "Invalid email format"))]
+    pub email: String,
+    pub password: String,
+}
+
+#[derive(Serialize)]
+pub struct EmailChangeResponse {
+    /// The address the confirmation was sent to.
+    pub email: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Starts changing the account's email. The change only applies once the
+/// token mailed to the new address comes back through
+/// [`verify_email_change`].
+pub async fn request_email_change(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    Json(req): Json<EmailChangeRequest>,
+) -> Result<(StatusCode, Json<EmailChangeResponse>), AuthError> {
+    req.validate()
+        .map_err(|e| AuthError::ValidationError(e.to_string()))?;
+
+    let mailer = state.mailer.as_ref().ok_or(AuthError::MailUnavailable)?;
+
+    let current_hash = password_hash(&state, user.id).await?;
+    if !verify_password(&req.password, &current_hash)? {
+        return Err(AuthError::InvalidCredentials);
+    }
+
+    let email_exists =
+        sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM users WHERE email = $1)")
+            .bind(&req.email)
+            .fetch_one(&state.db_pool)
+            .await
+            .map_err(database_error)?;
+
+    if email_exists {
+        return Err(AuthError::EmailAlreadyExists);
+    }
+
+    let token = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+    let expires_at = Utc::now() + Duration::hours(EMAIL_CHANGE_TTL_HOURS);
+
+    let mut tx = state.db_pool.begin().await.map_err(database_error)?;
+
+    // Only the latest request can be confirmed
+    sqlx::query("DELETE FROM email_change_requests WHERE user_id = $1")
+        .bind(user.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(database_error)?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO email_change_requests (user_id, new_email, token_hash, expires_at)
+        VALUES ($1, $2, $3, $4)
+        "#,
+    )
+    .bind(user.id)
+    .bind(&req.email)
+    .bind(jwt::hash_token(&token))
+    .bind(expires_at)
+    .execute(&mut *tx)
+    .await
+    .map_err(database_error)?;
+
+    // Kept only if the token actually went out
+    let email = Email {
+        to: req.email.clone(),
+        subject: "Confirm your new email address".to_string(),
+        body: format!(
+            "Enter this code to finish changing your email address:\n\n{}\n\n\
+             It expires in {} hours. If you didn't ask for this, ignore this email.",
+            token, EMAIL_CHANGE_TTL_HOURS
+        ),
+    };
+    mailer
+        .send(&email)
+        .await
+        .map_err(|e| AuthError::Internal(e.context("Failed to send verification email")))?;
+
+    tx.commit().await.map_err(database_error)?;
+
+    tracing::info!("email change for {} requested", user.id);
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(EmailChangeResponse {
+            email: req.email,
+            expires_at,
+        }),
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VerifyEmailRequest {
+    pub token: String,
+}
+
+pub async fn verify_email_change(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    Json(req): Json<VerifyEmailRequest>,
+) -> Result<Json<UserResponse>, AuthError> {
+    let mut tx = state.db_pool.begin().await.map_err(database_error)?;
+
+    let (new_email, expired) = sqlx::query_as::<_, (String, bool)>(
+        r#"
+        DELETE FROM email_change_requests
+        WHERE user_id = $1 AND token_hash = $2
+        RETURNING new_email, expires_at < NOW()
+        "#,
+    )
+    .bind(user.id)
+    .bind(jwt::hash_token(&req.token))
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(database_error)?
+    .ok_or(AuthError::TokenInvalid)?;
+
+    if expired {
+        // Drop the stale request rather than keep it around
+        tx.commit().await.map_err(database_error)?;
+        return Err(AuthError::TokenExpired);
+    }
+
+    let updated = sqlx::query_as::<_, UserResponse>(
+        r#"
+        UPDATE users
+        SET email = $2, updated_at = NOW()
+        WHERE id = $1 AND deleted_at IS NULL
+        RETURNING
+            id::text AS id,
+            email,
+            username,
+            role::text AS role,
+            created_at::text AS created_at,
+            updated_at::text AS updated_at
+        "#,
+    )
+    .bind(user.id)
+    .bind(&new_email)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(conflict_error)?
+    .ok_or(AuthError::TokenInvalid)?;
+
+    tx.commit().await.map_err(database_error)?;
+
+    Ok(Json(updated))
+}
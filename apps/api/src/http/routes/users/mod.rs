@@ -1,5 +1,13 @@
 pub mod bookmarks;
+pub mod categories;
+pub mod export;
+pub mod feed;
 pub mod history;
+pub mod import;
 pub mod library;
 pub mod me;
+pub mod notifications;
+pub mod preferences;
 pub mod progress;
+pub mod status;
+pub mod trackers;
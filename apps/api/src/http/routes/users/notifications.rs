@@ -0,0 +1,145 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use crate::auth::CurrentUser;
+use crate::AppState;
+
+#[derive(Serialize, FromRow)]
+pub struct Notification {
+    pub id: String,
+    pub source: String,
+    pub manga_mangadex_id: String,
+    pub manga_title: Option<String>,
+    pub chapter_mangadex_id: String,
+    pub chapter_number: Option<String>,
+    pub chapter_title: Option<String>,
+    pub created_at: String,
+    pub read_at: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct NotificationsResponse {
+    pub data: Vec<Notification>,
+    pub unread_count: i64,
+}
+
+#[derive(Deserialize)]
+pub struct NotificationsQuery {
+    #[serde(default)]
+    pub unread_only: bool,
+    #[serde(default = "default_limit")]
+    pub limit: i64,
+    #[serde(default)]
+    pub offset: i64,
+}
+
+fn default_limit() -> i64 {
+    50
+}
+
+#[derive(Deserialize)]
+pub struct UpdateNotificationsRequest {
+    /// Notifications to update; every notification when omitted.
+    #[serde(default)]
+    pub ids: Option<Vec<Uuid>>,
+    #[serde(default = "default_read")]
+    pub read: bool,
+}
+
+fn default_read() -> bool {
+    true
+}
+
+#[derive(Serialize)]
+pub struct UpdateNotificationsResponse {
+    pub updated: u64,
+    pub unread_count: i64,
+}
+
+pub async fn get_notifications(
+    Query(params): Query<NotificationsQuery>,
+    State(state): State<AppState>,
+    user: CurrentUser,
+) -> Result<Json<NotificationsResponse>, StatusCode> {
+    let notifications = sqlx::query_as::<_, Notification>(
+        r#"
+        SELECT
+            n.id::text AS id,
+            n.source,
+            n.manga_mangadex_id,
+            m.title AS manga_title,
+            n.chapter_mangadex_id,
+            c.chapter_number,
+            c.title AS chapter_title,
+            n.created_at::text AS created_at,
+            n.read_at::text AS read_at
+        FROM notifications n
+        LEFT JOIN manga_cache m
+            ON m.source = n.source
+            AND m.mangadex_id = n.manga_mangadex_id
+        LEFT JOIN chapter_cache c
+            ON c.source = n.source
+            AND c.mangadex_id = n.chapter_mangadex_id
+        WHERE n.user_id = $1
+            AND (NOT $2 OR n.read_at IS NULL)
+        ORDER BY n.created_at DESC
+        LIMIT $3 OFFSET $4
+        "#,
+    )
+    .bind(user.id)
+    .bind(params.unread_only)
+    .bind(params.limit.clamp(1, 100))
+    .bind(params.offset.max(0))
+    .fetch_all(&state.db_pool)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let unread_count = unread_count(&state, &user).await?;
+
+    Ok(Json(NotificationsResponse {
+        data: notifications,
+        unread_count,
+    }))
+}
+
+pub async fn update_notifications(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    Json(payload): Json<UpdateNotificationsRequest>,
+) -> Result<Json<UpdateNotificationsResponse>, StatusCode> {
+    let result = sqlx::query(
+        r#"
+        UPDATE notifications
+        SET read_at = CASE WHEN $2 THEN COALESCE(read_at, NOW()) END
+        WHERE user_id = $1
+            AND ($3::uuid[] IS NULL OR id = ANY($3))
+        "#,
+    )
+    .bind(user.id)
+    .bind(payload.read)
+    .bind(payload.ids)
+    .execute(&state.db_pool)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let unread_count = unread_count(&state, &user).await?;
+
+    Ok(Json(UpdateNotificationsResponse {
+        updated: result.rows_affected(),
+        unread_count,
+    }))
+}
+
+async fn unread_count(state: &AppState, user: &CurrentUser) -> Result<i64, StatusCode> {
+    sqlx::query_scalar("SELECT COUNT(*) FROM notifications WHERE user_id = $1 AND read_at IS NULL")
+        .bind(user.id)
+        .fetch_one(&state.db_pool)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
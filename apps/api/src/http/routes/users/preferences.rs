@@ -0,0 +1,93 @@
+use axum::{extract::State, http::StatusCode, Json};
+use serde::Deserialize;
+use validator::Validate;
+
+use crate::auth::CurrentUser;
+use crate::source::filters::ContentRating;
+use crate::users::{is_language_code, ReadingDirection, UserPreferences};
+use crate::AppState;
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdatePreferencesRequest {
+    #[validate(length(min = 1, max = 20))]
+    pub translated_languages: Vec<String>,
+    #[validate(length(max = 50))]
+    #[serde(default)]
+    pub scanlation_groups: Vec<String>,
+    #[serde(default)]
+    pub content_rating_ceiling: Option<ContentRating>,
+    #[serde(default)]
+    pub mature_content: bool,
+    /// Must be set when turning `mature_content` on.
+    #[serde(default)]
+    pub confirm_adult: bool,
+    #[serde(default)]
+    pub data_saver: bool,
+    #[serde(default)]
+    pub reading_direction: ReadingDirection,
+}
+
+pub async fn get_preferences(
+    State(state): State<AppState>,
+    user: CurrentUser,
+) -> Result<Json<UserPreferences>, StatusCode> {
+    let preferences = UserPreferences::load(&state.db_pool, user.id)
+        .await
+        .map_err(|err| {
+            tracing::error!("failed to load preferences for {}: {}", user.id, err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(preferences))
+}
+
+pub async fn update_preferences(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    Json(payload): Json<UpdatePreferencesRequest>,
+) -> Result<Json<UserPreferences>, StatusCode> {
+    payload.validate().map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    if !payload
+        .translated_languages
+        .iter()
+        .all(|lang| is_language_code(lang))
+        || payload
+            .scanlation_groups
+            .iter()
+            .any(|value| value.trim().is_empty() || value.len() > 64)
+    {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let current = UserPreferences::load(&state.db_pool, user.id)
+        .await
+        .map_err(|err| {
+            tracing::error!("failed to load preferences for {}: {}", user.id, err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    // Age gate: opting in needs an explicit confirmation, staying in does not
+    if payload.mature_content && !current.mature_content && !payload.confirm_adult {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let preferences = UserPreferences {
+        translated_languages: payload.translated_languages,
+        scanlation_groups: payload.scanlation_groups,
+        content_rating_ceiling: payload.content_rating_ceiling,
+        mature_content: payload.mature_content,
+        data_saver: payload.data_saver,
+        reading_direction: payload.reading_direction,
+    };
+
+    preferences
+        .save(&state.db_pool, user.id)
+        .await
+        .map_err(|err| {
+            tracing::error!("failed to save preferences for {}: {}", user.id, err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(preferences))
+}
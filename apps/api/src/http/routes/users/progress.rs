@@ -7,10 +7,12 @@ use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 
 use crate::auth::CurrentUser;
+use crate::trackers::sync::enqueue_and_push;
 use crate::AppState;
 
 #[derive(Serialize, FromRow)]
 pub struct ReadingProgress {
+    pub source: String,
     pub manga_mangadex_id: String,
     pub chapter_mangadex_id: String,
     pub page_number: i32,
@@ -30,6 +32,7 @@ pub async fn get_all_progress(
     let progress = sqlx::query_as::<_, ReadingProgress>(
         r#"
         SELECT 
+            source,
             manga_mangadex_id,
             chapter_mangadex_id,
             page_number,
@@ -48,22 +51,26 @@ pub async fn get_all_progress(
 }
 
 pub async fn get_progress(
-    Path(manga_mangadex_id): Path<String>,
+    Path(manga_id): Path<String>,
     State(state): State<AppState>,
     user: CurrentUser,
 ) -> Result<Json<ReadingProgress>, StatusCode> {
+    let (source, manga_mangadex_id) = state.sources.resolve(&manga_id);
+
     let progress = sqlx::query_as::<_, ReadingProgress>(
         r#"
         SELECT 
+            source,
             manga_mangadex_id,
             chapter_mangadex_id,
             page_number,
             updated_at::text AS updated_at
         FROM user_reading_progress
-        WHERE user_id = $1 AND manga_mangadex_id = $2
+        WHERE user_id = $1 AND source = $2 AND manga_mangadex_id = $3
         "#,
     )
     .bind(user.id)
+    .bind(source.id())
     .bind(&manga_mangadex_id)
     .fetch_optional(&state.db_pool)
     .await
@@ -76,16 +83,24 @@ pub async fn get_progress(
 }
 
 pub async fn update_progress(
-    Path(manga_mangadex_id): Path<String>,
+    Path(manga_id): Path<String>,
     State(state): State<AppState>,
     user: CurrentUser,
     Json(req): Json<UpdateProgressRequest>,
 ) -> Result<StatusCode, StatusCode> {
+    let (source, manga_mangadex_id) = state.sources.resolve(&manga_id);
+    let (chapter_source, chapter_mangadex_id) = state.sources.resolve(&req.chapter_id);
+
+    // A chapter from another source can't belong to this manga
+    if chapter_source.id() != source.id() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
     sqlx::query(
         r#"
-        INSERT INTO user_reading_progress (user_id, manga_mangadex_id, chapter_mangadex_id, page_number)
-        VALUES ($1, $2, $3, $4)
-        ON CONFLICT (user_id, manga_mangadex_id) 
+        INSERT INTO user_reading_progress (user_id, source, manga_mangadex_id, chapter_mangadex_id, page_number)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (user_id, source, manga_mangadex_id) 
         DO UPDATE SET 
             chapter_mangadex_id = EXCLUDED.chapter_mangadex_id,
             page_number = EXCLUDED.page_number,
@@ -93,12 +108,15 @@ pub async fn update_progress(
         "#,
     )
     .bind(user.id)
+    .bind(source.id())
     .bind(&manga_mangadex_id)
-    .bind(&req.chapter_id)
+    .bind(&chapter_mangadex_id)
     .bind(req.page_number as i32)
     .execute(&state.db_pool)
     .await
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
+    enqueue_and_push(&state, user.id, source.id(), &manga_mangadex_id).await;
+
     Ok(StatusCode::NO_CONTENT)
 }
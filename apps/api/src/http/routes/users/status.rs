@@ -0,0 +1,154 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use validator::Validate;
+
+use crate::auth::CurrentUser;
+use crate::trackers::sync::enqueue_and_push;
+use crate::users::status::{MangaStatus, ReadingStatus};
+use crate::AppState;
+
+#[derive(Serialize, FromRow)]
+pub struct MangaStatusEntry {
+    pub source: String,
+    pub manga_mangadex_id: String,
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub entry: MangaStatus,
+}
+
+#[derive(Deserialize)]
+pub struct StatusListQuery {
+    #[serde(default)]
+    pub status: Option<ReadingStatus>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateStatusRequest {
+    pub status: ReadingStatus,
+    #[validate(range(min = 1, max = 10))]
+    #[serde(default)]
+    pub score: Option<i16>,
+    #[serde(default)]
+    pub started_on: Option<NaiveDate>,
+    #[serde(default)]
+    pub finished_on: Option<NaiveDate>,
+    #[validate(length(max = 10000))]
+    #[serde(default)]
+    pub notes: Option<String>,
+    #[validate(range(min = 0))]
+    #[serde(default)]
+    pub reread_count: i32,
+}
+
+pub async fn get_all_status(
+    Query(params): Query<StatusListQuery>,
+    State(state): State<AppState>,
+    user: CurrentUser,
+) -> Result<Json<Vec<MangaStatusEntry>>, StatusCode> {
+    let entries = sqlx::query_as::<_, MangaStatusEntry>(
+        r#"
+        SELECT
+            source,
+            manga_mangadex_id,
+            status,
+            score,
+            started_on,
+            finished_on,
+            notes,
+            reread_count,
+            updated_at
+        FROM user_manga_status
+        WHERE user_id = $1 AND ($2::reading_status IS NULL OR status = $2)
+        ORDER BY updated_at DESC
+        "#,
+    )
+    .bind(user.id)
+    .bind(params.status)
+    .fetch_all(&state.db_pool)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(entries))
+}
+
+pub async fn get_status(
+    Path(manga_id): Path<String>,
+    State(state): State<AppState>,
+    user: CurrentUser,
+) -> Result<Json<MangaStatus>, StatusCode> {
+    let (source, manga_mangadex_id) = state.sources.resolve(&manga_id);
+
+    MangaStatus::load(&state.db_pool, user.id, source.id(), &manga_mangadex_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+pub async fn update_status(
+    Path(manga_id): Path<String>,
+    State(state): State<AppState>,
+    user: CurrentUser,
+    Json(req): Json<UpdateStatusRequest>,
+) -> Result<Json<MangaStatus>, StatusCode> {
+    req.validate().map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    if let (Some(started_on), Some(finished_on)) = (req.started_on, req.finished_on) {
+        if finished_on < started_on {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    }
+
+    let (source, manga_mangadex_id) = state.sources.resolve(&manga_id);
+
+    let entry = MangaStatus {
+        status: req.status,
+        score: req.score,
+        started_on: req.started_on,
+        finished_on: req.finished_on,
+        notes: req.notes.filter(|notes| !notes.trim().is_empty()),
+        reread_count: req.reread_count,
+        updated_at: chrono::Utc::now(),
+    };
+
+    let saved = entry
+        .save(&state.db_pool, user.id, source.id(), &manga_mangadex_id)
+        .await
+        .map_err(|err| {
+            tracing::error!("failed to save status for {}: {}", user.id, err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    enqueue_and_push(&state, user.id, source.id(), &manga_mangadex_id).await;
+
+    Ok(Json(saved))
+}
+
+pub async fn remove_status(
+    Path(manga_id): Path<String>,
+    State(state): State<AppState>,
+    user: CurrentUser,
+) -> Result<StatusCode, StatusCode> {
+    let (source, manga_mangadex_id) = state.sources.resolve(&manga_id);
+
+    sqlx::query(
+        r#"
+        DELETE FROM user_manga_status
+        WHERE user_id = $1 AND source = $2 AND manga_mangadex_id = $3
+        "#,
+    )
+    .bind(user.id)
+    .bind(source.id())
+    .bind(&manga_mangadex_id)
+    .execute(&state.db_pool)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
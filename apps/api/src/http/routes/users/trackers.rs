@@ -0,0 +1,194 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use crate::auth::CurrentUser;
+use crate::trackers::{sync::save_token, TrackerError};
+use crate::AppState;
+
+/// Minutes a user has to finish authorizing on the tracker's site.
+const AUTHORIZE_TTL_MINUTES: i32 = 15;
+
+#[derive(Serialize, FromRow)]
+pub struct TrackerLink {
+    pub tracker: String,
+    pub linked_at: Option<String>,
+    pub expires_at: Option<String>,
+    /// Pushes still waiting to be sent.
+    pub pending: i64,
+    /// Pushes that gave up after too many attempts.
+    pub failed: i64,
+}
+
+#[derive(Serialize)]
+pub struct AuthorizeResponse {
+    pub url: String,
+}
+
+#[derive(Deserialize)]
+pub struct CallbackRequest {
+    pub code: String,
+    pub state: String,
+}
+
+/// Every configured tracker, with the user's link to it if any.
+pub async fn get_trackers(
+    State(state): State<AppState>,
+    user: CurrentUser,
+) -> Result<Json<Vec<TrackerLink>>, StatusCode> {
+    let trackers: Vec<String> = state.trackers.ids().into_iter().map(String::from).collect();
+
+    let links = sqlx::query_as::<_, TrackerLink>(
+        r#"
+        SELECT
+            t.tracker,
+            a.created_at::text AS linked_at,
+            a.expires_at::text AS expires_at,
+            (SELECT COUNT(*) FROM tracker_sync_queue q
+                WHERE q.user_id = $1 AND q.tracker = t.tracker AND NOT q.failed) AS pending,
+            (SELECT COUNT(*) FROM tracker_sync_queue q
+                WHERE q.user_id = $1 AND q.tracker = t.tracker AND q.failed) AS failed
+        FROM UNNEST($2::text[]) AS t(tracker)
+        LEFT JOIN tracker_accounts a ON a.user_id = $1 AND a.tracker = t.tracker
+        ORDER BY t.tracker
+        "#,
+    )
+    .bind(user.id)
+    .bind(&trackers)
+    .fetch_all(&state.db_pool)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(links))
+}
+
+/// Starts linking a tracker account. The client sends the user to `url`;
+/// the tracker redirects back with a code and state for [`link_callback`].
+pub async fn authorize_tracker(
+    Path(tracker_id): Path<String>,
+    State(state): State<AppState>,
+    user: CurrentUser,
+) -> Result<Json<AuthorizeResponse>, StatusCode> {
+    let tracker = state
+        .trackers
+        .get(&tracker_id)
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let oauth_state = uuid::Uuid::new_v4().simple().to_string();
+    // 64 unreserved characters, within PKCE's 43-128
+    let code_verifier = format!(
+        "{}{}",
+        uuid::Uuid::new_v4().simple(),
+        uuid::Uuid::new_v4().simple()
+    );
+
+    sqlx::query("DELETE FROM tracker_oauth_states WHERE expires_at < NOW()")
+        .execute(&state.db_pool)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO tracker_oauth_states (state, user_id, tracker, code_verifier, expires_at)
+        VALUES ($1, $2, $3, $4, NOW() + make_interval(mins => $5))
+        "#,
+    )
+    .bind(&oauth_state)
+    .bind(user.id)
+    .bind(tracker.id())
+    .bind(&code_verifier)
+    .bind(AUTHORIZE_TTL_MINUTES)
+    .execute(&state.db_pool)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(AuthorizeResponse {
+        url: tracker.authorize_url(&oauth_state, &code_verifier),
+    }))
+}
+
+pub async fn link_callback(
+    Path(tracker_id): Path<String>,
+    State(state): State<AppState>,
+    user: CurrentUser,
+    Json(req): Json<CallbackRequest>,
+) -> Result<StatusCode, StatusCode> {
+    let tracker = state
+        .trackers
+        .get(&tracker_id)
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    // Single use: the state row is consumed whether or not the exchange works
+    let code_verifier: Option<String> = sqlx::query_scalar(
+        r#"
+        DELETE FROM tracker_oauth_states
+        WHERE state = $1 AND user_id = $2 AND tracker = $3 AND expires_at > NOW()
+        RETURNING code_verifier
+        "#,
+    )
+    .bind(&req.state)
+    .bind(user.id)
+    .bind(tracker.id())
+    .fetch_optional(&state.db_pool)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let code_verifier = code_verifier.ok_or(StatusCode::BAD_REQUEST)?;
+
+    let token = tracker
+        .exchange_code(&req.code, &code_verifier)
+        .await
+        .map_err(|err| {
+            tracing::warn!(
+                "{} code exchange for {} failed: {}",
+                tracker.id(),
+                user.id,
+                err
+            );
+            match err {
+                TrackerError::Unauthorized | TrackerError::ApiError(_) => StatusCode::BAD_REQUEST,
+                _ => StatusCode::BAD_GATEWAY,
+            }
+        })?;
+
+    save_token(&state.db_pool, user.id, tracker.id(), &token)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn unlink_tracker(
+    Path(tracker_id): Path<String>,
+    State(state): State<AppState>,
+    user: CurrentUser,
+) -> Result<StatusCode, StatusCode> {
+    let mut tx = state
+        .db_pool
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    sqlx::query("DELETE FROM tracker_sync_queue WHERE user_id = $1 AND tracker = $2")
+        .bind(user.id)
+        .bind(&tracker_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    sqlx::query("DELETE FROM tracker_accounts WHERE user_id = $1 AND tracker = $2")
+        .bind(user.id)
+        .bind(&tracker_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    tx.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
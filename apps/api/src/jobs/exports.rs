@@ -0,0 +1,35 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use super::Job;
+use crate::users::export::process_pending;
+use crate::AppState;
+
+/// Exports built per run; the rest wait for the next one.
+const BATCH_SIZE: i64 = 5;
+
+/// Finishes account exports whose builder died and deletes expired ones.
+pub struct ProcessExports {
+    pub interval: Duration,
+}
+
+#[async_trait]
+impl Job for ProcessExports {
+    fn name(&self) -> &'static str {
+        "process_exports"
+    }
+
+    fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    async fn run(&self, state: &AppState) -> anyhow::Result<String> {
+        let summary = process_pending(state, BATCH_SIZE).await?;
+
+        Ok(format!(
+            "built {}, failed {}, abandoned {}, purged {}",
+            summary.built, summary.failed, summary.abandoned, summary.purged
+        ))
+    }
+}
@@ -0,0 +1,117 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use super::Job;
+use crate::AppState;
+
+/// Deletes refresh tokens that can no longer be used.
+pub struct PurgeRefreshTokens {
+    pub interval: Duration,
+}
+
+#[async_trait]
+impl Job for PurgeRefreshTokens {
+    fn name(&self) -> &'static str {
+        "purge_refresh_tokens"
+    }
+
+    fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    async fn run(&self, state: &AppState) -> anyhow::Result<String> {
+        let result = sqlx::query("DELETE FROM refresh_tokens WHERE expires_at < NOW()")
+            .execute(&state.db_pool)
+            .await?;
+
+        Ok(format!(
+            "purged {} expired refresh tokens",
+            result.rows_affected()
+        ))
+    }
+}
+
+/// Deletes accounts whose deletion grace period is over. Everything the
+/// user owns goes with them through `ON DELETE CASCADE`.
+pub struct PurgeDeletedAccounts {
+    pub interval: Duration,
+}
+
+#[async_trait]
+impl Job for PurgeDeletedAccounts {
+    fn name(&self) -> &'static str {
+        "purge_deleted_accounts"
+    }
+
+    fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    async fn run(&self, state: &AppState) -> anyhow::Result<String> {
+        let result = sqlx::query("DELETE FROM users WHERE purge_after < NOW()")
+            .execute(&state.db_pool)
+            .await?;
+
+        Ok(format!(
+            "purged {} deleted accounts",
+            result.rows_affected()
+        ))
+    }
+}
+
+/// Deletes cache rows nobody has needed for a while. Bookmarked series are
+/// kept since the chapter poller relies on their cached chapter lists.
+pub struct PruneCache {
+    pub interval: Duration,
+    pub after_days: i64,
+}
+
+#[async_trait]
+impl Job for PruneCache {
+    fn name(&self) -> &'static str {
+        "prune_cache"
+    }
+
+    fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    async fn run(&self, state: &AppState) -> anyhow::Result<String> {
+        let chapters = sqlx::query(
+            r#"
+            DELETE FROM chapter_cache c
+            WHERE c.cached_at < NOW() - $1 * INTERVAL '1 day'
+                AND NOT EXISTS (
+                    SELECT 1 FROM user_bookmarks b
+                    WHERE b.source = c.source
+                        AND b.manga_mangadex_id = c.manga_mangadex_id
+                )
+            "#,
+        )
+        .bind(self.after_days as f64)
+        .execute(&state.db_pool)
+        .await?;
+
+        let manga = sqlx::query(
+            r#"
+            DELETE FROM manga_cache m
+            WHERE m.cached_at < NOW() - $1 * INTERVAL '1 day'
+                AND NOT EXISTS (
+                    SELECT 1 FROM user_bookmarks b
+                    WHERE b.source = m.source
+                        AND b.manga_mangadex_id = m.mangadex_id
+                )
+            "#,
+        )
+        .bind(self.after_days as f64)
+        .execute(&state.db_pool)
+        .await?;
+
+        Ok(format!(
+            "pruned {} manga and {} chapters",
+            manga.rows_affected(),
+            chapters.rows_affected()
+        ))
+    }
+}
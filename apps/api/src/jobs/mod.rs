@@ -0,0 +1,61 @@
+mod exports;
+mod maintenance;
+mod refresh;
+pub mod scheduler;
+mod trackers;
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use crate::config::AppConfig;
+use crate::AppState;
+
+pub use scheduler::Scheduler;
+
+/// A periodic background task run by the [`Scheduler`].
+#[async_trait]
+pub trait Job: Send + Sync {
+    /// Stable name, used as the key in the `jobs` table.
+    fn name(&self) -> &'static str;
+
+    fn interval(&self) -> Duration;
+
+    /// Runs the job once, returning a short summary for the log.
+    async fn run(&self, state: &AppState) -> anyhow::Result<String>;
+}
+
+/// The scheduler with every built-in job registered.
+pub fn scheduler(config: &AppConfig) -> Scheduler {
+    let jobs = &config.jobs;
+    let hours = |h: u64| Duration::from_secs(h.max(1) * 3600);
+    let minutes = |m: u64| Duration::from_secs(m.max(1) * 60);
+
+    Scheduler::new(Duration::from_secs(jobs.tick_secs.max(1)))
+        .with_job(refresh::PrewarmPopular {
+            interval: minutes(jobs.prewarm_interval_minutes),
+            count: jobs.prewarm_count,
+        })
+        .with_job(refresh::PollBookmarkedChapters {
+            interval: minutes(config.mangadex.chapter_poll_interval_minutes),
+        })
+        .with_job(refresh::RefreshTags {
+            interval: hours(config.mangadex.tag_refresh_interval_hours),
+        })
+        .with_job(trackers::SyncTrackers {
+            interval: Duration::from_secs(config.trackers.sync_interval_secs.max(1)),
+        })
+        .with_job(exports::ProcessExports {
+            interval: Duration::from_secs(config.exports.process_interval_secs.max(1)),
+        })
+        .with_job(maintenance::PurgeRefreshTokens {
+            interval: hours(jobs.token_purge_interval_hours),
+        })
+        .with_job(maintenance::PurgeDeletedAccounts {
+            interval: hours(jobs.account_purge_interval_hours),
+        })
+        .with_job(maintenance::PruneCache {
+            interval: hours(jobs.cache_prune_interval_hours),
+            after_days: jobs.cache_prune_after_days,
+        })
+}
@@ -0,0 +1,92 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use super::Job;
+use crate::mangadex::cache::{refresh_tags, store_manga};
+use crate::users::notifications::poll_bookmarked_chapters;
+use crate::AppState;
+
+/// Pulls the most popular manga into `manga_cache` so the first visitor
+/// doesn't pay for the MangaDex round trip.
+pub struct PrewarmPopular {
+    pub interval: Duration,
+    pub count: u32,
+}
+
+#[async_trait]
+impl Job for PrewarmPopular {
+    fn name(&self) -> &'static str {
+        "prewarm_popular"
+    }
+
+    fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    async fn run(&self, state: &AppState) -> anyhow::Result<String> {
+        let source = state.sources.default_source();
+        let ratings = &state.mangadex_config.default_content_ratings;
+
+        let mut cached = 0;
+        let mut offset = 0;
+        while offset < self.count {
+            let limit = (self.count - offset).min(100);
+            let page = source.popular(ratings, limit, offset).await?;
+            if page.data.is_empty() {
+                break;
+            }
+
+            for manga in &page.data {
+                store_manga(&state.db_pool, &state.hot_cache, manga).await?;
+                cached += 1;
+            }
+            offset += limit;
+        }
+
+        Ok(format!("cached {} popular manga", cached))
+    }
+}
+
+/// Refreshes chapter lists of bookmarked series and notifies their readers
+/// of new chapters.
+pub struct PollBookmarkedChapters {
+    pub interval: Duration,
+}
+
+#[async_trait]
+impl Job for PollBookmarkedChapters {
+    fn name(&self) -> &'static str {
+        "poll_bookmarked_chapters"
+    }
+
+    fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    async fn run(&self, state: &AppState) -> anyhow::Result<String> {
+        let created =
+            poll_bookmarked_chapters(&state.db_pool, &state.hot_cache, &state.sources).await?;
+        Ok(format!("created {} notifications", created))
+    }
+}
+
+pub struct RefreshTags {
+    pub interval: Duration,
+}
+
+#[async_trait]
+impl Job for RefreshTags {
+    fn name(&self) -> &'static str {
+        "refresh_tags"
+    }
+
+    fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    async fn run(&self, state: &AppState) -> anyhow::Result<String> {
+        let tags = refresh_tags(&state.db_pool, &state.mangadex_client).await?;
+        Ok(format!("refreshed {} tags", tags.len()))
+    }
+}
@@ -0,0 +1,323 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use sqlx::{PgConnection, PgPool};
+use tokio::task::JoinHandle;
+
+use super::Job;
+use crate::AppState;
+
+/// Key for the session-level advisory lock held by the leader instance.
+const LEADER_LOCK_KEY: i64 = 0x6a6f_6273;
+
+/// Runs registered jobs on their intervals.
+///
+/// Every instance runs a scheduler, but only the one holding the advisory
+/// lock runs jobs; the others keep trying to take it over. Schedules live in
+/// the `jobs` table so a new leader carries on where the previous one left
+/// off.
+pub struct Scheduler {
+    tick: Duration,
+    jobs: Vec<Arc<dyn Job>>,
+}
+
+impl Scheduler {
+    pub fn new(tick: Duration) -> Self {
+        Self {
+            tick,
+            jobs: Vec::new(),
+        }
+    }
+
+    pub fn with_job(mut self, job: impl Job + 'static) -> Self {
+        self.jobs.push(Arc::new(job));
+        self
+    }
+
+    /// Makes sure every job has a row, picking up interval changes.
+    pub async fn register_all(self, db: &PgPool) -> Result<Self, sqlx::Error> {
+        for job in &self.jobs {
+            sqlx::query(
+                r#"
+                INSERT INTO jobs (name, interval_secs)
+                VALUES ($1, $2)
+                ON CONFLICT (name) DO UPDATE SET
+                    interval_secs = EXCLUDED.interval_secs,
+                    next_run_at = LEAST(
+                        jobs.next_run_at,
+                        NOW() + EXCLUDED.interval_secs * INTERVAL '1 second'
+                    )
+                "#,
+            )
+            .bind(job.name())
+            .bind(job.interval().as_secs() as i64)
+            .execute(db)
+            .await?;
+        }
+
+        Ok(self)
+    }
+
+    pub fn spawn(self, state: AppState) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(self.tick);
+            let mut leader = Leader::default();
+            let mut running: HashMap<&'static str, JoinHandle<()>> = HashMap::new();
+
+            loop {
+                interval.tick().await;
+
+                if !leader.check(&state.db_pool).await {
+                    continue;
+                }
+
+                // Each job gets its own task so a slow one doesn't hold up
+                // the rest; a job still busy with its last run is skipped
+                for job in &self.jobs {
+                    if running
+                        .get(job.name())
+                        .is_some_and(|run| !run.is_finished())
+                    {
+                        continue;
+                    }
+
+                    match claim(&state.db_pool, job.name()).await {
+                        Ok(true) => {}
+                        Ok(false) => continue,
+                        Err(e) => {
+                            tracing::warn!("Failed to claim job {}: {}", job.name(), e);
+                            continue;
+                        }
+                    }
+
+                    let (job, state) = (job.clone(), state.clone());
+                    let name = job.name();
+                    let run = tokio::spawn(async move { run(job.as_ref(), &state).await });
+                    running.insert(name, run);
+                }
+            }
+        })
+    }
+}
+
+/// Claims a job whose `next_run_at` has passed by pushing it one interval
+/// out. Returns false if it isn't due, including when another run claimed it.
+async fn claim(db: &PgPool, name: &str) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        r#"
+        UPDATE jobs
+        SET next_run_at = NOW() + interval_secs * INTERVAL '1 second',
+            last_started_at = NOW()
+        WHERE name = $1 AND next_run_at <= NOW()
+        "#,
+    )
+    .bind(name)
+    .execute(db)
+    .await?;
+
+    Ok(result.rows_affected() == 1)
+}
+
+/// Runs a claimed job and records how it went.
+async fn run(job: &dyn Job, state: &AppState) {
+    let started = Instant::now();
+    let outcome = job.run(state).await;
+
+    let error = match &outcome {
+        Ok(summary) => {
+            tracing::info!(
+                "Job {} finished in {:?}: {}",
+                job.name(),
+                started.elapsed(),
+                summary
+            );
+            None
+        }
+        Err(e) => {
+            tracing::warn!("Job {} failed: {:#}", job.name(), e);
+            Some(format!("{:#}", e))
+        }
+    };
+
+    let recorded = sqlx::query(
+        r#"
+        UPDATE jobs
+        SET last_finished_at = NOW(),
+            last_status = $2,
+            last_error = $3,
+            run_count = run_count + 1
+        WHERE name = $1
+        "#,
+    )
+    .bind(job.name())
+    .bind(if error.is_none() { "ok" } else { "failed" })
+    .bind(error)
+    .execute(&state.db_pool)
+    .await;
+
+    if let Err(e) = recorded {
+        tracing::warn!("Failed to record outcome of job {}: {}", job.name(), e);
+    }
+}
+
+/// The leader lock and the connection it lives on.
+///
+/// The connection is detached from the pool so the session-level lock is
+/// released when it is dropped rather than lingering on a pooled connection.
+/// Followers keep the same connection and retry the lock on it each tick.
+#[derive(Default)]
+struct Leader {
+    conn: Option<PgConnection>,
+    is_leader: bool,
+}
+
+impl Leader {
+    /// Whether this instance holds the lock, trying to take it if not.
+    async fn check(&mut self, db: &PgPool) -> bool {
+        let conn = match self.conn.as_mut() {
+            Some(conn) => conn,
+            None => match db.acquire().await {
+                Ok(conn) => self.conn.insert(conn.detach()),
+                Err(e) => {
+                    tracing::warn!(
+                        "Failed to acquire connection for job leader election: {}",
+                        e
+                    );
+                    return false;
+                }
+            },
+        };
+
+        if self.is_leader {
+            // Losing the connection releases the lock, so another instance
+            // may already have taken over
+            if sqlx::query("SELECT 1").execute(&mut *conn).await.is_err() {
+                tracing::warn!("Lost job leader connection");
+                self.conn = None;
+                self.is_leader = false;
+            }
+            return self.is_leader;
+        }
+
+        match sqlx::query_scalar("SELECT pg_try_advisory_lock($1)")
+            .bind(LEADER_LOCK_KEY)
+            .fetch_one(&mut *conn)
+            .await
+        {
+            Ok(true) => {
+                tracing::info!("This instance is now the background job leader");
+                self.is_leader = true;
+            }
+            Ok(false) => {}
+            Err(e) => {
+                tracing::warn!("Job leader election failed: {}", e);
+                self.conn = None;
+            }
+        }
+
+        self.is_leader
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+    use async_trait::async_trait;
+
+    struct Noop(&'static str, Duration);
+
+    #[async_trait]
+    impl Job for Noop {
+        fn name(&self) -> &'static str {
+            self.0
+        }
+
+        fn interval(&self) -> Duration {
+            self.1
+        }
+
+        async fn run(&self, _state: &AppState) -> anyhow::Result<String> {
+            Ok(String::new())
+        }
+    }
+
+    async fn next_run_in(db: &PgPool, name: &str) -> i64 {
+        sqlx::query_scalar(
+            "SELECT EXTRACT(EPOCH FROM next_run_at - NOW())::BIGINT FROM jobs WHERE name = $1",
+        )
+        .bind(name)
+        .fetch_one(db)
+        .await
+        .unwrap()
+    }
+
+    async fn remove(db: &PgPool, name: &str) {
+        sqlx::query("DELETE FROM jobs WHERE name = $1")
+            .bind(name)
+            .execute(db)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn claims_only_a_due_job() {
+        let Some(db) = test_pool().await else { return };
+        let name = "test.scheduler.claim";
+        remove(&db, name).await;
+
+        // A new row is due straight away
+        Scheduler::new(Duration::from_secs(1))
+            .with_job(Noop(name, Duration::from_secs(600)))
+            .register_all(&db)
+            .await
+            .unwrap();
+
+        assert!(claim(&db, name).await.unwrap());
+        assert!((595..=600).contains(&next_run_in(&db, name).await));
+        assert!(!claim(&db, name).await.unwrap());
+
+        sqlx::query("UPDATE jobs SET next_run_at = NOW() - INTERVAL '1 second' WHERE name = $1")
+            .bind(name)
+            .execute(&db)
+            .await
+            .unwrap();
+        assert!(claim(&db, name).await.unwrap());
+
+        remove(&db, name).await;
+    }
+
+    #[tokio::test]
+    async fn register_all_picks_up_interval_changes() {
+        let Some(db) = test_pool().await else { return };
+        let name = "test.scheduler.interval";
+        remove(&db, name).await;
+
+        let register = |secs| {
+            Scheduler::new(Duration::from_secs(1))
+                .with_job(Noop(name, Duration::from_secs(secs)))
+                .register_all(&db)
+        };
+
+        register(3600).await.unwrap();
+        assert!(claim(&db, name).await.unwrap());
+        assert!(next_run_in(&db, name).await > 3500);
+
+        // A shorter interval pulls the next run in
+        register(60).await.unwrap();
+        let interval: i64 = sqlx::query_scalar("SELECT interval_secs FROM jobs WHERE name = $1")
+            .bind(name)
+            .fetch_one(&db)
+            .await
+            .unwrap();
+        assert_eq!(interval, 60);
+        assert!(next_run_in(&db, name).await <= 60);
+
+        // A longer one leaves the run already scheduled alone
+        register(7200).await.unwrap();
+        assert!(next_run_in(&db, name).await <= 60);
+
+        remove(&db, name).await;
+    }
+}
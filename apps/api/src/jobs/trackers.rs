@@ -0,0 +1,36 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use super::Job;
+use crate::trackers::sync::process_due;
+use crate::AppState;
+
+/// Rows pushed per run; the rest wait for the next one.
+const BATCH_SIZE: i64 = 200;
+
+/// Retries progress pushes to AniList and MyAnimeList that failed or were
+/// never attempted.
+pub struct SyncTrackers {
+    pub interval: Duration,
+}
+
+#[async_trait]
+impl Job for SyncTrackers {
+    fn name(&self) -> &'static str {
+        "sync_trackers"
+    }
+
+    fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    async fn run(&self, state: &AppState) -> anyhow::Result<String> {
+        let summary = process_due(state, None, BATCH_SIZE).await?;
+
+        Ok(format!(
+            "synced {}, skipped {}, failed {}",
+            summary.synced, summary.skipped, summary.failed
+        ))
+    }
+}
@@ -2,8 +2,14 @@ pub mod auth;
 pub mod config;
 pub mod db;
 pub mod http;
+pub mod jobs;
+pub mod mailer;
 pub mod manga;
 pub mod mangadex;
+pub mod page_store;
+pub mod source;
 pub mod state;
+pub mod trackers;
+pub mod users;
 
 pub use state::AppState;
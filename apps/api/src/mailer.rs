@@ -0,0 +1,224 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::Utc;
+use lettre::message::header::ContentType;
+use lettre::message::Mailbox;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use uuid::Uuid;
+
+use crate::config::MailConfig;
+
+/// A plain-text message to one recipient.
+#[derive(Debug, Clone)]
+pub struct Email {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+/// Delivers account emails such as verification tokens.
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, email: &Email) -> Result<()>;
+}
+
+/// The configured mailer, or `None` when email delivery is off. SMTP wins
+/// over the development outbox when both are set.
+pub fn from_config(config: &MailConfig) -> Result<Option<Arc<dyn Mailer>>> {
+    if let Some(url) = &config.smtp_url {
+        let from = config.from.as_deref().context("MAIL_FROM is not set")?;
+        return Ok(Some(Arc::new(SmtpMailer::new(url, from)?)));
+    }
+
+    Ok(config
+        .outbox_dir
+        .as_ref()
+        .map(|dir| Arc::new(OutboxMailer::new(dir)) as Arc<dyn Mailer>))
+}
+
+/// Sends through an SMTP server, reusing pooled connections.
+pub struct SmtpMailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpMailer {
+    /// `url` is an `smtp://` or `smtps://` URL, see [`MailConfig::smtp_url`].
+    pub fn new(url: &str, from: &str) -> Result<Self> {
+        let from = from
+            .parse()
+            .context("MAIL_FROM is not a valid email address")?;
+        let transport = AsyncSmtpTransport::<Tokio1Executor>::from_url(url)
+            .context("SMTP_URL is not a valid SMTP URL")?
+            .build();
+
+        Ok(Self { transport, from })
+    }
+}
+
+#[async_trait]
+impl Mailer for SmtpMailer {
+    async fn send(&self, email: &Email) -> Result<()> {
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(email.to.parse().context("Invalid recipient address")?)
+            .subject(&email.subject)
+            .header(ContentType::TEXT_PLAIN)
+            .body(email.body.clone())
+            .context("Failed to build email")?;
+
+        self.transport
+            .send(message)
+            .await
+            .context("SMTP server refused the email")?;
+        Ok(())
+    }
+}
+
+/// Development sink that writes each email to its own `.eml` file instead
+/// of sending it.
+pub struct OutboxMailer {
+    dir: PathBuf,
+}
+
+impl OutboxMailer {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+}
+
+#[async_trait]
+impl Mailer for OutboxMailer {
+    async fn send(&self, email: &Email) -> Result<()> {
+        tokio::fs::create_dir_all(&self.dir)
+            .await
+            .with_context(|| format!("Failed to create outbox {}", self.dir.display()))?;
+
+        // Sorts by when it was sent
+        let path = self.dir.join(format!(
+            "{}-{}.eml",
+            Utc::now().format("%Y%m%dT%H%M%S%.3f"),
+            Uuid::new_v4().simple()
+        ));
+        let message = format!(
+            "To: {}\r\nSubject: {}\r\nContent-Type: text/plain; charset=utf-8\r\n\r\n{}\r\n",
+            email.to, email.subject, email.body
+        );
+
+        tokio::fs::write(&path, message)
+            .await
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+
+    /// Accepts one SMTP session and returns everything the client sent.
+    async fn smtp_server(listener: TcpListener) -> String {
+        let (stream, _) = listener.accept().await.unwrap();
+        let (read, mut write) = stream.into_split();
+        let mut lines = BufReader::new(read).lines();
+        let mut received = String::new();
+        let mut in_data = false;
+
+        write.write_all(b"220 test ESMTP\r\n").await.unwrap();
+        while let Some(line) = lines.next_line().await.unwrap() {
+            received.push_str(&line);
+            received.push('\n');
+
+            let reply: &[u8] = if in_data {
+                if line != "." {
+                    continue;
+                }
+                in_data = false;
+                b"250 queued\r\n"
+            } else if line.starts_with("DATA") {
+                in_data = true;
+                b"354 go ahead\r\n"
+            } else if line.starts_with("QUIT") {
+                write.write_all(b"221 bye\r\n").await.unwrap();
+                break;
+            } else {
+                b"250 ok\r\n"
+            };
+            write.write_all(reply).await.unwrap();
+        }
+
+        received
+    }
+
+    #[tokio::test]
+    async fn smtp_sends_from_the_configured_address() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("smtp://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(smtp_server(listener));
+
+        let config = MailConfig {
+            smtp_url: Some(url),
+            from: Some("Reader <no-reply@example.com>".to_string()),
+            outbox_dir: Some("/unused".to_string()),
+        };
+        let mailer = from_config(&config).unwrap().unwrap();
+        mailer
+            .send(&Email {
+                to: "reader@example.com".to_string(),
+                subject: "Confirm your new email".to_string(),
+                body: "Token: abc123".to_string(),
+            })
+            .await
+            .unwrap();
+        drop(mailer);
+
+        let received = server.await.unwrap();
+        assert!(received.contains("MAIL FROM:<no-reply@example.com>"));
+        assert!(received.contains("RCPT TO:<reader@example.com>"));
+        assert!(received.contains("Subject: Confirm your new email"));
+        assert!(received.contains("Token: abc123"));
+    }
+
+    #[test]
+    fn rejects_a_bad_sender() {
+        let config = MailConfig {
+            smtp_url: Some("smtp://localhost".to_string()),
+            from: Some("not an address".to_string()),
+            outbox_dir: None,
+        };
+
+        assert!(from_config(&config).is_err());
+    }
+
+    #[tokio::test]
+    async fn outbox_writes_one_file_per_email() {
+        let dir = std::env::temp_dir().join(format!("outbox-{}", Uuid::new_v4().simple()));
+        let mailer = OutboxMailer::new(&dir);
+        let email = Email {
+            to: "reader@example.com".to_string(),
+            subject: "Confirm your new email".to_string(),
+            body: "Token: abc123".to_string(),
+        };
+
+        mailer.send(&email).await.unwrap();
+        mailer.send(&email).await.unwrap();
+
+        let mut files = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect::<Vec<_>>();
+        files.sort();
+        assert_eq!(files.len(), 2);
+
+        let message = std::fs::read_to_string(&files[0]).unwrap();
+        assert!(message.starts_with("To: reader@example.com\r\n"));
+        assert!(message.contains("Subject: Confirm your new email\r\n"));
+        assert!(message.ends_with("\r\n\r\nToken: abc123\r\n"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
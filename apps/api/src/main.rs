@@ -3,16 +3,8 @@ use tracing_subscriber::{FmtSubscriber};
 use anyhow::Context;
 
 use std::net::SocketAddr;
-mod auth;
-mod config;
-mod http;
-mod db;
-mod mangadex;
-mod manga;
 
-use crate::{config::AppConfig, http::build_router};
-mod state;
-pub use state::AppState;
+use api::{config::AppConfig, db, http::build_router, source, AppState};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -50,19 +42,74 @@ async fn main() -> anyhow::Result<()> {
 
     // 5. Initialize MangaDex client
     tracing::info!("Initializing MangaDex client...");
-    let mangadex_client = crate::mangadex::MangaDexClient::new(&config.mangadex)
+    let mangadex_client = api::mangadex::MangaDexClient::new(&config.mangadex)
         .context("Failed to create MangaDex client")?;
     let mangadex_client = std::sync::Arc::new(mangadex_client);
     tracing::info!("MangaDex client initialized");
 
+    // 5b. Register manga sources (MangaDex is the default)
+    let sources = source::SourceRegistry::new(mangadex_client.clone());
+
+    // 5c. Open the on-disk page store
+    let page_store = api::page_store::PageStore::open(&config.page_store)
+        .context("Failed to open page store")?;
+    tracing::info!(
+        "Page store ready at {} ({} bytes in use)",
+        config.page_store.dir,
+        page_store.total_bytes()
+    );
+
+    // 5d. Image proxy client (redirects are limited to MangaDex image hosts)
+    let proxy_http = api::http::routes::proxy::http_client()
+        .context("Failed to create image proxy HTTP client")?;
+
+    // 5e. Start MangaDex@Home page load reporting
+    let home_reporter = api::mangadex::report::HomeReporter::spawn(
+        &config.mangadex,
+        mangadex_client.http().clone(),
+    );
+
+    // 5f. Configure tracker integrations (AniList, MyAnimeList)
+    let tracker_http = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(15))
+        .build()
+        .context("Failed to create tracker HTTP client")?;
+    let trackers = api::trackers::TrackerRegistry::from_config(&config.trackers, tracker_http);
+    tracing::info!("Trackers enabled: {:?}", trackers.ids());
+
+    // 5g. Email delivery
+    let mailer = api::mailer::from_config(&config.mail).context("Invalid mail configuration")?;
+    if mailer.is_none() {
+        tracing::warn!("Neither SMTP_URL nor MAIL_OUTBOX_DIR set; email changes are disabled");
+    }
+
     // 6. App state
     let state = AppState {
         db_pool: pool,
         auth_config: config.auth.clone(),
         mangadex_client,
         mangadex_config: config.mangadex.clone(),
+        hot_cache: std::sync::Arc::new(api::mangadex::cache::HotCaches::new(&config.mangadex)),
+        sources: std::sync::Arc::new(sources),
+        page_store: std::sync::Arc::new(page_store),
+        proxy_config: config.proxy.clone(),
+        proxy_http,
+        home_reporter: std::sync::Arc::new(home_reporter),
+        trackers: std::sync::Arc::new(trackers),
+        tracker_config: config.trackers.clone(),
+        export_config: config.exports.clone(),
+        mailer,
     };
 
+    // 6b. Start background jobs (cache refresh, notifications, maintenance)
+    if config.jobs.enabled {
+        api::jobs::scheduler(&config)
+            .register_all(&state.db_pool)
+            .await
+            .context("Failed to register background jobs")?
+            .spawn(state.clone());
+    }
+
     // 7. Build HTTP router
     let app = build_router(&config, state.clone());
 
@@ -1,4 +1,4 @@
 pub mod models;
 
-pub use crate::mangadex::types::{Chapter, Manga};
-pub use models::{ChapterCache, MangaCache};
+pub use crate::mangadex::types::{Chapter, Manga, Tag};
+pub use models::{ChapterCache, MangaCache, TagCache};
@@ -1,12 +1,13 @@
 use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 
+use crate::mangadex::source::SOURCE_ID;
 use crate::mangadex::types::*;
 
 #[derive(Debug, FromRow)]
 pub struct MangaCache {
     pub id: uuid::Uuid,
+    pub source: String,
     pub mangadex_id: String,
     pub title: String,
     pub alt_titles: Option<serde_json::Value>,
@@ -21,9 +22,20 @@ pub struct MangaCache {
     pub cached_at: DateTime<Utc>,
 }
 
+#[derive(Debug, FromRow)]
+pub struct TagCache {
+    pub id: uuid::Uuid,
+    pub source: String,
+    pub mangadex_id: String,
+    pub name: String,
+    pub tag_group: String,
+    pub cached_at: DateTime<Utc>,
+}
+
 #[derive(Debug, FromRow)]
 pub struct ChapterCache {
     pub id: uuid::Uuid,
+    pub source: String,
     pub mangadex_id: String,
     pub manga_mangadex_id: String,
     pub chapter_number: Option<String>,
@@ -40,7 +52,8 @@ pub struct ChapterCache {
 impl From<MangaCache> for Manga {
     fn from(cache: MangaCache) -> Self {
         Manga {
-            mangadex_id: cache.mangadex_id.clone(),
+            source: cache.source,
+            mangadex_id: cache.mangadex_id,
             title: cache.title,
             alt_titles: cache
                 .alt_titles
@@ -70,6 +83,7 @@ impl From<MangaCache> for Manga {
 impl From<ChapterCache> for Chapter {
     fn from(cache: ChapterCache) -> Self {
         Chapter {
+            source: cache.source,
             mangadex_id: cache.mangadex_id,
             manga_mangadex_id: cache.manga_mangadex_id,
             chapter_number: cache.chapter_number,
@@ -84,6 +98,34 @@ impl From<ChapterCache> for Chapter {
     }
 }
 
+impl From<TagCache> for Tag {
+    fn from(cache: TagCache) -> Self {
+        Tag {
+            id: cache.mangadex_id,
+            name: cache.name,
+            group: cache.tag_group,
+        }
+    }
+}
+
+impl From<&MangaDexTag> for Tag {
+    fn from(mangadex: &MangaDexTag) -> Self {
+        let name = mangadex
+            .attributes
+            .name
+            .en
+            .clone()
+            .or_else(|| mangadex.attributes.name.ja.clone())
+            .unwrap_or_else(|| "Unknown".to_string());
+
+        Tag {
+            id: mangadex.id.clone(),
+            name,
+            group: mangadex.attributes.group.clone(),
+        }
+    }
+}
+
 impl TryFrom<MangaDexManga> for Manga {
     type Error = anyhow::Error;
 
@@ -147,27 +189,10 @@ impl TryFrom<MangaDexManga> for Manga {
             }
         }
 
-        let tags: Vec<Tag> = attrs
-            .tags
-            .iter()
-            .map(|t| {
-                let name = t
-                    .attributes
-                    .name
-                    .en
-                    .clone()
-                    .or_else(|| t.attributes.name.ja.clone())
-                    .unwrap_or_else(|| "Unknown".to_string());
-
-                Tag {
-                    id: t.id.clone(),
-                    name,
-                    group: t.attributes.group.clone(),
-                }
-            })
-            .collect();
+        let tags: Vec<Tag> = attrs.tags.iter().map(Tag::from).collect();
 
         Ok(Manga {
+            source: SOURCE_ID.to_string(),
             mangadex_id: mangadex.id,
             title,
             alt_titles,
@@ -213,6 +238,7 @@ impl TryFrom<MangaDexChapter> for Chapter {
             .map(|dt| dt.to_rfc3339());
 
         Ok(Chapter {
+            source: SOURCE_ID.to_string(),
             mangadex_id: mangadex.id,
             manga_mangadex_id,
             chapter_number: attrs.chapter.clone(),
@@ -1,44 +1,403 @@
-use chrono::{Duration, Utc};
+use axum::http::{HeaderName, HeaderValue};
+use chrono::{DateTime, Duration, Utc};
 use sqlx::PgPool;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 
 use crate::config::MangaDexConfig;
-use crate::manga::models::{ChapterCache, MangaCache};
+use crate::manga::models::{ChapterCache, MangaCache, TagCache};
 use crate::mangadex::client::MangaDexClient;
 use crate::mangadex::error::MangaDexError;
+use crate::mangadex::hot_cache::{HotCache, HotCacheStats};
+use crate::mangadex::single_flight::SingleFlight;
+use crate::mangadex::source::SOURCE_ID;
 use crate::mangadex::types::*;
+use crate::source::{MangaSource, SourceRegistry};
+
+fn manga_key(source: &str, mangadex_id: &str) -> String {
+    format!("{}:{}", source, mangadex_id)
+}
+
+fn chapters_key(source: &str, manga_mangadex_id: &str, lang: &str) -> String {
+    format!("{}:{}:{}", source, manga_mangadex_id, lang)
+}
+
+/// In-memory LRUs of decoded manga and chapter lists in front of
+/// `manga_cache` and `chapter_cache`, along with the in-flight refreshes that
+/// fill them. One per process, shared through [`crate::AppState`].
+pub struct HotCaches {
+    manga: HotCache<Manga>,
+    chapters: HotCache<Vec<Chapter>>,
+    manga_flights: SingleFlight<Manga>,
+    chapter_flights: SingleFlight<Vec<Chapter>>,
+}
+
+impl HotCaches {
+    pub fn new(config: &MangaDexConfig) -> Self {
+        let ttl = std::time::Duration::from_secs(config.hot_cache_ttl_secs);
+        Self {
+            manga: HotCache::new(config.hot_cache_capacity, ttl),
+            chapters: HotCache::new(config.hot_cache_capacity, ttl),
+            manga_flights: SingleFlight::default(),
+            chapter_flights: SingleFlight::default(),
+        }
+    }
+
+    pub fn stats(&self) -> HotCacheReport {
+        HotCacheReport {
+            manga: self.manga.stats(),
+            chapters: self.chapters.stats(),
+        }
+    }
+}
+
+/// Hit rates of the in-memory manga and chapter list caches.
+#[derive(Debug, serde::Serialize)]
+pub struct HotCacheReport {
+    pub manga: HotCacheStats,
+    pub chapters: HotCacheStats,
+}
+
+/// A cache lookup result and whether it is past its TTL.
+#[derive(Debug)]
+pub struct Cached<T> {
+    pub value: T,
+    /// When the data was last fetched from the source.
+    pub cached_at: DateTime<Utc>,
+    pub stale: bool,
+}
+
+impl<T> Cached<T> {
+    fn fresh(value: T) -> Self {
+        Self {
+            value,
+            cached_at: Utc::now(),
+            stale: false,
+        }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+
+    /// `X-Cache-Stale` and `X-Cached-At` headers describing this result.
+    pub fn staleness_headers(&self) -> [(HeaderName, HeaderValue); 2] {
+        [
+            (
+                HeaderName::from_static("x-cache-stale"),
+                HeaderValue::from_static(if self.stale { "true" } else { "false" }),
+            ),
+            (
+                HeaderName::from_static("x-cached-at"),
+                HeaderValue::from_str(&self.cached_at.to_rfc3339())
+                    .unwrap_or_else(|_| HeaderValue::from_static("")),
+            ),
+        ]
+    }
+}
+
+/// How a cached row of the given age should be used.
+enum Freshness {
+    Fresh,
+    /// Serve it now and refresh in the background.
+    Stale,
+    /// Too old to serve without trying the source first.
+    Expired,
+}
+
+fn freshness(cached_at: DateTime<Utc>, ttl_hours: i64, max_stale_hours: i64) -> Freshness {
+    let age = Utc::now() - cached_at;
+
+    if age < Duration::hours(ttl_hours) {
+        Freshness::Fresh
+    } else if age < Duration::hours(ttl_hours + max_stale_hours) {
+        Freshness::Stale
+    } else {
+        Freshness::Expired
+    }
+}
 
 pub async fn get_manga_with_cache(
     mangadex_id: &str,
     db: &PgPool,
-    client: &MangaDexClient,
+    hot: &Arc<HotCaches>,
+    source: &Arc<dyn MangaSource>,
     config: &MangaDexConfig,
-) -> Result<Manga, MangaDexError> {
+) -> Result<Cached<Manga>, MangaDexError> {
+    let key = manga_key(source.id(), mangadex_id);
+
+    if let Some((manga, cached_at)) = hot.manga.get(&key) {
+        if let Freshness::Fresh = freshness(
+            cached_at,
+            config.cache_manga_ttl_hours,
+            config.cache_max_stale_hours,
+        ) {
+            return Ok(Cached {
+                value: manga,
+                cached_at,
+                stale: false,
+            });
+        }
+    }
+
     let cached = sqlx::query_as::<_, MangaCache>(
-        "SELECT id, mangadex_id, title, alt_titles, description, cover_url, status::text AS status, year, content_rating, tags, author_names, artist_names, cached_at FROM manga_cache WHERE mangadex_id = $1"
+        "SELECT id, source, mangadex_id, title, alt_titles, description, cover_url, status::text AS status, year, content_rating, tags, author_names, artist_names, cached_at FROM manga_cache WHERE source = $1 AND mangadex_id = $2"
     )
+    .bind(source.id())
     .bind(mangadex_id)
     .fetch_optional(db)
     .await
     .map_err(|e| MangaDexError::Internal(anyhow::anyhow!("Database error: {}", e)))?;
 
-    if let Some(manga_cache) = cached {
-        let cache_age = Utc::now() - manga_cache.cached_at;
-        if cache_age < Duration::hours(config.cache_manga_ttl_hours) {
-            return Ok(manga_cache.into());
+    let Some(manga_cache) = cached else {
+        return Ok(Cached::fresh(
+            refresh_manga(mangadex_id, db, hot, source).await?,
+        ));
+    };
+
+    let cached_at = manga_cache.cached_at;
+    let stale = |value| Cached {
+        value,
+        cached_at,
+        stale: true,
+    };
+
+    match freshness(
+        cached_at,
+        config.cache_manga_ttl_hours,
+        config.cache_max_stale_hours,
+    ) {
+        Freshness::Fresh => {
+            let manga: Manga = manga_cache.into();
+            hot.manga.insert(key, manga.clone(), cached_at);
+            Ok(Cached {
+                value: manga,
+                cached_at,
+                stale: false,
+            })
+        }
+        Freshness::Stale => {
+            let (db, hot, source) = (db.clone(), hot.clone(), source.clone());
+            let id = mangadex_id.to_string();
+            tokio::spawn(async move {
+                if let Err(e) = refresh_manga(&id, &db, &hot, &source).await {
+                    tracing::debug!("Background refresh of manga {} failed: {}", id, e);
+                }
+            });
+            Ok(stale(manga_cache.into()))
         }
+        Freshness::Expired => match refresh_manga(mangadex_id, db, hot, source).await {
+            Ok(manga) => Ok(Cached::fresh(manga)),
+            Err(e) if e.is_upstream_failure() => {
+                tracing::warn!(
+                    "Serving stale manga {} after upstream error: {}",
+                    mangadex_id,
+                    e
+                );
+                Ok(stale(manga_cache.into()))
+            }
+            Err(e) => Err(e),
+        },
     }
+}
 
-    let mangadex_manga = client.get_manga(mangadex_id).await?;
-    let manga: Manga = mangadex_manga.try_into()?;
+/// Most IDs a source is asked for in one batch lookup.
+const BATCH_SIZE: usize = 100;
 
+/// Batch form of [`get_manga_with_cache`] for listing endpoints.
+///
+/// Cached rows are read in one query; missing and expired ones are fetched
+/// from the source in chunks of [`BATCH_SIZE`] and stale ones are refreshed in
+/// the background. Manga the source can't provide right now are left out of
+/// the result rather than failing the whole batch.
+pub async fn get_mangas_with_cache(
+    mangadex_ids: &[String],
+    db: &PgPool,
+    hot: &Arc<HotCaches>,
+    source: &Arc<dyn MangaSource>,
+    config: &MangaDexConfig,
+) -> Result<HashMap<String, Manga>, MangaDexError> {
+    let mut found = HashMap::with_capacity(mangadex_ids.len());
+    let mut lookup = Vec::new();
+    let mut seen = HashSet::with_capacity(mangadex_ids.len());
+
+    for id in mangadex_ids {
+        if !seen.insert(id) {
+            continue;
+        }
+        match hot.manga.get(&manga_key(source.id(), id)) {
+            Some((manga, cached_at))
+                if matches!(
+                    freshness(
+                        cached_at,
+                        config.cache_manga_ttl_hours,
+                        config.cache_max_stale_hours
+                    ),
+                    Freshness::Fresh
+                ) =>
+            {
+                found.insert(id.clone(), manga);
+            }
+            _ => lookup.push(id.clone()),
+        }
+    }
+
+    if lookup.is_empty() {
+        return Ok(found);
+    }
+
+    let cached = sqlx::query_as::<_, MangaCache>(
+        "SELECT id, source, mangadex_id, title, alt_titles, description, cover_url, status::text AS status, year, content_rating, tags, author_names, artist_names, cached_at FROM manga_cache WHERE source = $1 AND mangadex_id = ANY($2)"
+    )
+    .bind(source.id())
+    .bind(&lookup)
+    .fetch_all(db)
+    .await
+    .map_err(|e| MangaDexError::Internal(anyhow::anyhow!("Database error: {}", e)))?;
+
+    let mut stale = Vec::new();
+    let mut expired = HashMap::new();
+    for row in cached {
+        let (id, cached_at) = (row.mangadex_id.clone(), row.cached_at);
+        let manga: Manga = row.into();
+
+        match freshness(
+            cached_at,
+            config.cache_manga_ttl_hours,
+            config.cache_max_stale_hours,
+        ) {
+            Freshness::Fresh => {
+                hot.manga
+                    .insert(manga_key(source.id(), &id), manga.clone(), cached_at);
+                found.insert(id, manga);
+            }
+            Freshness::Stale => {
+                stale.push(id.clone());
+                found.insert(id, manga);
+            }
+            Freshness::Expired => {
+                expired.insert(id, manga);
+            }
+        }
+    }
+
+    if !stale.is_empty() {
+        let (db, hot, source) = (db.clone(), hot.clone(), source.clone());
+        tokio::spawn(async move {
+            if let Err(e) = fetch_and_store_mangas(&stale, &db, &hot, source.as_ref()).await {
+                tracing::debug!("Background refresh of {} manga failed: {}", stale.len(), e);
+            }
+        });
+    }
+
+    let missing: Vec<String> = lookup
+        .into_iter()
+        .filter(|id| !found.contains_key(id))
+        .collect();
+
+    if !missing.is_empty() {
+        match fetch_and_store_mangas(&missing, db, hot, source.as_ref()).await {
+            Ok(mangas) => {
+                for manga in mangas {
+                    expired.remove(&manga.mangadex_id);
+                    found.insert(manga.mangadex_id.clone(), manga);
+                }
+            }
+            Err(e) if e.is_upstream_failure() => {
+                tracing::warn!("Batch manga lookup failed, serving stale rows: {}", e);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    // Expired rows the source didn't return are still better than nothing
+    found.extend(expired);
+
+    Ok(found)
+}
+
+/// [`get_mangas_with_cache`] for `(source, id)` pairs that may span
+/// sources. Pairs naming an unknown source are left out.
+pub async fn get_mangas_by_source(
+    keys: &[(String, String)],
+    db: &PgPool,
+    hot: &Arc<HotCaches>,
+    sources: &SourceRegistry,
+    config: &MangaDexConfig,
+) -> Result<HashMap<(String, String), Manga>, MangaDexError> {
+    let mut by_source: HashMap<&str, Vec<String>> = HashMap::new();
+    for (source, id) in keys {
+        by_source.entry(source).or_default().push(id.clone());
+    }
+
+    let mut found = HashMap::with_capacity(keys.len());
+    for (source_id, ids) in by_source {
+        let Some(source) = sources.get(source_id) else {
+            continue;
+        };
+
+        for (id, manga) in get_mangas_with_cache(&ids, db, hot, &source, config).await? {
+            found.insert((source_id.to_string(), id), manga);
+        }
+    }
+
+    Ok(found)
+}
+
+async fn fetch_and_store_mangas(
+    mangadex_ids: &[String],
+    db: &PgPool,
+    hot: &HotCaches,
+    source: &dyn MangaSource,
+) -> Result<Vec<Manga>, MangaDexError> {
+    let mut mangas = Vec::with_capacity(mangadex_ids.len());
+
+    for chunk in mangadex_ids.chunks(BATCH_SIZE) {
+        for manga in source.get_mangas(chunk).await? {
+            store_manga(db, hot, &manga).await?;
+            mangas.push(manga);
+        }
+    }
+
+    Ok(mangas)
+}
+
+/// Fetches a manga from the source and writes it to `manga_cache`.
+///
+/// Concurrent refreshes of the same manga share one fetch and one upsert.
+pub async fn refresh_manga(
+    mangadex_id: &str,
+    db: &PgPool,
+    hot: &Arc<HotCaches>,
+    source: &Arc<dyn MangaSource>,
+) -> Result<Manga, MangaDexError> {
+    let key = manga_key(source.id(), mangadex_id);
+    let (id, db, caches, source) = (
+        mangadex_id.to_string(),
+        db.clone(),
+        hot.clone(),
+        source.clone(),
+    );
+
+    hot.manga_flights
+        .run(key, move || async move {
+            let manga = source.get_manga(&id).await?;
+            store_manga(&db, &caches, &manga).await?;
+            Ok(manga)
+        })
+        .await
+}
+
+/// Upserts a manga into `manga_cache`, resetting its cache age.
+pub async fn store_manga(db: &PgPool, hot: &HotCaches, manga: &Manga) -> Result<(), MangaDexError> {
     sqlx::query(
         r#"
         INSERT INTO manga_cache (
-            mangadex_id, title, alt_titles, description, cover_url,
+            source, mangadex_id, title, alt_titles, description, cover_url,
             status, year, content_rating, tags, author_names, artist_names
         )
-        VALUES ($1, $2, $3, $4, $5, $6::manga_status, $7, $8, $9, $10, $11)
-        ON CONFLICT (mangadex_id) DO UPDATE SET
+        VALUES ($1, $2, $3, $4, $5, $6, $7::manga_status, $8, $9, $10, $11, $12)
+        ON CONFLICT (source, mangadex_id) DO UPDATE SET
             title = EXCLUDED.title,
             alt_titles = EXCLUDED.alt_titles,
             description = EXCLUDED.description,
@@ -52,6 +411,7 @@ pub async fn get_manga_with_cache(
             cached_at = NOW()
         "#,
     )
+    .bind(&manga.source)
     .bind(&manga.mangadex_id)
     .bind(&manga.title)
     .bind(serde_json::to_value(&manga.alt_titles).ok())
@@ -67,100 +427,369 @@ pub async fn get_manga_with_cache(
     .await
     .map_err(|e| MangaDexError::Internal(anyhow::anyhow!("Failed to cache manga: {}", e)))?;
 
-    Ok(manga)
+    hot.manga
+        .invalidate(&manga_key(&manga.source, &manga.mangadex_id));
+
+    Ok(())
 }
 
 pub async fn get_chapters_with_cache(
     manga_mangadex_id: &str,
     lang: &str,
     db: &PgPool,
-    client: &MangaDexClient,
+    hot: &Arc<HotCaches>,
+    source: &Arc<dyn MangaSource>,
     config: &MangaDexConfig,
-) -> Result<Vec<Chapter>, MangaDexError> {
+) -> Result<Cached<Vec<Chapter>>, MangaDexError> {
+    let key = chapters_key(source.id(), manga_mangadex_id, lang);
+
+    if let Some((chapters, cached_at)) = hot.chapters.get(&key) {
+        if let Freshness::Fresh = freshness(
+            cached_at,
+            config.cache_chapter_ttl_hours,
+            config.cache_max_stale_hours,
+        ) {
+            return Ok(Cached {
+                value: chapters,
+                cached_at,
+                stale: false,
+            });
+        }
+    }
+
     let cached = sqlx::query_as::<_, ChapterCache>(
-        "SELECT id, mangadex_id, manga_mangadex_id, chapter_number, volume, title, language, scanlation_group_id, scanlation_group_name, page_count, published_at, cached_at FROM chapter_cache WHERE manga_mangadex_id = $1 AND language = $2 ORDER BY chapter_number::numeric"
+        "SELECT id, source, mangadex_id, manga_mangadex_id, chapter_number, volume, title, language, scanlation_group_id, scanlation_group_name, page_count, published_at, cached_at FROM chapter_cache WHERE source = $1 AND manga_mangadex_id = $2 AND language = $3 ORDER BY chapter_number::numeric"
     )
+    .bind(source.id())
     .bind(manga_mangadex_id)
     .bind(lang)
     .fetch_all(db)
     .await
     .map_err(|e| MangaDexError::Internal(anyhow::anyhow!("Database error: {}", e)))?;
 
-    if !cached.is_empty() {
-        let oldest_cache = cached.iter().map(|c| c.cached_at).min().unwrap();
-        let cache_age = Utc::now() - oldest_cache;
-        if cache_age < Duration::hours(config.cache_chapter_ttl_hours) {
-            return Ok(cached.into_iter().map(|c| c.into()).collect());
-        }
-    }
-
-    let mut all_chapters = Vec::new();
-    let mut offset = 0;
-    let limit = 100;
-
-    loop {
-        let response = client
-            .get_chapters(manga_mangadex_id, lang, limit, offset)
-            .await?;
-
-        if response.data.is_empty() {
-            break;
-        }
-
-        for mangadex_chapter in response.data {
-            let chapter: Chapter = mangadex_chapter.try_into()?;
-            all_chapters.push(chapter.clone());
-
-            sqlx::query(
-                r#"
-                INSERT INTO chapter_cache (
-                    mangadex_id, manga_mangadex_id, chapter_number, volume,
-                    title, language, scanlation_group_id, scanlation_group_name,
-                    page_count, published_at
-                )
-                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
-                ON CONFLICT (mangadex_id) DO UPDATE SET
-                    chapter_number = EXCLUDED.chapter_number,
-                    volume = EXCLUDED.volume,
-                    title = EXCLUDED.title,
-                    language = EXCLUDED.language,
-                    scanlation_group_id = EXCLUDED.scanlation_group_id,
-                    scanlation_group_name = EXCLUDED.scanlation_group_name,
-                    page_count = EXCLUDED.page_count,
-                    published_at = EXCLUDED.published_at,
-                    cached_at = NOW()
-                "#,
-            )
-            .bind(&chapter.mangadex_id)
-            .bind(&chapter.manga_mangadex_id)
-            .bind(&chapter.chapter_number)
-            .bind(&chapter.volume)
-            .bind(&chapter.title)
-            .bind(&chapter.language)
-            .bind(&chapter.scanlation_group_id)
-            .bind(&chapter.scanlation_group_name)
-            .bind(chapter.page_count as i32)
-            .bind(chapter.published_at.as_ref().and_then(|s| {
-                chrono::DateTime::parse_from_rfc3339(s)
-                    .ok()
-                    .map(|dt| dt.with_timezone(&chrono::Utc))
-            }))
-            .execute(db)
-            .await
-            .map_err(|e| {
-                MangaDexError::Internal(anyhow::anyhow!("Failed to cache chapter: {}", e))
-            })?;
+    let Some(cached_at) = cached.iter().map(|c| c.cached_at).min() else {
+        let chapters = refresh_chapters(manga_mangadex_id, lang, db, hot, source).await?;
+        // An empty list leaves no rows behind, so remember it in memory or
+        // every lookup in this language (e.g. each step of the preferred
+        // language fallback) would go upstream again
+        if chapters.is_empty() {
+            hot.chapters.insert(key, Vec::new(), Utc::now());
         }
+        return Ok(Cached::fresh(chapters));
+    };
+
+    let cached: Vec<Chapter> = cached.into_iter().map(|c| c.into()).collect();
+    let stale = |value| Cached {
+        value,
+        cached_at,
+        stale: true,
+    };
 
-        offset += limit;
-        if let Some(total) = response.total {
-            if offset >= total {
-                break;
+    match freshness(
+        cached_at,
+        config.cache_chapter_ttl_hours,
+        config.cache_max_stale_hours,
+    ) {
+        Freshness::Fresh => {
+            hot.chapters.insert(key, cached.clone(), cached_at);
+            Ok(Cached {
+                value: cached,
+                cached_at,
+                stale: false,
+            })
+        }
+        Freshness::Stale => {
+            let (db, hot, source) = (db.clone(), hot.clone(), source.clone());
+            let (manga_id, lang) = (manga_mangadex_id.to_string(), lang.to_string());
+            tokio::spawn(async move {
+                if let Err(e) = refresh_chapters(&manga_id, &lang, &db, &hot, &source).await {
+                    tracing::debug!(
+                        "Background refresh of chapters for {} failed: {}",
+                        manga_id,
+                        e
+                    );
+                }
+            });
+            Ok(stale(cached))
+        }
+        Freshness::Expired => {
+            match refresh_chapters(manga_mangadex_id, lang, db, hot, source).await {
+                Ok(chapters) => Ok(Cached::fresh(chapters)),
+                Err(e) if e.is_upstream_failure() => {
+                    tracing::warn!(
+                        "Serving stale chapters for {} after upstream error: {}",
+                        manga_mangadex_id,
+                        e
+                    );
+                    Ok(stale(cached))
+                }
+                Err(e) => Err(e),
             }
-        } else {
-            break;
         }
     }
+}
+
+/// The manga a chapter belongs to, read from `chapter_cache` when the
+/// chapter has been listed before and asked of the source otherwise.
+pub async fn get_chapter_manga_id(
+    chapter_mangadex_id: &str,
+    db: &PgPool,
+    source: &Arc<dyn MangaSource>,
+) -> Result<String, MangaDexError> {
+    let cached = sqlx::query_scalar::<_, String>(
+        "SELECT manga_mangadex_id FROM chapter_cache WHERE source = $1 AND mangadex_id = $2",
+    )
+    .bind(source.id())
+    .bind(chapter_mangadex_id)
+    .fetch_optional(db)
+    .await
+    .map_err(|e| MangaDexError::Internal(anyhow::anyhow!("Database error: {}", e)))?;
+
+    match cached {
+        Some(manga_id) => Ok(manga_id),
+        None => Ok(source
+            .get_chapter(chapter_mangadex_id)
+            .await?
+            .manga_mangadex_id),
+    }
+}
+
+/// Fetches a manga's chapters in one language from the source and writes them
+/// to `chapter_cache`, regardless of how fresh the cached copy is.
+///
+/// Concurrent refreshes of the same list share one fetch and one upsert.
+pub async fn refresh_chapters(
+    manga_mangadex_id: &str,
+    lang: &str,
+    db: &PgPool,
+    hot: &Arc<HotCaches>,
+    source: &Arc<dyn MangaSource>,
+) -> Result<Vec<Chapter>, MangaDexError> {
+    let key = chapters_key(source.id(), manga_mangadex_id, lang);
+    let (manga_id, lang, db) = (manga_mangadex_id.to_string(), lang.to_string(), db.clone());
+    let (caches, source) = (hot.clone(), source.clone());
+
+    hot.chapter_flights
+        .run(key, move || async move {
+            fetch_and_store_chapters(&manga_id, &lang, &db, &caches, source.as_ref()).await
+        })
+        .await
+}
+
+async fn fetch_and_store_chapters(
+    manga_mangadex_id: &str,
+    lang: &str,
+    db: &PgPool,
+    hot: &HotCaches,
+    source: &dyn MangaSource,
+) -> Result<Vec<Chapter>, MangaDexError> {
+    let chapters = source.get_chapters(manga_mangadex_id, lang).await?;
+
+    for chapter in &chapters {
+        sqlx::query(
+            r#"
+            INSERT INTO chapter_cache (
+                source, mangadex_id, manga_mangadex_id, chapter_number, volume,
+                title, language, scanlation_group_id, scanlation_group_name,
+                page_count, published_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            ON CONFLICT (source, mangadex_id) DO UPDATE SET
+                chapter_number = EXCLUDED.chapter_number,
+                volume = EXCLUDED.volume,
+                title = EXCLUDED.title,
+                language = EXCLUDED.language,
+                scanlation_group_id = EXCLUDED.scanlation_group_id,
+                scanlation_group_name = EXCLUDED.scanlation_group_name,
+                page_count = EXCLUDED.page_count,
+                published_at = EXCLUDED.published_at,
+                cached_at = NOW()
+            "#,
+        )
+        .bind(&chapter.source)
+        .bind(&chapter.mangadex_id)
+        .bind(&chapter.manga_mangadex_id)
+        .bind(&chapter.chapter_number)
+        .bind(&chapter.volume)
+        .bind(&chapter.title)
+        .bind(&chapter.language)
+        .bind(&chapter.scanlation_group_id)
+        .bind(&chapter.scanlation_group_name)
+        .bind(chapter.page_count as i32)
+        .bind(chapter.published_at.as_ref().and_then(|s| {
+            chrono::DateTime::parse_from_rfc3339(s)
+                .ok()
+                .map(|dt| dt.with_timezone(&chrono::Utc))
+        }))
+        .execute(db)
+        .await
+        .map_err(|e| {
+            MangaDexError::Internal(anyhow::anyhow!("Failed to cache chapter: {}", e))
+        })?;
+    }
+
+    hot.chapters
+        .invalidate(&chapters_key(source.id(), manga_mangadex_id, lang));
+
+    Ok(chapters)
+}
+
+pub async fn get_tags_with_cache(
+    db: &PgPool,
+    client: &MangaDexClient,
+) -> Result<Vec<Tag>, MangaDexError> {
+    let cached = sqlx::query_as::<_, TagCache>(
+        "SELECT id, source, mangadex_id, name, tag_group, cached_at FROM tags WHERE source = $1 ORDER BY tag_group, name"
+    )
+    .bind(SOURCE_ID)
+    .fetch_all(db)
+    .await
+    .map_err(|e| MangaDexError::Internal(anyhow::anyhow!("Database error: {}", e)))?;
+
+    if !cached.is_empty() {
+        return Ok(cached.into_iter().map(|t| t.into()).collect());
+    }
+
+    refresh_tags(db, client).await
+}
+
+/// Replaces the cached tag catalogue with the current list from MangaDex.
+pub async fn refresh_tags(db: &PgPool, client: &MangaDexClient) -> Result<Vec<Tag>, MangaDexError> {
+    let refreshed_at = Utc::now();
+    let mut tags: Vec<Tag> = client.get_tags().await?.iter().map(Tag::from).collect();
+    tags.sort_by(|a, b| a.group.cmp(&b.group).then_with(|| a.name.cmp(&b.name)));
+
+    for tag in &tags {
+        sqlx::query(
+            r#"
+            INSERT INTO tags (source, mangadex_id, name, tag_group, cached_at)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (source, mangadex_id) DO UPDATE SET
+                name = EXCLUDED.name,
+                tag_group = EXCLUDED.tag_group,
+                cached_at = EXCLUDED.cached_at
+            "#,
+        )
+        .bind(SOURCE_ID)
+        .bind(&tag.id)
+        .bind(&tag.name)
+        .bind(&tag.group)
+        .bind(refreshed_at)
+        .execute(db)
+        .await
+        .map_err(|e| MangaDexError::Internal(anyhow::anyhow!("Failed to cache tag: {}", e)))?;
+    }
+
+    // Tags MangaDex no longer returns were not touched by this refresh
+    sqlx::query("DELETE FROM tags WHERE source = $1 AND cached_at < $2")
+        .bind(SOURCE_ID)
+        .bind(refreshed_at)
+        .execute(db)
+        .await
+        .map_err(|e| MangaDexError::Internal(anyhow::anyhow!("Failed to prune tags: {}", e)))?;
 
-    Ok(all_chapters)
+    Ok(tags)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hours_ago(hours: i64) -> DateTime<Utc> {
+        Utc::now() - Duration::hours(hours)
+    }
+
+    #[test]
+    fn classifies_rows_by_age() {
+        assert!(matches!(freshness(hours_ago(0), 24, 48), Freshness::Fresh));
+        assert!(matches!(freshness(hours_ago(23), 24, 48), Freshness::Fresh));
+        assert!(matches!(freshness(hours_ago(25), 24, 48), Freshness::Stale));
+        assert!(matches!(freshness(hours_ago(71), 24, 48), Freshness::Stale));
+        assert!(matches!(
+            freshness(hours_ago(73), 24, 48),
+            Freshness::Expired
+        ));
+    }
+
+    #[test]
+    fn no_stale_window_expires_at_the_ttl() {
+        assert!(matches!(
+            freshness(hours_ago(25), 24, 0),
+            Freshness::Expired
+        ));
+    }
+
+    #[test]
+    fn falls_back_to_stale_rows_only_on_upstream_failures() {
+        let network = reqwest::Client::new().get("not a url").build().unwrap_err();
+
+        assert!(MangaDexError::from(network).is_upstream_failure());
+        assert!(MangaDexError::RateLimited.is_upstream_failure());
+        assert!(MangaDexError::ApiError("500".into()).is_upstream_failure());
+
+        assert!(!MangaDexError::NotFound.is_upstream_failure());
+        assert!(!MangaDexError::InvalidResponse.is_upstream_failure());
+        assert!(!MangaDexError::Internal(anyhow::anyhow!("db")).is_upstream_failure());
+    }
+
+    #[tokio::test]
+    async fn upserting_a_manga_invalidates_its_hot_entry() {
+        let Some(db) = crate::db::test_pool().await else {
+            return;
+        };
+        let hot = HotCaches {
+            manga: HotCache::new(8, std::time::Duration::from_secs(3600)),
+            chapters: HotCache::new(8, std::time::Duration::from_secs(3600)),
+            manga_flights: SingleFlight::default(),
+            chapter_flights: SingleFlight::default(),
+        };
+        let manga = Manga {
+            source: "test".into(),
+            mangadex_id: "hot-cache-upsert".into(),
+            title: "Old title".into(),
+            alt_titles: Vec::new(),
+            description: String::new(),
+            cover_url: String::new(),
+            status: "ongoing".into(),
+            year: None,
+            content_rating: "safe".into(),
+            tags: Vec::new(),
+            author_names: Vec::new(),
+            artist_names: Vec::new(),
+        };
+        let key = manga_key(&manga.source, &manga.mangadex_id);
+
+        hot.manga.insert(key.clone(), manga.clone(), Utc::now());
+        store_manga(&db, &hot, &manga).await.unwrap();
+        assert!(hot.manga.get(&key).is_none());
+
+        sqlx::query("DELETE FROM manga_cache WHERE source = $1 AND mangadex_id = $2")
+            .bind(&manga.source)
+            .bind(&manga.mangadex_id)
+            .execute(&db)
+            .await
+            .unwrap();
+    }
+
+    #[test]
+    fn staleness_headers_describe_the_result() {
+        let cached_at = hours_ago(30);
+        let headers = |stale| {
+            Cached {
+                value: (),
+                cached_at,
+                stale,
+            }
+            .staleness_headers()
+        };
+
+        let [(name, value), (at_name, at)] = headers(true);
+        assert_eq!(name, "x-cache-stale");
+        assert_eq!(value, "true");
+        assert_eq!(at_name, "x-cached-at");
+        assert_eq!(at, cached_at.to_rfc3339().as_str());
+
+        let [(_, value), _] = headers(false);
+        assert_eq!(value, "false");
+    }
 }
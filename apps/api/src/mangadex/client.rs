@@ -1,6 +1,7 @@
 use backoff::ExponentialBackoff;
 use governor::{Quota, RateLimiter};
-use reqwest::Client;
+use reqwest::{Client, Url};
+use std::collections::HashMap;
 use std::num::NonZeroU32;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
@@ -8,7 +9,60 @@ use tokio::sync::Mutex;
 
 use crate::config::MangaDexConfig;
 use crate::mangadex::error::MangaDexError;
+use crate::mangadex::single_flight::SingleFlight;
 use crate::mangadex::types::*;
+use crate::source::filters::{ContentRating, SearchFilters, SortField};
+
+/// How long the chapter a page hash belongs to is remembered. Page URLs
+/// outlive the at-home server they were issued with, and failover relies on
+/// knowing the chapter.
+const CHAPTER_HASH_TTL: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// A chapter that just failed over keeps its new server for this long before
+/// another failure may request a fresh one.
+const FAILOVER_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// Recently issued MangaDex@Home servers, so the base URL is not requested
+/// again for every page of a chapter.
+#[derive(Default)]
+struct AtHomeCache {
+    by_chapter: HashMap<String, (Instant, ChapterAtHomeResponse)>,
+    chapter_by_hash: HashMap<String, (Instant, String)>,
+    failed_over: HashMap<String, Instant>,
+}
+
+impl AtHomeCache {
+    fn get(&self, chapter_id: &str, ttl: Duration) -> Option<ChapterAtHomeResponse> {
+        self.by_chapter
+            .get(chapter_id)
+            .filter(|(fetched_at, _)| fetched_at.elapsed() < ttl)
+            .map(|(_, response)| response.clone())
+    }
+
+    fn insert(&mut self, chapter_id: &str, response: ChapterAtHomeResponse, ttl: Duration) {
+        self.by_chapter
+            .retain(|_, (fetched_at, _)| fetched_at.elapsed() < ttl);
+        self.chapter_by_hash
+            .retain(|_, (seen_at, _)| seen_at.elapsed() < CHAPTER_HASH_TTL);
+        self.failed_over
+            .retain(|_, failed_at| failed_at.elapsed() < FAILOVER_COOLDOWN);
+
+        self.chapter_by_hash.insert(
+            response.chapter.hash.clone(),
+            (Instant::now(), chapter_id.to_string()),
+        );
+        self.by_chapter
+            .insert(chapter_id.to_string(), (Instant::now(), response));
+    }
+
+    /// The server issued by a failover within the cooldown, if any.
+    fn recent_failover(&self, chapter_id: &str) -> Option<Option<ChapterAtHomeResponse>> {
+        self.failed_over
+            .get(chapter_id)
+            .filter(|failed_at| failed_at.elapsed() < FAILOVER_COOLDOWN)
+            .map(|_| self.get(chapter_id, FAILOVER_COOLDOWN))
+    }
+}
 
 #[derive(Clone)]
 struct TokenPair {
@@ -17,6 +71,7 @@ struct TokenPair {
     expires_at: Instant,
 }
 
+#[derive(Clone)]
 pub struct MangaDexClient {
     http: Client,
     rate_limiter: Arc<
@@ -29,6 +84,8 @@ pub struct MangaDexClient {
     base_url: String,
     auth_url: String,
     tokens: Arc<Mutex<Option<TokenPair>>>,
+    at_home: Arc<Mutex<AtHomeCache>>,
+    failovers: Arc<SingleFlight<ChapterAtHomeResponse>>,
     config: MangaDexConfig,
 }
 
@@ -53,6 +110,8 @@ impl MangaDexClient {
             base_url: config.base_url.clone(),
             auth_url: "https://auth.mangadex.org/realms/mangadex/protocol/openid-connect/token".to_string(),
             tokens: Arc::new(Mutex::new(None)),
+            at_home: Arc::new(Mutex::new(AtHomeCache::default())),
+            failovers: Arc::new(SingleFlight::default()),
             config: config.clone(),
         })
     }
@@ -84,7 +143,7 @@ impl MangaDexClient {
             .form(&form_data)
             .send()
             .await
-            .map_err(|e| MangaDexError::NetworkError(e))?;
+            .map_err(MangaDexError::from)?;
 
         let status = response.status();
         if !status.is_success() {
@@ -140,7 +199,7 @@ impl MangaDexClient {
             .form(&form_data)
             .send()
             .await
-            .map_err(|e| MangaDexError::NetworkError(e))?;
+            .map_err(MangaDexError::from)?;
 
         let status = response.status();
         if !status.is_success() {
@@ -248,17 +307,35 @@ impl MangaDexClient {
     where
         T: serde::de::DeserializeOwned,
     {
-        if let Err(e) = self.ensure_authenticated().await {
-            return Err(backoff::Error::Permanent(e));
+        self.get_json_as(url, None).await
+    }
+
+    /// Like [`Self::get_json`], but on behalf of a MangaDex user when
+    /// `user_token` is given instead of the configured account.
+    async fn get_json_as<T>(
+        &self,
+        url: &str,
+        user_token: Option<&str>,
+    ) -> Result<T, backoff::Error<MangaDexError>>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        if user_token.is_none() {
+            if let Err(e) = self.ensure_authenticated().await {
+                return Err(backoff::Error::Permanent(e));
+            }
         }
 
         while self.rate_limiter.check().is_err() {
             tokio::time::sleep(Duration::from_millis(100)).await;
         }
 
-        let access_token = {
-            let tokens = self.tokens.lock().await;
-            tokens.as_ref().map(|t| t.access_token.clone())
+        let access_token = match user_token {
+            Some(token) => Some(token.to_string()),
+            None => {
+                let tokens = self.tokens.lock().await;
+                tokens.as_ref().map(|t| t.access_token.clone())
+            }
         };
 
         let mut request = self
@@ -280,24 +357,28 @@ impl MangaDexClient {
             .map_err(|e| {
                 if e.is_timeout() {
                     backoff::Error::Transient {
-                        err: MangaDexError::NetworkError(e),
+                        err: MangaDexError::from(e),
                         retry_after: None,
                     }
                 } else {
-                    backoff::Error::Permanent(MangaDexError::NetworkError(e))
+                    backoff::Error::Permanent(MangaDexError::from(e))
                 }
             })?;
 
         let status = response.status();
 
+        if status == 401 && user_token.is_some() {
+            return Err(backoff::Error::Permanent(MangaDexError::ApiError(
+                "MangaDex rejected the user's token".to_string(),
+            )));
+        }
+
         if status == 401 {
-            if let Err(_) = self.refresh_token().await {
-                if let Err(_) = self.authenticate().await {
-                    return Err(backoff::Error::Transient {
-                        err: MangaDexError::ApiError("Authentication failed".to_string()),
-                        retry_after: Some(Duration::from_secs(5)),
-                    });
-                }
+            if self.refresh_token().await.is_err() && self.authenticate().await.is_err() {
+                return Err(backoff::Error::Transient {
+                    err: MangaDexError::ApiError("Authentication failed".to_string()),
+                    retry_after: Some(Duration::from_secs(5)),
+                });
             }
             return Err(backoff::Error::Transient {
                 err: MangaDexError::ApiError("Token expired, retrying".to_string()),
@@ -337,16 +418,16 @@ impl MangaDexClient {
 
     pub async fn search_manga(
         &self,
-        query: &str,
+        filters: &SearchFilters,
         limit: u32,
         offset: u32,
     ) -> Result<MangaDexResponse<Vec<MangaDexManga>>, MangaDexError> {
         let url = format!(
-            "{}/manga?title={}&limit={}&offset={}&includes[]=cover_art&includes[]=author&includes[]=artist",
+            "{}/manga?limit={}&offset={}&includes[]=cover_art&includes[]=author&includes[]=artist{}",
             self.base_url,
-            urlencoding::encode(query),
             limit,
-            offset
+            offset,
+            search_params(filters)
         );
 
         self.request_with_retry(|| async { self.get_json(&url).await })
@@ -355,14 +436,16 @@ impl MangaDexClient {
 
     pub async fn get_popular_manga(
         &self,
+        content_rating: &[ContentRating],
         limit: u32,
         offset: u32,
     ) -> Result<MangaDexResponse<Vec<MangaDexManga>>, MangaDexError> {
         let url = format!(
-            "{}/manga?order[followedCount]=desc&limit={}&offset={}&includes[]=cover_art&includes[]=author&includes[]=artist",
+            "{}/manga?order[followedCount]=desc&limit={}&offset={}&includes[]=cover_art&includes[]=author&includes[]=artist{}",
             self.base_url,
             limit,
-            offset
+            offset,
+            content_rating_params(content_rating)
         );
 
         self.request_with_retry(|| async { self.get_json(&url).await })
@@ -371,14 +454,16 @@ impl MangaDexClient {
 
     pub async fn get_latest_manga(
         &self,
+        content_rating: &[ContentRating],
         limit: u32,
         offset: u32,
     ) -> Result<MangaDexResponse<Vec<MangaDexManga>>, MangaDexError> {
         let url = format!(
-            "{}/manga?order[latestUploadedChapter]=desc&limit={}&offset={}&includes[]=cover_art&includes[]=author&includes[]=artist",
+            "{}/manga?order[latestUploadedChapter]=desc&limit={}&offset={}&includes[]=cover_art&includes[]=author&includes[]=artist{}",
             self.base_url,
             limit,
-            offset
+            offset,
+            content_rating_params(content_rating)
         );
 
         self.request_with_retry(|| async { self.get_json(&url).await })
@@ -398,6 +483,87 @@ impl MangaDexClient {
         Ok(response.data)
     }
 
+    /// Looks up to 100 manga by ID in one request, whatever their rating.
+    pub async fn get_manga_batch(
+        &self,
+        ids: &[String],
+    ) -> Result<MangaDexResponse<Vec<MangaDexManga>>, MangaDexError> {
+        let ids: String = ids
+            .iter()
+            .map(|id| format!("&ids[]={}", urlencoding::encode(id)))
+            .collect();
+        let url = format!(
+            "{}/manga?limit=100&includes[]=cover_art&includes[]=author&includes[]=artist{}{}",
+            self.base_url,
+            ids,
+            content_rating_params(&ContentRating::ALL)
+        );
+
+        self.request_with_retry(|| async { self.get_json(&url).await })
+            .await
+    }
+
+    /// Manga followed by the MangaDex user owning `user_token`.
+    pub async fn get_followed_manga(
+        &self,
+        user_token: &str,
+        limit: u32,
+        offset: u32,
+    ) -> Result<MangaDexResponse<Vec<MangaDexManga>>, MangaDexError> {
+        let url = format!(
+            "{}/user/follows/manga?limit={}&offset={}&includes[]=cover_art&includes[]=author&includes[]=artist",
+            self.base_url, limit, offset
+        );
+
+        self.request_with_retry(|| async { self.get_json_as(&url, Some(user_token)).await })
+            .await
+    }
+
+    /// Reading status of every manga the user has given one, by manga ID.
+    pub async fn get_reading_statuses(
+        &self,
+        user_token: &str,
+    ) -> Result<HashMap<String, String>, MangaDexError> {
+        #[derive(serde::Deserialize)]
+        struct StatusesResponse {
+            #[serde(default)]
+            statuses: HashMap<String, String>,
+        }
+
+        let url = format!("{}/manga/status", self.base_url);
+        let response: StatusesResponse = self
+            .request_with_retry(|| async { self.get_json_as(&url, Some(user_token)).await })
+            .await?;
+
+        Ok(response.statuses)
+    }
+
+    /// Chapters the user has marked read, grouped by manga ID. Callers pass
+    /// at most 100 manga IDs.
+    pub async fn get_read_markers(
+        &self,
+        user_token: &str,
+        manga_ids: &[String],
+    ) -> Result<HashMap<String, Vec<String>>, MangaDexError> {
+        #[derive(serde::Deserialize)]
+        struct ReadMarkersResponse {
+            // An empty array rather than an object when nothing is read
+            #[serde(default)]
+            data: serde_json::Value,
+        }
+
+        let ids: String = manga_ids
+            .iter()
+            .map(|id| format!("&ids[]={}", urlencoding::encode(id)))
+            .collect();
+        let url = format!("{}/manga/read?grouped=true{}", self.base_url, ids);
+        let response: ReadMarkersResponse = self
+            .request_with_retry(|| async { self.get_json_as(&url, Some(user_token)).await })
+            .await?;
+
+        Ok(serde_json::from_value(response.data).unwrap_or_default())
+    }
+
     pub async fn get_chapters(
         &self,
         manga_id: &str,
@@ -405,10 +571,7 @@ impl MangaDexClient {
         limit: u32,
         offset: u32,
     ) -> Result<MangaDexResponse<Vec<MangaDexChapter>>, MangaDexError> {
-        let url = format!(
-            "{}/manga/{}/feed?translatedLanguage[]={}&limit={}&offset={}&includes[]=scanlation_group&order[chapter]=asc",
-            self.base_url, manga_id, lang, limit, offset
-        );
+        let url = feed_url(&self.base_url, manga_id, lang, limit, offset)?;
 
         self.request_with_retry(|| async { self.get_json(&url).await })
             .await
@@ -428,13 +591,306 @@ impl MangaDexClient {
         &self,
         chapter_id: &str,
     ) -> Result<ChapterAtHomeResponse, MangaDexError> {
-        let url = format!("{}/at-home/server/{}", self.base_url, chapter_id);
+        let ttl = Duration::from_secs(self.config.at_home_cache_ttl_secs);
+        if let Some(response) = self.at_home.lock().await.get(chapter_id, ttl) {
+            return Ok(response);
+        }
 
-        self.request_with_retry(|| async { self.get_json(&url).await })
+        self.fetch_at_home_server(chapter_id, false).await
+    }
+
+    /// Requests a new MangaDex@Home server for a chapter, bypassing and
+    /// replacing the cached one. Used when the current node fails.
+    ///
+    /// Concurrent failovers for a chapter share one upstream request, and for
+    /// [`FAILOVER_COOLDOWN`] afterwards the server it returned is handed out
+    /// instead of asking again. Returns `None` when a failover is cooling
+    /// down but its server is no longer cached.
+    pub async fn failover_chapter_pages(
+        &self,
+        chapter_id: &str,
+        force_port_443: bool,
+    ) -> Result<Option<ChapterAtHomeResponse>, MangaDexError> {
+        if let Some(recent) = self.at_home.lock().await.recent_failover(chapter_id) {
+            return Ok(recent);
+        }
+
+        let client = self.clone();
+        let chapter = chapter_id.to_string();
+        let server = self
+            .failovers
+            .run(chapter_id.to_string(), move || async move {
+                let server = client
+                    .fetch_at_home_server(&chapter, force_port_443)
+                    .await?;
+                client
+                    .at_home
+                    .lock()
+                    .await
+                    .failed_over
+                    .insert(chapter, Instant::now());
+                Ok(server)
+            })
+            .await?;
+
+        Ok(Some(server))
+    }
+
+    /// The chapter an at-home server was recently issued for, by chapter hash.
+    pub async fn chapter_for_hash(&self, hash: &str) -> Option<String> {
+        self.at_home
+            .lock()
+            .await
+            .chapter_by_hash
+            .get(hash)
+            .filter(|(seen_at, _)| seen_at.elapsed() < CHAPTER_HASH_TTL)
+            .map(|(_, chapter_id)| chapter_id.clone())
+    }
+
+    async fn fetch_at_home_server(
+        &self,
+        chapter_id: &str,
+        force_port_443: bool,
+    ) -> Result<ChapterAtHomeResponse, MangaDexError> {
+        let url = format!(
+            "{}/at-home/server/{}?forcePort443={}",
+            self.base_url,
+            urlencoding::encode(chapter_id),
+            force_port_443
+        );
+
+        let response: ChapterAtHomeResponse = self
+            .request_with_retry(|| async { self.get_json(&url).await })
+            .await?;
+
+        let ttl = Duration::from_secs(self.config.at_home_cache_ttl_secs);
+        self.at_home
+            .lock()
             .await
+            .insert(chapter_id, response.clone(), ttl);
+
+        Ok(response)
+    }
+
+    pub async fn get_tags(&self) -> Result<Vec<MangaDexTag>, MangaDexError> {
+        let url = format!("{}/manga/tag", self.base_url);
+
+        let response: MangaDexResponse<Vec<MangaDexTag>> = self
+            .request_with_retry(|| async { self.get_json(&url).await })
+            .await?;
+
+        Ok(response.data)
+    }
+
+    /// The underlying HTTP client, shared with the MangaDex@Home load
+    /// reporter.
+    pub fn http(&self) -> &Client {
+        &self.http
     }
 
     pub fn get_cover_url(&self, manga_id: &str, cover_filename: &str) -> String {
         format!("{}/covers/{}/{}", self.base_url, manga_id, cover_filename)
     }
 }
+
+/// MangaDex falls back to safe, suggestive and erotica when no
+/// `contentRating[]` is sent, so callers always pass the allowed set.
+fn content_rating_params(content_rating: &[ContentRating]) -> String {
+    content_rating
+        .iter()
+        .map(|rating| format!("&contentRating[]={}", rating.as_str()))
+        .collect()
+}
+
+/// URL of one page of a manga's chapter feed. The language comes from user
+/// preferences, so it is encoded rather than trusted.
+fn feed_url(
+    base_url: &str,
+    manga_id: &str,
+    lang: &str,
+    limit: u32,
+    offset: u32,
+) -> Result<String, MangaDexError> {
+    let mut url = Url::parse(&format!("{}/manga/{}/feed", base_url, manga_id))
+        .map_err(|e| MangaDexError::Internal(anyhow::anyhow!("Invalid feed URL: {}", e)))?;
+    url.query_pairs_mut()
+        .append_pair("translatedLanguage[]", lang)
+        .append_pair("limit", &limit.to_string())
+        .append_pair("offset", &offset.to_string())
+        .append_pair("includes[]", "scanlation_group")
+        .append_pair("order[chapter]", "asc");
+
+    Ok(url.into())
+}
+
+/// Encodes search filters as MangaDex `/manga` query parameters.
+fn search_params(filters: &SearchFilters) -> String {
+    let mut params = String::new();
+    let mut push = |key: &str, value: &str| {
+        params.push_str(&format!("&{}={}", key, urlencoding::encode(value)));
+    };
+
+    if let Some(title) = filters.title.as_deref().filter(|t| !t.is_empty()) {
+        push("title", title);
+    }
+    for tag in &filters.included_tags {
+        push("includedTags[]", tag);
+    }
+    if let Some(mode) = filters.included_tags_mode {
+        push("includedTagsMode", mode.as_str());
+    }
+    for tag in &filters.excluded_tags {
+        push("excludedTags[]", tag);
+    }
+    if let Some(mode) = filters.excluded_tags_mode {
+        push("excludedTagsMode", mode.as_str());
+    }
+    for status in &filters.status {
+        push("status[]", status.as_str());
+    }
+    for rating in &filters.content_rating {
+        push("contentRating[]", rating.as_str());
+    }
+    for demographic in &filters.demographic {
+        push("publicationDemographic[]", demographic.as_str());
+    }
+    for author in &filters.authors {
+        push("authors[]", author);
+    }
+    for artist in &filters.artists {
+        push("artists[]", artist);
+    }
+    for lang in &filters.original_language {
+        push("originalLanguage[]", lang);
+    }
+    for lang in &filters.available_translated_language {
+        push("availableTranslatedLanguage[]", lang);
+    }
+    if let Some(year) = filters.year {
+        push("year", &year.to_string());
+    }
+    if let Some(field) = filters.order_by {
+        let key = match field {
+            SortField::Relevance => "order[relevance]",
+            SortField::Title => "order[title]",
+            SortField::Year => "order[year]",
+            SortField::Rating => "order[rating]",
+            SortField::FollowedCount => "order[followedCount]",
+            SortField::LatestUploadedChapter => "order[latestUploadedChapter]",
+            SortField::CreatedAt => "order[createdAt]",
+            SortField::UpdatedAt => "order[updatedAt]",
+        };
+        push(key, filters.order_direction.as_str());
+    }
+
+    params
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::filters::{Demographic, SortDirection};
+
+    fn at_home_server(base_url: &str, hash: &str) -> ChapterAtHomeResponse {
+        ChapterAtHomeResponse {
+            result: "ok".to_string(),
+            base_url: base_url.to_string(),
+            chapter: ChapterAtHomeData {
+                hash: hash.to_string(),
+                data: Vec::new(),
+                data_saver: Vec::new(),
+            },
+        }
+    }
+
+    #[test]
+    fn at_home_cache_remembers_chapter_by_hash() {
+        let ttl = Duration::from_secs(60);
+        let mut cache = AtHomeCache::default();
+        cache.insert("chapter-1", at_home_server("https://a", "hash-1"), ttl);
+
+        assert_eq!(cache.chapter_by_hash["hash-1"].1, "chapter-1");
+        assert_eq!(cache.get("chapter-1", ttl).unwrap().base_url, "https://a");
+        assert!(cache.get("chapter-1", Duration::ZERO).is_none());
+    }
+
+    #[test]
+    fn at_home_cache_hands_out_recent_failovers() {
+        let ttl = Duration::from_secs(60);
+        let mut cache = AtHomeCache::default();
+        cache.insert("chapter-1", at_home_server("https://a", "hash-1"), ttl);
+        assert!(cache.recent_failover("chapter-1").is_none());
+
+        cache.insert("chapter-1", at_home_server("https://b", "hash-1"), ttl);
+        cache
+            .failed_over
+            .insert("chapter-1".to_string(), Instant::now());
+        let recent = cache.recent_failover("chapter-1").unwrap().unwrap();
+        assert_eq!(recent.base_url, "https://b");
+
+        cache
+            .failed_over
+            .insert("chapter-1".to_string(), Instant::now() - FAILOVER_COOLDOWN);
+        assert!(cache.recent_failover("chapter-1").is_none());
+    }
+
+    #[test]
+    fn feed_url_encodes_the_language() {
+        let url = feed_url(
+            "https://api.mangadex.org",
+            "abc",
+            "en&contentRating[]=pornographic",
+            100,
+            200,
+        )
+        .unwrap();
+        let url = Url::parse(&url).unwrap();
+
+        assert_eq!(url.path(), "/manga/abc/feed");
+        let pairs: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+        let value = |key: &str| {
+            pairs
+                .iter()
+                .filter(|(k, _)| k == key)
+                .map(|(_, v)| v.as_str())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            value("translatedLanguage[]"),
+            ["en&contentRating[]=pornographic"]
+        );
+        assert!(value("contentRating[]").is_empty());
+        assert_eq!(value("limit"), ["100"]);
+        assert_eq!(value("offset"), ["200"]);
+    }
+
+    #[test]
+    fn search_params_encodes_list_filters() {
+        let filters = SearchFilters {
+            title: Some("one piece".to_string()),
+            demographic: vec![Demographic::Shounen, Demographic::None],
+            authors: vec!["author-1".to_string()],
+            artists: vec!["artist-1".to_string(), "artist-2".to_string()],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            search_params(&filters),
+            "&title=one%20piece\
+             &publicationDemographic[]=shounen&publicationDemographic[]=none\
+             &authors[]=author-1\
+             &artists[]=artist-1&artists[]=artist-2"
+        );
+    }
+
+    #[test]
+    fn search_params_orders_by_field() {
+        let filters = SearchFilters {
+            order_by: Some(SortField::FollowedCount),
+            order_direction: SortDirection::Asc,
+            ..Default::default()
+        };
+
+        assert_eq!(search_params(&filters), "&order[followedCount]=asc");
+    }
+}
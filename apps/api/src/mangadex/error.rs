@@ -1,7 +1,11 @@
+use std::sync::Arc;
+
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
 use thiserror::Error;
 
+use crate::source::SourceError;
+
 #[derive(Error, Debug)]
 pub enum MangaDexError {
     #[error("MangaDex API error: {0}")]
@@ -16,13 +20,45 @@ pub enum MangaDexError {
     #[error("Invalid response format")]
     InvalidResponse,
 
+    /// Shared so a coalesced failure reaches every waiter unchanged.
     #[error("Network error: {0}")]
-    NetworkError(#[from] reqwest::Error),
+    NetworkError(#[source] Arc<reqwest::Error>),
 
     #[error("Internal error")]
     Internal(#[from] anyhow::Error),
 }
 
+impl MangaDexError {
+    /// Upstream failures that a cached copy can paper over.
+    pub fn is_upstream_failure(&self) -> bool {
+        matches!(
+            self,
+            MangaDexError::NetworkError(_)
+                | MangaDexError::RateLimited
+                | MangaDexError::ApiError(_)
+        )
+    }
+}
+
+impl From<reqwest::Error> for MangaDexError {
+    fn from(error: reqwest::Error) -> Self {
+        MangaDexError::NetworkError(Arc::new(error))
+    }
+}
+
+impl From<SourceError> for MangaDexError {
+    fn from(error: SourceError) -> Self {
+        match error {
+            SourceError::NotFound => MangaDexError::NotFound,
+            SourceError::RateLimited => MangaDexError::RateLimited,
+            SourceError::Upstream(message) => MangaDexError::ApiError(message),
+            SourceError::InvalidResponse => MangaDexError::InvalidResponse,
+            SourceError::Network(e) => MangaDexError::NetworkError(e),
+            SourceError::Internal(e) => MangaDexError::Internal(e),
+        }
+    }
+}
+
 impl IntoResponse for MangaDexError {
     fn into_response(self) -> Response {
         let (status, error_code) = match &self {
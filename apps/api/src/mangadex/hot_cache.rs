@@ -0,0 +1,254 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+struct Entry<T> {
+    value: T,
+    /// When the row was written to Postgres, used for TTL decisions upstream.
+    cached_at: DateTime<Utc>,
+    inserted: Instant,
+    last_used: u64,
+}
+
+struct Lru<T> {
+    entries: HashMap<String, Entry<T>>,
+    order: BTreeMap<u64, String>,
+    tick: u64,
+}
+
+impl<T> Lru<T> {
+    fn remove(&mut self, key: &str) -> Option<Entry<T>> {
+        let entry = self.entries.remove(key)?;
+        self.order.remove(&entry.last_used);
+        Some(entry)
+    }
+}
+
+/// Counters and size of a [`HotCache`].
+#[derive(Debug, Serialize)]
+pub struct HotCacheStats {
+    pub entries: usize,
+    pub capacity: usize,
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    pub hit_rate: f64,
+}
+
+/// Bounded in-memory LRU of decoded cache rows, sitting in front of
+/// Postgres.
+///
+/// Entries also expire after `ttl` so that rows upserted by other instances
+/// are picked up; rows upserted by this instance are invalidated directly.
+pub struct HotCache<T> {
+    inner: Mutex<Lru<T>>,
+    capacity: usize,
+    ttl: Duration,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+}
+
+impl<T: Clone> HotCache<T> {
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            inner: Mutex::new(Lru {
+                entries: HashMap::new(),
+                order: BTreeMap::new(),
+                tick: 0,
+            }),
+            capacity,
+            ttl,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns the value and its Postgres `cached_at`, if present and not
+    /// expired.
+    pub fn get(&self, key: &str) -> Option<(T, DateTime<Utc>)> {
+        let mut lru = self.inner.lock().unwrap();
+
+        let expired = match lru.entries.get(key) {
+            Some(entry) => entry.inserted.elapsed() >= self.ttl,
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                return None;
+            }
+        };
+        if expired {
+            lru.remove(key);
+            self.misses.fetch_add(1, Ordering::Relaxed);
+            return None;
+        }
+
+        lru.tick += 1;
+        let tick = lru.tick;
+        let entry = lru.entries.get_mut(key)?;
+        let previous = std::mem::replace(&mut entry.last_used, tick);
+        let found = (entry.value.clone(), entry.cached_at);
+        lru.order.remove(&previous);
+        lru.order.insert(tick, key.to_string());
+
+        self.hits.fetch_add(1, Ordering::Relaxed);
+        Some(found)
+    }
+
+    pub fn insert(&self, key: String, value: T, cached_at: DateTime<Utc>) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut lru = self.inner.lock().unwrap();
+        lru.remove(&key);
+
+        while lru.entries.len() >= self.capacity {
+            let Some((_, oldest)) = lru.order.pop_first() else {
+                break;
+            };
+            lru.entries.remove(&oldest);
+            self.evictions.fetch_add(1, Ordering::Relaxed);
+        }
+
+        lru.tick += 1;
+        let tick = lru.tick;
+        lru.order.insert(tick, key.clone());
+        lru.entries.insert(
+            key,
+            Entry {
+                value,
+                cached_at,
+                inserted: Instant::now(),
+                last_used: tick,
+            },
+        );
+    }
+
+    pub fn invalidate(&self, key: &str) {
+        self.inner.lock().unwrap().remove(key);
+    }
+
+    pub fn stats(&self) -> HotCacheStats {
+        let hits = self.hits.load(Ordering::Relaxed);
+        let misses = self.misses.load(Ordering::Relaxed);
+        let lookups = hits + misses;
+
+        HotCacheStats {
+            entries: self.inner.lock().unwrap().entries.len(),
+            capacity: self.capacity,
+            hits,
+            misses,
+            evictions: self.evictions.load(Ordering::Relaxed),
+            hit_rate: if lookups == 0 {
+                0.0
+            } else {
+                hits as f64 / lookups as f64
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR: Duration = Duration::from_secs(3600);
+
+    fn cache(capacity: usize, ttl: Duration) -> HotCache<u32> {
+        HotCache::new(capacity, ttl)
+    }
+
+    #[test]
+    fn evicts_the_least_recently_used_entry() {
+        let cache = cache(2, HOUR);
+        cache.insert("a".into(), 1, Utc::now());
+        cache.insert("b".into(), 2, Utc::now());
+
+        // Reading "a" leaves "b" as the oldest
+        assert!(cache.get("a").is_some());
+        cache.insert("c".into(), 3, Utc::now());
+
+        assert!(cache.get("b").is_none());
+        assert_eq!(cache.get("a").map(|(v, _)| v), Some(1));
+        assert_eq!(cache.get("c").map(|(v, _)| v), Some(3));
+        assert_eq!(cache.stats().evictions, 1);
+        assert_eq!(cache.stats().entries, 2);
+    }
+
+    #[test]
+    fn replacing_an_entry_does_not_evict() {
+        let cache = cache(2, HOUR);
+        cache.insert("a".into(), 1, Utc::now());
+        cache.insert("b".into(), 2, Utc::now());
+        cache.insert("a".into(), 10, Utc::now());
+
+        assert_eq!(cache.get("a").map(|(v, _)| v), Some(10));
+        assert_eq!(cache.get("b").map(|(v, _)| v), Some(2));
+        assert_eq!(cache.stats().evictions, 0);
+    }
+
+    #[test]
+    fn expires_entries_after_the_ttl() {
+        let cache = cache(2, Duration::from_millis(20));
+        cache.insert("a".into(), 1, Utc::now());
+        assert!(cache.get("a").is_some());
+
+        std::thread::sleep(Duration::from_millis(30));
+
+        assert!(cache.get("a").is_none());
+        assert_eq!(cache.stats().entries, 0);
+    }
+
+    #[test]
+    fn invalidate_drops_an_upserted_entry() {
+        let cache = cache(2, HOUR);
+        cache.insert("a".into(), 1, Utc::now());
+        cache.insert("b".into(), 2, Utc::now());
+
+        cache.invalidate("a");
+
+        assert!(cache.get("a").is_none());
+        assert!(cache.get("b").is_some());
+        assert_eq!(cache.stats().evictions, 0);
+    }
+
+    #[test]
+    fn keeps_the_postgres_cached_at() {
+        let cache = cache(1, HOUR);
+        let cached_at = Utc::now() - chrono::Duration::hours(5);
+        cache.insert("a".into(), 1, cached_at);
+
+        assert_eq!(cache.get("a"), Some((1, cached_at)));
+    }
+
+    #[test]
+    fn counts_hits_and_misses() {
+        let cache = cache(2, HOUR);
+        assert_eq!(cache.stats().hit_rate, 0.0);
+
+        cache.insert("a".into(), 1, Utc::now());
+        cache.get("a");
+        cache.get("a");
+        cache.get("a");
+        cache.get("missing");
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses), (3, 1));
+        assert_eq!(stats.hit_rate, 0.75);
+        assert_eq!(stats.capacity, 2);
+    }
+
+    #[test]
+    fn zero_capacity_stores_nothing() {
+        let cache = cache(0, HOUR);
+        cache.insert("a".into(), 1, Utc::now());
+
+        assert!(cache.get("a").is_none());
+        assert_eq!(cache.stats().entries, 0);
+    }
+}
@@ -1,6 +1,10 @@
 pub mod cache;
 pub mod client;
 pub mod error;
+pub mod hot_cache;
+pub mod report;
+pub mod single_flight;
+pub mod source;
 pub mod types;
 
 pub use client::MangaDexClient;
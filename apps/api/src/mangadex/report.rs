@@ -0,0 +1,243 @@
+use std::time::{Duration, Instant};
+
+use reqwest::Client;
+use serde::Serialize;
+use tokio::sync::mpsc;
+
+use crate::config::MangaDexConfig;
+
+/// Reports are flushed once this many are queued, or on every tick.
+const BATCH_SIZE: usize = 50;
+const FLUSH_INTERVAL: Duration = Duration::from_secs(10);
+const QUEUE_CAPACITY: usize = 1024;
+
+/// Outcome of a single page fetch from a MangaDex@Home node, in the shape
+/// expected by `POST https://api.mangadex.network/report`.
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(test, derive(serde::Deserialize))]
+pub struct PageReport {
+    pub url: String,
+    pub success: bool,
+    pub cached: bool,
+    pub bytes: u64,
+    /// Milliseconds from sending the request to receiving the last byte.
+    pub duration: u64,
+}
+
+impl PageReport {
+    pub fn new(url: &str, success: bool, cached: bool, bytes: u64, started: Instant) -> Self {
+        Self {
+            url: url.to_string(),
+            success,
+            cached,
+            bytes,
+            duration: started.elapsed().as_millis() as u64,
+        }
+    }
+}
+
+/// Whether a host is a MangaDex@Home node. Only those fetches are reported.
+pub fn is_at_home_host(host: &str) -> bool {
+    host.ends_with(".mangadex.network")
+}
+
+/// Whether an at-home response was served from the node's cache, based on
+/// its `X-Cache` header.
+pub fn is_cache_hit(headers: &reqwest::header::HeaderMap) -> bool {
+    headers
+        .get("X-Cache")
+        .and_then(|h| h.to_str().ok())
+        .is_some_and(|v| v.starts_with("HIT"))
+}
+
+/// Queues page fetch outcomes and ships them to the MangaDex@Home report
+/// endpoint from a background task.
+pub struct HomeReporter {
+    sender: Option<mpsc::Sender<PageReport>>,
+}
+
+impl HomeReporter {
+    /// A reporter that drops everything, used when reporting is switched off.
+    pub fn disabled() -> Self {
+        Self { sender: None }
+    }
+
+    /// Starts the background sender. Must be called from within a Tokio runtime.
+    pub fn spawn(config: &MangaDexConfig, http: Client) -> Self {
+        if !config.report_enabled {
+            return Self::disabled();
+        }
+
+        Self::start(http, config.report_url.clone())
+    }
+
+    fn start(http: Client, report_url: String) -> Self {
+        let (sender, receiver) = mpsc::channel(QUEUE_CAPACITY);
+        tokio::spawn(run(receiver, http, report_url));
+
+        Self {
+            sender: Some(sender),
+        }
+    }
+
+    /// A reporter whose reports land in the returned receiver instead of
+    /// being sent anywhere.
+    #[cfg(test)]
+    pub(crate) fn channel() -> (Self, mpsc::Receiver<PageReport>) {
+        let (sender, receiver) = mpsc::channel(QUEUE_CAPACITY);
+        (
+            Self {
+                sender: Some(sender),
+            },
+            receiver,
+        )
+    }
+
+    pub fn record(&self, report: PageReport) {
+        let Some(sender) = &self.sender else {
+            return;
+        };
+
+        if sender.try_send(report).is_err() {
+            tracing::debug!("MangaDex@Home report queue full, dropping report");
+        }
+    }
+}
+
+async fn run(mut receiver: mpsc::Receiver<PageReport>, http: Client, report_url: String) {
+    let mut batch = Vec::with_capacity(BATCH_SIZE);
+    let mut interval = tokio::time::interval(FLUSH_INTERVAL);
+
+    loop {
+        tokio::select! {
+            report = receiver.recv() => match report {
+                Some(report) => {
+                    batch.push(report);
+                    if batch.len() >= BATCH_SIZE {
+                        flush(&http, &report_url, &mut batch).await;
+                    }
+                }
+                None => {
+                    flush(&http, &report_url, &mut batch).await;
+                    return;
+                }
+            },
+            _ = interval.tick() => flush(&http, &report_url, &mut batch).await,
+        }
+    }
+}
+
+async fn flush(http: &Client, report_url: &str, batch: &mut Vec<PageReport>) {
+    if batch.is_empty() {
+        return;
+    }
+
+    let sends = batch
+        .drain(..)
+        .map(|report| async move { http.post(report_url).json(&report).send().await });
+
+    let failed = futures_util::future::join_all(sends)
+        .await
+        .into_iter()
+        .filter(|result| !matches!(result, Ok(r) if r.status().is_success()))
+        .count();
+
+    if failed > 0 {
+        tracing::debug!("{} MangaDex@Home reports failed to send", failed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{extract::State, routing::post, Json, Router};
+
+    /// Serves a local report endpoint and returns its URL along with the
+    /// reports it receives.
+    async fn mock_report_endpoint() -> (String, mpsc::UnboundedReceiver<PageReport>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let app = Router::new()
+            .route(
+                "/report",
+                post(
+                    |State(sender): State<mpsc::UnboundedSender<PageReport>>,
+                     Json(report): Json<PageReport>| async move {
+                        let _ = sender.send(report);
+                    },
+                ),
+            )
+            .with_state(sender);
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        (format!("http://{addr}/report"), receiver)
+    }
+
+    async fn next(receiver: &mut mpsc::UnboundedReceiver<PageReport>) -> PageReport {
+        tokio::time::timeout(Duration::from_secs(5), receiver.recv())
+            .await
+            .expect("report was not sent")
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn sends_queued_reports_when_closed() {
+        let (url, mut received) = mock_report_endpoint().await;
+        let reporter = HomeReporter::start(Client::new(), url);
+
+        let started = Instant::now();
+        reporter.record(PageReport::new(
+            "https://a.mangadex.network/1",
+            true,
+            true,
+            10,
+            started,
+        ));
+        reporter.record(PageReport::new(
+            "https://a.mangadex.network/2",
+            false,
+            false,
+            0,
+            started,
+        ));
+        drop(reporter);
+
+        let mut reports = [next(&mut received).await, next(&mut received).await];
+        reports.sort_by(|a, b| a.url.cmp(&b.url));
+        assert!(reports[0].success && reports[0].cached);
+        assert_eq!(reports[0].bytes, 10);
+        assert!(!reports[1].success && !reports[1].cached);
+    }
+
+    #[tokio::test]
+    async fn sends_a_full_batch_without_waiting_for_the_tick() {
+        let (url, mut received) = mock_report_endpoint().await;
+        let reporter = HomeReporter::start(Client::new(), url);
+
+        let started = Instant::now();
+        for n in 0..BATCH_SIZE {
+            let url = format!("https://a.mangadex.network/{n}");
+            reporter.record(PageReport::new(&url, true, false, 1, started));
+        }
+
+        for _ in 0..BATCH_SIZE {
+            next(&mut received).await;
+        }
+        drop(reporter);
+    }
+
+    #[test]
+    fn recognises_at_home_hosts_and_cache_hits() {
+        assert!(is_at_home_host("abc.xyz.mangadex.network"));
+        assert!(!is_at_home_host("uploads.mangadex.org"));
+
+        let mut headers = reqwest::header::HeaderMap::new();
+        assert!(!is_cache_hit(&headers));
+        headers.insert("X-Cache", "HIT from node".parse().unwrap());
+        assert!(is_cache_hit(&headers));
+        headers.insert("X-Cache", "MISS".parse().unwrap());
+        assert!(!is_cache_hit(&headers));
+    }
+}
@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+use futures_util::future::{BoxFuture, FutureExt, Shared};
+
+use crate::mangadex::error::MangaDexError;
+
+type SharedResult<T> = Result<T, Arc<MangaDexError>>;
+type Flight<T> = Shared<BoxFuture<'static, SharedResult<T>>>;
+
+/// Collapses concurrent calls with the same key into a single execution.
+///
+/// The first caller for a key starts the work on its own task; everyone who
+/// asks for the same key before it finishes waits on that task instead of
+/// starting another. The work runs to completion even if every caller goes
+/// away, so a cancelled request never leaves a half-finished flight behind.
+pub struct SingleFlight<T> {
+    in_flight: Arc<Mutex<HashMap<String, Flight<T>>>>,
+}
+
+impl<T> Default for SingleFlight<T> {
+    fn default() -> Self {
+        Self {
+            in_flight: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+}
+
+impl<T> SingleFlight<T>
+where
+    T: Clone + Send + Sync + 'static,
+{
+    pub async fn run<F, Fut>(&self, key: String, work: F) -> Result<T, MangaDexError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, MangaDexError>> + Send + 'static,
+    {
+        let flight = {
+            let mut in_flight = self.in_flight.lock().unwrap();
+
+            match in_flight.get(&key) {
+                Some(flight) => flight.clone(),
+                None => {
+                    // Spawned while holding the lock, so the task can't remove
+                    // its entry before it has been inserted
+                    let registry = self.in_flight.clone();
+                    let task_key = key.clone();
+                    let fut = work();
+                    let handle = tokio::spawn(async move {
+                        let result = fut.await.map_err(Arc::new);
+                        registry.lock().unwrap().remove(&task_key);
+                        result
+                    });
+
+                    let flight = async move {
+                        handle.await.unwrap_or_else(|e| {
+                            Err(Arc::new(MangaDexError::Internal(anyhow::anyhow!(
+                                "Coalesced request panicked: {}",
+                                e
+                            ))))
+                        })
+                    }
+                    .boxed()
+                    .shared();
+
+                    in_flight.insert(key, flight.clone());
+                    flight
+                }
+            }
+        };
+
+        flight.await.map_err(unshare)
+    }
+}
+
+/// Recovers an owned error for one waiter. Every waiter gets the same variant,
+/// so one upstream failure maps to one status however the calls were timed.
+fn unshare(error: Arc<MangaDexError>) -> MangaDexError {
+    Arc::try_unwrap(error).unwrap_or_else(|error| match &*error {
+        MangaDexError::ApiError(message) => MangaDexError::ApiError(message.clone()),
+        MangaDexError::RateLimited => MangaDexError::RateLimited,
+        MangaDexError::NotFound => MangaDexError::NotFound,
+        MangaDexError::InvalidResponse => MangaDexError::InvalidResponse,
+        MangaDexError::NetworkError(e) => MangaDexError::NetworkError(e.clone()),
+        MangaDexError::Internal(e) => MangaDexError::Internal(anyhow::anyhow!("{:#}", e)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use futures_util::future::join_all;
+
+    const CALLERS: usize = 8;
+
+    /// Starts `CALLERS` concurrent runs for one key, each of which would
+    /// count itself and then return `outcome` after a short wait.
+    async fn run_concurrently(
+        outcome: fn() -> Result<u32, MangaDexError>,
+    ) -> (usize, Vec<Result<u32, MangaDexError>>) {
+        let flights = SingleFlight::default();
+        let runs = Arc::new(AtomicUsize::new(0));
+
+        let results = join_all((0..CALLERS).map(|_| {
+            let runs = runs.clone();
+            flights.run("key".to_string(), move || async move {
+                runs.fetch_add(1, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                outcome()
+            })
+        }))
+        .await;
+
+        (runs.load(Ordering::SeqCst), results)
+    }
+
+    #[tokio::test]
+    async fn concurrent_callers_share_one_run() {
+        let (runs, results) = run_concurrently(|| Ok(7)).await;
+
+        assert_eq!(runs, 1);
+        assert!(results.iter().all(|result| matches!(result, Ok(7))));
+    }
+
+    #[tokio::test]
+    async fn every_caller_gets_the_same_error() {
+        let (runs, results) = run_concurrently(|| Err(MangaDexError::RateLimited)).await;
+
+        assert_eq!(runs, 1);
+        assert!(results
+            .iter()
+            .all(|result| matches!(result, Err(MangaDexError::RateLimited))));
+    }
+
+    #[test]
+    fn network_errors_keep_their_kind_for_every_waiter() {
+        let error = reqwest::Client::new().get("not a url").build().unwrap_err();
+        let shared = Arc::new(MangaDexError::from(error));
+        let _other_waiter = shared.clone();
+
+        assert!(matches!(unshare(shared), MangaDexError::NetworkError(_)));
+    }
+
+    #[tokio::test]
+    async fn a_finished_key_runs_again() {
+        let flights = SingleFlight::default();
+
+        assert_eq!(
+            flights.run("key".into(), || async { Ok(1) }).await.unwrap(),
+            1
+        );
+        assert_eq!(
+            flights.run("key".into(), || async { Ok(2) }).await.unwrap(),
+            2
+        );
+    }
+}
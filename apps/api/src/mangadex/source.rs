@@ -0,0 +1,207 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+
+use crate::mangadex::client::MangaDexClient;
+use crate::mangadex::error::MangaDexError;
+use crate::mangadex::types::*;
+use crate::source::{ContentRating, MangaPage, MangaSource, SearchFilters, SourceError};
+
+pub const SOURCE_ID: &str = "mangadex";
+
+impl From<MangaDexError> for SourceError {
+    fn from(error: MangaDexError) -> Self {
+        match error {
+            MangaDexError::ApiError(message) => SourceError::Upstream(message),
+            MangaDexError::RateLimited => SourceError::RateLimited,
+            MangaDexError::NotFound => SourceError::NotFound,
+            MangaDexError::InvalidResponse => SourceError::InvalidResponse,
+            MangaDexError::NetworkError(e) => SourceError::Network(e),
+            MangaDexError::Internal(e) => SourceError::Internal(e),
+        }
+    }
+}
+
+fn into_manga_page(
+    response: MangaDexResponse<Vec<MangaDexManga>>,
+    limit: u32,
+    offset: u32,
+) -> Result<MangaPage, SourceError> {
+    let data = response
+        .data
+        .into_iter()
+        .map(Manga::try_from)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(MangaPage {
+        total: response.total.unwrap_or(data.len() as u32),
+        limit,
+        offset,
+        data,
+    })
+}
+
+#[async_trait]
+impl MangaSource for MangaDexClient {
+    fn id(&self) -> &'static str {
+        SOURCE_ID
+    }
+
+    async fn search(
+        &self,
+        filters: &SearchFilters,
+        limit: u32,
+        offset: u32,
+    ) -> Result<MangaPage, SourceError> {
+        let response = self.search_manga(filters, limit, offset).await?;
+        into_manga_page(response, limit, offset)
+    }
+
+    async fn popular(
+        &self,
+        content_rating: &[ContentRating],
+        limit: u32,
+        offset: u32,
+    ) -> Result<MangaPage, SourceError> {
+        let response = self
+            .get_popular_manga(content_rating, limit, offset)
+            .await?;
+        into_manga_page(response, limit, offset)
+    }
+
+    async fn latest(
+        &self,
+        content_rating: &[ContentRating],
+        limit: u32,
+        offset: u32,
+    ) -> Result<MangaPage, SourceError> {
+        let response = self.get_latest_manga(content_rating, limit, offset).await?;
+        into_manga_page(response, limit, offset)
+    }
+
+    async fn get_manga(&self, manga_id: &str) -> Result<Manga, SourceError> {
+        let manga = MangaDexClient::get_manga(self, manga_id).await?;
+        Ok(manga.try_into()?)
+    }
+
+    async fn get_mangas(&self, manga_ids: &[String]) -> Result<Vec<Manga>, SourceError> {
+        let response = self.get_manga_batch(manga_ids).await?;
+        Ok(into_manga_page(response, manga_ids.len() as u32, 0)?.data)
+    }
+
+    async fn tracker_links(&self, manga_id: &str) -> Result<HashMap<String, String>, SourceError> {
+        let manga = MangaDexClient::get_manga(self, manga_id).await?;
+        Ok(manga.attributes.links.unwrap_or_default())
+    }
+
+    async fn get_chapters(&self, manga_id: &str, lang: &str) -> Result<Vec<Chapter>, SourceError> {
+        let mut all_chapters = Vec::new();
+        let mut offset = 0;
+        let limit = 100;
+
+        loop {
+            let response =
+                MangaDexClient::get_chapters(self, manga_id, lang, limit, offset).await?;
+
+            if response.data.is_empty() {
+                break;
+            }
+
+            for mangadex_chapter in response.data {
+                all_chapters.push(Chapter::try_from(mangadex_chapter)?);
+            }
+
+            offset += limit;
+            match response.total {
+                Some(total) if offset < total => {}
+                _ => break,
+            }
+        }
+
+        Ok(all_chapters)
+    }
+
+    async fn get_chapter(&self, chapter_id: &str) -> Result<Chapter, SourceError> {
+        let chapter = MangaDexClient::get_chapter(self, chapter_id).await?;
+        Ok(chapter.try_into()?)
+    }
+
+    async fn get_chapter_pages(&self, chapter_id: &str) -> Result<ChapterPages, SourceError> {
+        let chapter = MangaDexClient::get_chapter(self, chapter_id).await?;
+
+        if chapter.attributes.external_url.is_some() {
+            return Err(SourceError::Upstream(
+                "This chapter is hosted externally and does not have pages available".to_string(),
+            ));
+        }
+
+        // Check if chapter has pages
+        if chapter.attributes.pages == 0 {
+            return Err(SourceError::Upstream(
+                "This chapter has no pages available".to_string(),
+            ));
+        }
+
+        let response = MangaDexClient::get_chapter_pages(self, chapter_id).await?;
+
+        if response.chapter.data.is_empty() && response.chapter.data_saver.is_empty() {
+            return Err(SourceError::Upstream(format!(
+                "Chapter has {} pages but no image data available",
+                chapter.attributes.pages
+            )));
+        }
+
+        let page_filenames = if !response.chapter.data.is_empty() {
+            &response.chapter.data
+        } else {
+            &response.chapter.data_saver
+        };
+
+        let pages: Vec<PageInfo> = page_filenames
+            .iter()
+            .enumerate()
+            .map(|(idx, filename)| {
+                let page_number = idx + 1;
+
+                let (url, url_data_saver) = if !response.chapter.data.is_empty() {
+                    let url = format!(
+                        "{}/data/{}/{}",
+                        response.base_url, response.chapter.hash, filename
+                    );
+                    let url_data_saver = response
+                        .chapter
+                        .data_saver
+                        .get(idx)
+                        .map(|ds_filename| {
+                            format!(
+                                "{}/data-saver/{}/{}",
+                                response.base_url, response.chapter.hash, ds_filename
+                            )
+                        })
+                        .unwrap_or_else(|| url.clone());
+                    (url, url_data_saver)
+                } else {
+                    let url_data_saver = format!(
+                        "{}/data-saver/{}/{}",
+                        response.base_url, response.chapter.hash, filename
+                    );
+                    (url_data_saver.clone(), url_data_saver)
+                };
+
+                PageInfo {
+                    page_number: page_number as u32,
+                    filename: filename.clone(),
+                    url,
+                    url_data_saver,
+                }
+            })
+            .collect();
+
+        Ok(ChapterPages {
+            chapter_id: chapter_id.to_string(),
+            base_url: response.base_url,
+            hash: response.chapter.hash,
+            pages,
+        })
+    }
+}
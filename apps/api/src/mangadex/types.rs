@@ -35,6 +35,9 @@ pub struct MangaAttributes {
     pub content_rating: String,
     #[serde(default)]
     pub tags: Vec<MangaDexTag>,
+    /// External site IDs keyed by site code (`al`, `mal`, `mu`, ...).
+    #[serde(default)]
+    pub links: Option<std::collections::HashMap<String, String>>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -119,7 +122,7 @@ pub struct ChapterAttributes {
     pub version: u32,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct ChapterAtHomeResponse {
     pub result: String,
     #[serde(rename = "baseUrl")]
@@ -127,7 +130,7 @@ pub struct ChapterAtHomeResponse {
     pub chapter: ChapterAtHomeData,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct ChapterAtHomeData {
     pub hash: String,
     pub data: Vec<String>,
@@ -139,6 +142,7 @@ pub struct ChapterAtHomeData {
 
 #[derive(Debug, Serialize, Clone)]
 pub struct Manga {
+    pub source: String,
     pub mangadex_id: String,
     pub title: String,
     pub alt_titles: Vec<String>,
@@ -161,6 +165,7 @@ pub struct Tag {
 
 #[derive(Debug, Serialize, Clone)]
 pub struct Chapter {
+    pub source: String,
     pub mangadex_id: String,
     pub manga_mangadex_id: String,
     pub chapter_number: Option<String>,
@@ -173,7 +178,7 @@ pub struct Chapter {
     pub published_at: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct ChapterPages {
     pub chapter_id: String,
     pub base_url: String,
@@ -181,7 +186,7 @@ pub struct ChapterPages {
     pub pages: Vec<PageInfo>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct PageInfo {
     pub page_number: u32,
     pub filename: String,
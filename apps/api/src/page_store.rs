@@ -0,0 +1,517 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use anyhow::{Context, Result};
+
+use crate::config::PageStoreConfig;
+use crate::mangadex::types::ChapterPages;
+
+/// Which rendition of a page a file holds. Matches the at-home URL segment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PageQuality {
+    Data,
+    DataSaver,
+}
+
+impl PageQuality {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PageQuality::Data => "data",
+            PageQuality::DataSaver => "data-saver",
+        }
+    }
+
+    pub fn from_segment(segment: &str) -> Option<Self> {
+        match segment {
+            "data" => Some(PageQuality::Data),
+            "data-saver" => Some(PageQuality::DataSaver),
+            _ => None,
+        }
+    }
+}
+
+/// Identifies a stored page by chapter hash and filename.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageKey {
+    pub hash: String,
+    pub quality: PageQuality,
+    pub filename: String,
+}
+
+impl PageKey {
+    pub fn new(hash: &str, quality: PageQuality, filename: &str) -> Option<Self> {
+        if !is_safe_segment(hash) || !is_safe_segment(filename) {
+            return None;
+        }
+
+        Some(Self {
+            hash: hash.to_string(),
+            quality,
+            filename: filename.to_string(),
+        })
+    }
+
+    /// Parses an at-home page URL (or just its path) ending in
+    /// `/data/{hash}/{filename}` or `/data-saver/{hash}/{filename}`.
+    ///
+    /// Nodes may prefix the path with a token, so only the last three segments
+    /// are considered.
+    pub fn from_url(url: &str) -> Option<Self> {
+        let mut segments = url.trim_end_matches('/').rsplit('/');
+        let filename = segments.next()?;
+        let hash = segments.next()?;
+        let quality = PageQuality::from_segment(segments.next()?)?;
+
+        Self::new(hash, quality, filename)
+    }
+
+    fn relative_path(&self) -> PathBuf {
+        Path::new(&self.hash)
+            .join(self.quality.as_str())
+            .join(&self.filename)
+    }
+}
+
+/// Rejects anything that could escape the store directory.
+fn is_safe_segment(segment: &str) -> bool {
+    !segment.is_empty()
+        && segment != "."
+        && segment != ".."
+        && segment
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'))
+}
+
+struct IndexEntry {
+    size: u64,
+    last_used: u64,
+}
+
+/// In-memory LRU bookkeeping for the files under `pages/` and `manifests/`.
+#[derive(Default)]
+struct LruIndex {
+    entries: HashMap<PathBuf, IndexEntry>,
+    order: BTreeMap<u64, PathBuf>,
+    total_bytes: u64,
+    tick: u64,
+}
+
+impl LruIndex {
+    fn touch(&mut self, path: &Path) -> bool {
+        let Some(entry) = self.entries.get_mut(path) else {
+            return false;
+        };
+
+        self.order.remove(&entry.last_used);
+        self.tick += 1;
+        entry.last_used = self.tick;
+        self.order.insert(self.tick, path.to_path_buf());
+        true
+    }
+
+    fn insert(&mut self, path: PathBuf, size: u64) {
+        self.remove(&path);
+
+        self.tick += 1;
+        self.order.insert(self.tick, path.clone());
+        self.entries.insert(
+            path,
+            IndexEntry {
+                size,
+                last_used: self.tick,
+            },
+        );
+        self.total_bytes += size;
+    }
+
+    fn remove(&mut self, path: &Path) {
+        if let Some(entry) = self.entries.remove(path) {
+            self.order.remove(&entry.last_used);
+            self.total_bytes -= entry.size;
+        }
+    }
+
+    /// Pops least recently used paths until the index fits in `max_bytes`.
+    fn evict(&mut self, max_bytes: u64) -> Vec<PathBuf> {
+        let mut evicted = Vec::new();
+
+        while self.total_bytes > max_bytes {
+            let Some((_, path)) = self.order.pop_first() else {
+                break;
+            };
+            if let Some(entry) = self.entries.remove(&path) {
+                self.total_bytes -= entry.size;
+            }
+            evicted.push(path);
+        }
+
+        evicted
+    }
+}
+
+/// Size-bounded on-disk store for chapter page images.
+///
+/// Pages live under `{dir}/pages/{hash}/{quality}/{filename}`; the chapter
+/// manifest returned by the source is kept under `{dir}/manifests` so a fully
+/// stored chapter can be served without asking the at-home server again.
+/// Both count towards the size limit and share one LRU order.
+pub struct PageStore {
+    pages_dir: PathBuf,
+    manifests_dir: PathBuf,
+    max_bytes: u64,
+    index: Mutex<LruIndex>,
+}
+
+impl PageStore {
+    pub fn open(config: &PageStoreConfig) -> Result<Self> {
+        let root = PathBuf::from(&config.dir);
+        let pages_dir = root.join("pages");
+        let manifests_dir = root.join("manifests");
+
+        std::fs::create_dir_all(&pages_dir)
+            .with_context(|| format!("Failed to create {}", pages_dir.display()))?;
+        std::fs::create_dir_all(&manifests_dir)
+            .with_context(|| format!("Failed to create {}", manifests_dir.display()))?;
+
+        // Rebuild the LRU order from what is already on disk, oldest first
+        let mut files = Vec::new();
+        scan_files(&pages_dir, &mut files)?;
+        scan_files(&manifests_dir, &mut files)?;
+        files.sort_by_key(|(_, _, modified)| *modified);
+
+        let mut index = LruIndex::default();
+        for (path, size, _) in files {
+            index.insert(path, size);
+        }
+
+        let store = Self {
+            pages_dir,
+            manifests_dir,
+            max_bytes: config.max_size_mb * 1024 * 1024,
+            index: Mutex::new(index),
+        };
+        store.enforce_limit();
+
+        Ok(store)
+    }
+
+    pub fn total_bytes(&self) -> u64 {
+        self.index.lock().unwrap().total_bytes
+    }
+
+    pub async fn get(&self, key: &PageKey) -> Option<Vec<u8>> {
+        let path = self.pages_dir.join(key.relative_path());
+
+        if !self.index.lock().unwrap().touch(&path) {
+            return None;
+        }
+
+        match tokio::fs::read(&path).await {
+            Ok(bytes) => Some(bytes),
+            Err(e) => {
+                tracing::warn!("Stored page {} is unreadable: {}", path.display(), e);
+                self.index.lock().unwrap().remove(&path);
+                None
+            }
+        }
+    }
+
+    pub fn contains(&self, key: &PageKey) -> bool {
+        let path = self.pages_dir.join(key.relative_path());
+        self.index.lock().unwrap().entries.contains_key(&path)
+    }
+
+    pub async fn put(&self, key: &PageKey, bytes: &[u8]) -> Result<()> {
+        let size = bytes.len() as u64;
+        if size > self.max_bytes {
+            return Ok(());
+        }
+
+        let path = self.pages_dir.join(key.relative_path());
+        self.write(path, bytes).await
+    }
+
+    /// Returns the stored manifest for a chapter if every page of one quality
+    /// is still on disk.
+    pub async fn stored_chapter(&self, source: &str, chapter_id: &str) -> Option<ChapterPages> {
+        let path = self.manifest_path(source, chapter_id)?;
+        if !self.index.lock().unwrap().touch(&path) {
+            return None;
+        }
+        let raw = tokio::fs::read(&path).await.ok()?;
+        let manifest: ChapterPages = serde_json::from_slice(&raw).ok()?;
+
+        let stored = |url: &str, quality: PageQuality| {
+            PageKey::from_url(url).is_some_and(|key| key.quality == quality && self.contains(&key))
+        };
+
+        let complete = manifest
+            .pages
+            .iter()
+            .all(|p| stored(&p.url, PageQuality::Data))
+            || manifest
+                .pages
+                .iter()
+                .all(|p| stored(&p.url_data_saver, PageQuality::DataSaver));
+
+        (complete && !manifest.pages.is_empty()).then_some(manifest)
+    }
+
+    pub async fn save_manifest(
+        &self,
+        source: &str,
+        chapter_id: &str,
+        pages: &ChapterPages,
+    ) -> Result<()> {
+        let Some(path) = self.manifest_path(source, chapter_id) else {
+            return Ok(());
+        };
+
+        self.write(path, &serde_json::to_vec(pages)?).await
+    }
+
+    /// Stores a file and evicts whatever no longer fits.
+    ///
+    /// The file is written under a temporary name first. Moving it into place,
+    /// indexing it and evicting all happen under the index lock, so a file on
+    /// disk is indexed exactly when it is there and eviction never deletes a
+    /// copy that was just written.
+    async fn write(&self, path: PathBuf, bytes: &[u8]) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let tmp_path = write_temp(&path, bytes).await?;
+
+        let mut index = self.index.lock().unwrap();
+        if let Err(e) = std::fs::rename(&tmp_path, &path) {
+            let _ = std::fs::remove_file(&tmp_path);
+            return Err(e.into());
+        }
+        index.insert(path, bytes.len() as u64);
+        self.evict(&mut index);
+
+        Ok(())
+    }
+
+    fn manifest_path(&self, source: &str, chapter_id: &str) -> Option<PathBuf> {
+        if !is_safe_segment(source) || !is_safe_segment(chapter_id) {
+            return None;
+        }
+
+        Some(
+            self.manifests_dir
+                .join(source)
+                .join(format!("{}.json", chapter_id)),
+        )
+    }
+
+    fn enforce_limit(&self) {
+        self.evict(&mut self.index.lock().unwrap());
+    }
+
+    /// Drops least recently used files until the store fits. Takes the locked
+    /// index so files are deleted before anyone can store them again.
+    fn evict(&self, index: &mut LruIndex) {
+        for path in index.evict(self.max_bytes) {
+            if let Err(e) = std::fs::remove_file(&path) {
+                tracing::warn!("Failed to evict stored file {}: {}", path.display(), e);
+            }
+        }
+    }
+}
+
+/// Writes `bytes` next to `path` under a temporary name, so readers never see
+/// a partial file once it is renamed into place. The name is unique per
+/// write, so concurrent writers of the same path can't interleave.
+async fn write_temp(path: &Path, bytes: &[u8]) -> Result<PathBuf> {
+    let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(format!(".{}.part", uuid::Uuid::new_v4().simple()));
+    let tmp_path = path.with_file_name(tmp_name);
+
+    if let Err(e) = tokio::fs::write(&tmp_path, bytes).await {
+        let _ = tokio::fs::remove_file(&tmp_path).await;
+        return Err(e.into());
+    }
+
+    Ok(tmp_path)
+}
+
+fn scan_files(dir: &Path, files: &mut Vec<(PathBuf, u64, SystemTime)>) -> Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        let path = entry.path();
+
+        if metadata.is_dir() {
+            scan_files(&path, files)?;
+        } else if path.extension().is_some_and(|ext| ext == "part") {
+            // Left over from an interrupted write
+            let _ = std::fs::remove_file(&path);
+        } else {
+            let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            files.push((path, metadata.len(), modified));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn open_store(dir: &Path) -> PageStore {
+        PageStore::open(&PageStoreConfig {
+            dir: dir.to_string_lossy().into_owned(),
+            max_size_mb: 1,
+        })
+        .unwrap()
+    }
+
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("page-store-{}", uuid::Uuid::new_v4()))
+    }
+
+    /// A bit under half the 1 MiB test store.
+    const PAGE: &[u8] = &[7; 400 * 1024];
+
+    fn page(filename: &str) -> PageKey {
+        PageKey::new("abc123", PageQuality::Data, filename).unwrap()
+    }
+
+    fn page_path(dir: &Path, key: &PageKey) -> PathBuf {
+        dir.join("pages").join(key.relative_path())
+    }
+
+    #[tokio::test]
+    async fn evicts_the_least_recently_used_page_once_full() {
+        let dir = temp_dir();
+        let store = open_store(&dir);
+        let (first, second, third) = (page("1.png"), page("2.png"), page("3.png"));
+
+        store.put(&first, PAGE).await.unwrap();
+        store.put(&second, PAGE).await.unwrap();
+        assert_eq!(store.total_bytes(), 2 * PAGE.len() as u64);
+
+        store.put(&third, PAGE).await.unwrap();
+        assert!(!store.contains(&first));
+        assert!(!page_path(&dir, &first).exists());
+        assert!(store.contains(&second) && store.contains(&third));
+        assert_eq!(store.total_bytes(), 2 * PAGE.len() as u64);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn reading_a_page_protects_it_from_eviction() {
+        let dir = temp_dir();
+        let store = open_store(&dir);
+        let (first, second, third) = (page("1.png"), page("2.png"), page("3.png"));
+
+        store.put(&first, PAGE).await.unwrap();
+        store.put(&second, PAGE).await.unwrap();
+        assert!(store.get(&first).await.is_some());
+
+        store.put(&third, PAGE).await.unwrap();
+        assert!(store.contains(&first));
+        assert!(!store.contains(&second));
+        assert!(!page_path(&dir, &second).exists());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn manifests_are_counted_and_evicted() {
+        let dir = temp_dir();
+        let store = open_store(&dir);
+        let first = page("1.png");
+        let manifest = ChapterPages {
+            chapter_id: "chapter-1".to_string(),
+            base_url: "https://node.example".to_string(),
+            hash: "abc123".to_string(),
+            pages: vec![crate::mangadex::types::PageInfo {
+                page_number: 1,
+                filename: "1.png".to_string(),
+                url: "https://node.example/data/abc123/1.png".to_string(),
+                url_data_saver: "https://node.example/data-saver/abc123/1.png".to_string(),
+            }],
+        };
+
+        store
+            .save_manifest("mangadex", "chapter-1", &manifest)
+            .await
+            .unwrap();
+        store.put(&first, PAGE).await.unwrap();
+        assert!(store.total_bytes() > PAGE.len() as u64);
+        assert!(store
+            .stored_chapter("mangadex", "chapter-1")
+            .await
+            .is_some());
+
+        // Reopening indexes the manifest again
+        let total = store.total_bytes();
+        let store = open_store(&dir);
+        assert_eq!(store.total_bytes(), total);
+
+        for filename in ["2.png", "3.png", "4.png"] {
+            store.put(&page(filename), PAGE).await.unwrap();
+        }
+        assert!(store
+            .stored_chapter("mangadex", "chapter-1")
+            .await
+            .is_none());
+        assert!(!dir.join("manifests/mangadex/chapter-1.json").exists());
+        assert_eq!(store.total_bytes(), 2 * PAGE.len() as u64);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn concurrent_puts_leave_one_complete_page() {
+        let dir = temp_dir();
+        let store = std::sync::Arc::new(open_store(&dir));
+        let key = PageKey::new("abc123", PageQuality::Data, "1.png").unwrap();
+
+        let writes: Vec<_> = (0..8u8)
+            .map(|n| {
+                let store = store.clone();
+                let key = key.clone();
+                tokio::spawn(async move { store.put(&key, &[n; 4096]).await })
+            })
+            .collect();
+        for write in writes {
+            write.await.unwrap().unwrap();
+        }
+
+        let page = store.get(&key).await.unwrap();
+        assert_eq!(page.len(), 4096);
+        assert!(page.iter().all(|byte| *byte == page[0]));
+
+        let mut files = Vec::new();
+        scan_files(&dir.join("pages"), &mut files).unwrap();
+        assert_eq!(files.len(), 1);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn open_removes_leftover_temp_files() {
+        let dir = temp_dir();
+        let key = PageKey::new("abc123", PageQuality::Data, "1.png").unwrap();
+        open_store(&dir).put(&key, b"page").await.unwrap();
+
+        let leftover = dir
+            .join("pages")
+            .join(key.relative_path())
+            .with_file_name("1.png.0f1e.part");
+        std::fs::write(&leftover, b"partial").unwrap();
+
+        let store = open_store(&dir);
+        assert!(!leftover.exists());
+        assert_eq!(store.total_bytes(), 4);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,32 @@
+use std::sync::Arc;
+
+use thiserror::Error;
+
+/// Why a [`super::MangaSource`] call failed, whatever the backend.
+#[derive(Error, Debug)]
+pub enum SourceError {
+    #[error("Not found")]
+    NotFound,
+
+    #[error("Rate limit exceeded")]
+    RateLimited,
+
+    /// The source answered, but with an error.
+    #[error("Source error: {0}")]
+    Upstream(String),
+
+    #[error("Invalid response format")]
+    InvalidResponse,
+
+    #[error("Network error: {0}")]
+    Network(#[source] Arc<reqwest::Error>),
+
+    #[error("Internal error")]
+    Internal(#[from] anyhow::Error),
+}
+
+impl From<reqwest::Error> for SourceError {
+    fn from(error: reqwest::Error) -> Self {
+        SourceError::Network(Arc::new(error))
+    }
+}
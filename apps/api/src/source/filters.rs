@@ -0,0 +1,256 @@
+use serde::de::{value::StrDeserializer, DeserializeOwned, Deserializer};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum TagMode {
+    And,
+    Or,
+}
+
+impl TagMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TagMode::And => "AND",
+            TagMode::Or => "OR",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PublicationStatus {
+    Ongoing,
+    Completed,
+    Hiatus,
+    Cancelled,
+}
+
+impl PublicationStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PublicationStatus::Ongoing => "ongoing",
+            PublicationStatus::Completed => "completed",
+            PublicationStatus::Hiatus => "hiatus",
+            PublicationStatus::Cancelled => "cancelled",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Demographic {
+    Shounen,
+    Shoujo,
+    Josei,
+    Seinen,
+    None,
+}
+
+impl Demographic {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Demographic::Shounen => "shounen",
+            Demographic::Shoujo => "shoujo",
+            Demographic::Josei => "josei",
+            Demographic::Seinen => "seinen",
+            Demographic::None => "none",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentRating {
+    Safe,
+    Suggestive,
+    Erotica,
+    Pornographic,
+}
+
+impl ContentRating {
+    pub const ALL: [ContentRating; 4] = [
+        ContentRating::Safe,
+        ContentRating::Suggestive,
+        ContentRating::Erotica,
+        ContentRating::Pornographic,
+    ];
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|rating| rating.as_str() == value)
+    }
+
+    /// Whether a rating as reported by a source is in `allowed`. Ratings the
+    /// source left blank or that we don't recognise are treated as not
+    /// allowed, so an unrated title is never shown to someone who opted out.
+    pub fn is_allowed(rating: &str, allowed: &[ContentRating]) -> bool {
+        Self::parse(rating).is_some_and(|rating| allowed.contains(&rating))
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContentRating::Safe => "safe",
+            ContentRating::Suggestive => "suggestive",
+            ContentRating::Erotica => "erotica",
+            ContentRating::Pornographic => "pornographic",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortField {
+    Relevance,
+    Title,
+    Year,
+    Rating,
+    FollowedCount,
+    LatestUploadedChapter,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortDirection {
+    Asc,
+    #[default]
+    Desc,
+}
+
+impl SortDirection {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SortDirection::Asc => "asc",
+            SortDirection::Desc => "desc",
+        }
+    }
+}
+
+/// Filters accepted by [`crate::source::MangaSource::search`].
+///
+/// List-valued fields are read from comma separated query parameters, e.g.
+/// `status=ongoing,hiatus`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SearchFilters {
+    #[serde(default, alias = "q")]
+    pub title: Option<String>,
+    #[serde(default, deserialize_with = "comma_separated")]
+    pub included_tags: Vec<String>,
+    #[serde(default)]
+    pub included_tags_mode: Option<TagMode>,
+    #[serde(default, deserialize_with = "comma_separated")]
+    pub excluded_tags: Vec<String>,
+    #[serde(default)]
+    pub excluded_tags_mode: Option<TagMode>,
+    #[serde(default, deserialize_with = "comma_separated")]
+    pub status: Vec<PublicationStatus>,
+    #[serde(default, deserialize_with = "comma_separated")]
+    pub content_rating: Vec<ContentRating>,
+    #[serde(default, deserialize_with = "comma_separated")]
+    pub demographic: Vec<Demographic>,
+    #[serde(default, deserialize_with = "comma_separated")]
+    pub authors: Vec<String>,
+    #[serde(default, deserialize_with = "comma_separated")]
+    pub artists: Vec<String>,
+    #[serde(default, deserialize_with = "comma_separated")]
+    pub original_language: Vec<String>,
+    #[serde(default, deserialize_with = "comma_separated")]
+    pub available_translated_language: Vec<String>,
+    #[serde(default)]
+    pub year: Option<u32>,
+    #[serde(default)]
+    pub order_by: Option<SortField>,
+    #[serde(default)]
+    pub order_direction: SortDirection,
+}
+
+impl SearchFilters {
+    /// Narrows `content_rating` to `allowed`, or fills it in when the caller
+    /// asked for no particular rating. Sources fall back to their own default
+    /// ratings when none are given, so callers should not search if this
+    /// leaves the list empty.
+    pub fn restrict_content_rating(&mut self, allowed: &[ContentRating]) {
+        if self.content_rating.is_empty() {
+            self.content_rating = allowed.to_vec();
+        } else {
+            self.content_rating
+                .retain(|rating| allowed.contains(rating));
+        }
+    }
+}
+
+pub(crate) fn comma_separated<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned,
+{
+    let raw = Option::<String>::deserialize(deserializer)?.unwrap_or_default();
+
+    raw.split(',')
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(|value| T::deserialize(StrDeserializer::<D::Error>::new(value)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::extract::Query;
+    use axum::http::Uri;
+
+    fn filters(query: &str) -> SearchFilters {
+        let uri: Uri = format!("/manga/search?{query}").parse().unwrap();
+        Query::<SearchFilters>::try_from_uri(&uri).unwrap().0
+    }
+
+    #[test]
+    fn comma_separated_splits_and_trims() {
+        let filters = filters("status=ongoing,%20hiatus&authors=a,,b&demographic=seinen");
+        assert_eq!(
+            filters.status,
+            vec![PublicationStatus::Ongoing, PublicationStatus::Hiatus]
+        );
+        assert_eq!(filters.authors, vec!["a", "b"]);
+        assert_eq!(filters.demographic, vec![Demographic::Seinen]);
+    }
+
+    #[test]
+    fn comma_separated_defaults_to_empty() {
+        let filters = filters("title=x&status=");
+        assert!(filters.status.is_empty());
+        assert!(filters.content_rating.is_empty());
+    }
+
+    #[test]
+    fn comma_separated_rejects_unknown_values() {
+        let uri: Uri = "/manga/search?status=ongoing,paused".parse().unwrap();
+        assert!(Query::<SearchFilters>::try_from_uri(&uri).is_err());
+    }
+
+    #[test]
+    fn is_allowed_fails_closed() {
+        let allowed = [ContentRating::Safe, ContentRating::Suggestive];
+        assert!(ContentRating::is_allowed("safe", &allowed));
+        assert!(!ContentRating::is_allowed("erotica", &allowed));
+        assert!(!ContentRating::is_allowed("", &allowed));
+        assert!(!ContentRating::is_allowed("Safe", &allowed));
+        assert!(!ContentRating::is_allowed("unknown", &ContentRating::ALL));
+    }
+
+    #[test]
+    fn restrict_content_rating_narrows_or_fills() {
+        let allowed = [ContentRating::Safe, ContentRating::Suggestive];
+
+        let mut filters = filters("content_rating=safe,erotica");
+        filters.restrict_content_rating(&allowed);
+        assert_eq!(filters.content_rating, vec![ContentRating::Safe]);
+
+        let mut filters = SearchFilters::default();
+        filters.restrict_content_rating(&allowed);
+        assert_eq!(filters.content_rating, allowed.to_vec());
+    }
+}
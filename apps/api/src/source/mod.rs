@@ -0,0 +1,97 @@
+pub mod error;
+pub mod filters;
+pub mod registry;
+
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+
+use crate::mangadex::types::{Chapter, ChapterPages, Manga};
+
+pub use error::SourceError;
+pub use filters::{ContentRating, SearchFilters};
+pub use registry::SourceRegistry;
+
+/// A page of manga returned by a listing call (search, popular, latest).
+#[derive(Debug)]
+pub struct MangaPage {
+    pub data: Vec<Manga>,
+    pub total: u32,
+    pub limit: u32,
+    pub offset: u32,
+}
+
+impl MangaPage {
+    pub fn empty(limit: u32, offset: u32) -> Self {
+        Self {
+            data: Vec::new(),
+            total: 0,
+            limit,
+            offset,
+        }
+    }
+}
+
+/// A backend that manga, chapters and pages can be read from.
+///
+/// IDs passed to and returned from a source are the source's own IDs; the
+/// registry takes care of qualifying them with [`MangaSource::id`].
+#[async_trait]
+pub trait MangaSource: Send + Sync {
+    /// Stable identifier stored alongside every cached and user-owned row.
+    fn id(&self) -> &'static str;
+
+    async fn search(
+        &self,
+        filters: &SearchFilters,
+        limit: u32,
+        offset: u32,
+    ) -> Result<MangaPage, SourceError>;
+
+    /// Most followed manga with one of the given content ratings.
+    async fn popular(
+        &self,
+        content_rating: &[ContentRating],
+        limit: u32,
+        offset: u32,
+    ) -> Result<MangaPage, SourceError>;
+
+    /// Most recently updated manga with one of the given content ratings.
+    async fn latest(
+        &self,
+        content_rating: &[ContentRating],
+        limit: u32,
+        offset: u32,
+    ) -> Result<MangaPage, SourceError>;
+
+    async fn get_manga(&self, manga_id: &str) -> Result<Manga, SourceError>;
+
+    /// Looks up several manga at once; IDs that don't exist are left out.
+    /// Callers pass at most 100 IDs. Sources without a batch endpoint get
+    /// one lookup per ID.
+    async fn get_mangas(&self, manga_ids: &[String]) -> Result<Vec<Manga>, SourceError> {
+        let mut mangas = Vec::with_capacity(manga_ids.len());
+        for manga_id in manga_ids {
+            match self.get_manga(manga_id).await {
+                Ok(manga) => mangas.push(manga),
+                Err(SourceError::NotFound) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(mangas)
+    }
+
+    /// IDs of the manga on external trackers, keyed by MangaDex's link codes
+    /// (`al` for AniList, `mal` for MyAnimeList). Sources that don't know
+    /// return none.
+    async fn tracker_links(&self, _manga_id: &str) -> Result<HashMap<String, String>, SourceError> {
+        Ok(HashMap::new())
+    }
+
+    /// Every chapter of a manga in the given translated language.
+    async fn get_chapters(&self, manga_id: &str, lang: &str) -> Result<Vec<Chapter>, SourceError>;
+
+    async fn get_chapter(&self, chapter_id: &str) -> Result<Chapter, SourceError>;
+
+    async fn get_chapter_pages(&self, chapter_id: &str) -> Result<ChapterPages, SourceError>;
+}
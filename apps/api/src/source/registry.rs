@@ -0,0 +1,70 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::source::{MangaSource, SourceError};
+
+/// Separator between the source and the source-local ID, e.g. `mangadex:<uuid>`.
+pub const ID_SEPARATOR: char = ':';
+
+pub struct SourceRegistry {
+    sources: HashMap<&'static str, Arc<dyn MangaSource>>,
+    default_source: &'static str,
+}
+
+impl SourceRegistry {
+    /// Creates a registry whose default source is `default`.
+    pub fn new(default: Arc<dyn MangaSource>) -> Self {
+        let default_source = default.id();
+        let mut sources: HashMap<&'static str, Arc<dyn MangaSource>> = HashMap::new();
+        sources.insert(default_source, default);
+
+        Self {
+            sources,
+            default_source,
+        }
+    }
+
+    pub fn register(&mut self, source: Arc<dyn MangaSource>) {
+        self.sources.insert(source.id(), source);
+    }
+
+    pub fn get(&self, id: &str) -> Option<Arc<dyn MangaSource>> {
+        self.sources.get(id).cloned()
+    }
+
+    pub fn default_source(&self) -> Arc<dyn MangaSource> {
+        self.sources[self.default_source].clone()
+    }
+
+    /// Looks up a source by name, falling back to the default when none is given.
+    pub fn get_or_default(&self, id: Option<&str>) -> Result<Arc<dyn MangaSource>, SourceError> {
+        match id {
+            Some(id) => self.get(id).ok_or(SourceError::NotFound),
+            None => Ok(self.default_source()),
+        }
+    }
+
+    /// Splits a source-qualified ID (`source:id`) into its source and local ID.
+    ///
+    /// Unqualified IDs, and IDs whose prefix is not a registered source, are
+    /// treated as belonging to the default source.
+    pub fn resolve(&self, qualified_id: &str) -> (Arc<dyn MangaSource>, String) {
+        if let Some((prefix, local_id)) = qualified_id.split_once(ID_SEPARATOR) {
+            if let Some(source) = self.get(prefix) {
+                return (source, local_id.to_string());
+            }
+        }
+
+        (self.default_source(), qualified_id.to_string())
+    }
+
+    /// Formats a source-qualified ID. IDs from the default source stay bare so
+    /// existing links keep working.
+    pub fn qualify(&self, source: &str, local_id: &str) -> String {
+        if source == self.default_source {
+            local_id.to_string()
+        } else {
+            format!("{}{}{}", source, ID_SEPARATOR, local_id)
+        }
+    }
+}
@@ -1,7 +1,13 @@
 use std::sync::Arc;
 
-use crate::config::AuthConfig;
+use crate::config::{AuthConfig, ExportConfig, ProxyConfig, TrackersConfig};
+use crate::mailer::Mailer;
+use crate::mangadex::cache::HotCaches;
+use crate::mangadex::report::HomeReporter;
 use crate::mangadex::MangaDexClient;
+use crate::page_store::PageStore;
+use crate::source::SourceRegistry;
+use crate::trackers::TrackerRegistry;
 
 #[derive(Clone)]
 pub struct AppState {
@@ -9,4 +15,17 @@ pub struct AppState {
     pub auth_config: AuthConfig,
     pub mangadex_client: Arc<MangaDexClient>,
     pub mangadex_config: crate::config::MangaDexConfig,
+    pub hot_cache: Arc<HotCaches>,
+    pub sources: Arc<SourceRegistry>,
+    pub page_store: Arc<PageStore>,
+    pub proxy_config: ProxyConfig,
+    /// Client for proxied image requests; only follows redirects to allowed
+    /// image hosts.
+    pub proxy_http: reqwest::Client,
+    pub home_reporter: Arc<HomeReporter>,
+    pub trackers: Arc<TrackerRegistry>,
+    pub tracker_config: TrackersConfig,
+    pub export_config: ExportConfig,
+    /// `None` when email delivery isn't configured.
+    pub mailer: Option<Arc<dyn Mailer>>,
 }
@@ -0,0 +1,143 @@
+use async_trait::async_trait;
+use serde_json::json;
+
+use super::{ListUpdate, Tracker, TrackerError, TrackerToken};
+use crate::config::TrackerClientConfig;
+use crate::users::status::ReadingStatus;
+
+/// Key of the tracker in MangaDex's `links` attribute.
+pub const LINK_KEY: &str = "al";
+
+pub const TRACKER_ID: &str = "anilist";
+
+const SAVE_ENTRY: &str = r#"
+mutation ($mediaId: Int, $progress: Int, $status: MediaListStatus, $scoreRaw: Int, $repeat: Int) {
+    SaveMediaListEntry(mediaId: $mediaId, progress: $progress, status: $status, scoreRaw: $scoreRaw, repeat: $repeat) {
+        id
+    }
+}
+"#;
+
+/// AniList, through its GraphQL API.
+pub struct AniList {
+    config: TrackerClientConfig,
+    http: reqwest::Client,
+}
+
+impl AniList {
+    pub fn new(config: TrackerClientConfig, http: reqwest::Client) -> Self {
+        Self { config, http }
+    }
+}
+
+fn list_status(status: ReadingStatus) -> &'static str {
+    match status {
+        ReadingStatus::Reading => "CURRENT",
+        ReadingStatus::Completed => "COMPLETED",
+        ReadingStatus::OnHold => "PAUSED",
+        ReadingStatus::Dropped => "DROPPED",
+        ReadingStatus::PlanToRead => "PLANNING",
+        ReadingStatus::Rereading => "REPEATING",
+    }
+}
+
+#[async_trait]
+impl Tracker for AniList {
+    fn id(&self) -> &'static str {
+        TRACKER_ID
+    }
+
+    fn link_key(&self) -> &'static str {
+        LINK_KEY
+    }
+
+    fn authorize_url(&self, state: &str, _code_verifier: &str) -> String {
+        format!(
+            "{}?client_id={}&redirect_uri={}&response_type=code&state={}",
+            self.config.auth_url,
+            urlencoding::encode(&self.config.client_id),
+            urlencoding::encode(&self.config.redirect_uri),
+            urlencoding::encode(state)
+        )
+    }
+
+    async fn exchange_code(
+        &self,
+        code: &str,
+        _code_verifier: &str,
+    ) -> Result<TrackerToken, TrackerError> {
+        let response = self
+            .http
+            .post(&self.config.token_url)
+            .json(&json!({
+                "grant_type": "authorization_code",
+                "client_id": self.config.client_id,
+                "client_secret": self.config.client_secret,
+                "redirect_uri": self.config.redirect_uri,
+                "code": code,
+            }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            return Err(TrackerError::from_status(
+                status,
+                response.text().await.unwrap_or_default(),
+            ));
+        }
+
+        Ok(response.json().await?)
+    }
+
+    async fn refresh_token(&self, _refresh_token: &str) -> Result<TrackerToken, TrackerError> {
+        // AniList tokens last a year and can't be refreshed; the user links again
+        Err(TrackerError::Unauthorized)
+    }
+
+    async fn update_entry(
+        &self,
+        access_token: &str,
+        remote_id: &str,
+        update: &ListUpdate,
+    ) -> Result<(), TrackerError> {
+        let media_id: i64 = remote_id
+            .parse()
+            .map_err(|_| TrackerError::ApiError(format!("Invalid AniList ID {}", remote_id)))?;
+
+        let response = self
+            .http
+            .post(&self.config.api_url)
+            .bearer_auth(access_token)
+            .json(&json!({
+                "query": SAVE_ENTRY,
+                "variables": {
+                    "mediaId": media_id,
+                    "progress": update.chapters_read,
+                    "status": list_status(update.status),
+                    // scoreRaw is out of 100 whatever the user's scoring format
+                    "scoreRaw": update.score.map(|score| score * 10),
+                    "repeat": update.reread_count,
+                },
+            }))
+            .send()
+            .await?;
+
+        let status = response.status();
+        let body: serde_json::Value = if status.is_success() {
+            response.json().await?
+        } else {
+            return Err(TrackerError::from_status(
+                status,
+                response.text().await.unwrap_or_default(),
+            ));
+        };
+
+        // GraphQL reports most failures in the body with a 200
+        if let Some(errors) = body.get("errors").filter(|e| !e.is_null()) {
+            return Err(TrackerError::ApiError(errors.to_string()));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,191 @@
+//! Stand-in for the AniList and MyAnimeList APIs, served by the
+//! `mock_tracker` binary and used by the tracker tests.
+//!
+//! Authorizing redirects straight back with a code. Every list update is
+//! recorded and can be read from `GET /_mock/requests`.
+//! `POST /_mock/fail?count=3&status=500` makes the next three list updates
+//! fail, to exercise the retry queue; status 401 exercises token refresh.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Redirect, Response},
+    routing::{get, patch, post},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+#[derive(Clone, Serialize)]
+struct RecordedRequest {
+    tracker: &'static str,
+    authorization: Option<String>,
+    media_id: Option<String>,
+    body: String,
+}
+
+#[derive(Default)]
+struct MockState {
+    requests: Mutex<Vec<RecordedRequest>>,
+    /// Status to answer the next `count` list updates with.
+    failures: Mutex<Option<(u32, StatusCode)>>,
+    tokens_issued: AtomicU64,
+}
+
+type Shared = Arc<MockState>;
+
+impl MockState {
+    /// Takes one injected failure, if any are left.
+    fn next_failure(&self) -> Option<StatusCode> {
+        let mut failures = self.failures.lock().unwrap();
+        let (count, status) = (*failures)?;
+        *failures = (count > 1).then_some((count - 1, status));
+        Some(status)
+    }
+
+    fn record(&self, request: RecordedRequest) -> Option<Response> {
+        if let Some(status) = self.next_failure() {
+            return Some((status, "injected failure").into_response());
+        }
+        self.requests.lock().unwrap().push(request);
+        None
+    }
+}
+
+#[derive(Deserialize)]
+struct AuthorizeQuery {
+    redirect_uri: String,
+    #[serde(default)]
+    state: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct FailQuery {
+    #[serde(default = "default_fail_count")]
+    count: u32,
+    #[serde(default = "default_fail_status")]
+    status: u16,
+}
+
+fn default_fail_count() -> u32 {
+    1
+}
+
+fn default_fail_status() -> u16 {
+    500
+}
+
+async fn authorize(Query(query): Query<AuthorizeQuery>) -> Redirect {
+    let separator = if query.redirect_uri.contains('?') {
+        '&'
+    } else {
+        '?'
+    };
+    Redirect::to(&format!(
+        "{}{}code=mock-code&state={}",
+        query.redirect_uri,
+        separator,
+        urlencoding::encode(query.state.as_deref().unwrap_or_default())
+    ))
+}
+
+async fn token(State(state): State<Shared>) -> Json<serde_json::Value> {
+    let n = state.tokens_issued.fetch_add(1, Ordering::Relaxed) + 1;
+    Json(json!({
+        "token_type": "Bearer",
+        "access_token": format!("mock-access-{}", n),
+        "refresh_token": format!("mock-refresh-{}", n),
+        "expires_in": 3600,
+    }))
+}
+
+fn authorization(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get("authorization")
+        .and_then(|value| value.to_str().ok())
+        .map(String::from)
+}
+
+async fn anilist_graphql(
+    State(state): State<Shared>,
+    headers: HeaderMap,
+    body: String,
+) -> Response {
+    let media_id = serde_json::from_str::<serde_json::Value>(&body)
+        .ok()
+        .and_then(|body| body["variables"]["mediaId"].as_i64())
+        .map(|id| id.to_string());
+
+    let request = RecordedRequest {
+        tracker: "anilist",
+        authorization: authorization(&headers),
+        media_id,
+        body,
+    };
+    if let Some(response) = state.record(request) {
+        return response;
+    }
+
+    Json(json!({ "data": { "SaveMediaListEntry": { "id": 1 } } })).into_response()
+}
+
+async fn mal_list_status(
+    State(state): State<Shared>,
+    Path(manga_id): Path<String>,
+    headers: HeaderMap,
+    body: String,
+) -> Response {
+    let request = RecordedRequest {
+        tracker: "myanimelist",
+        authorization: authorization(&headers),
+        media_id: Some(manga_id),
+        body: body.clone(),
+    };
+    if let Some(response) = state.record(request) {
+        return response;
+    }
+
+    let fields: HashMap<String, String> = body
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+    Json(json!(fields)).into_response()
+}
+
+async fn list_requests(State(state): State<Shared>) -> Json<Vec<RecordedRequest>> {
+    Json(state.requests.lock().unwrap().clone())
+}
+
+async fn clear_requests(State(state): State<Shared>) -> StatusCode {
+    state.requests.lock().unwrap().clear();
+    StatusCode::NO_CONTENT
+}
+
+async fn inject_failures(
+    State(state): State<Shared>,
+    Query(query): Query<FailQuery>,
+) -> Result<StatusCode, StatusCode> {
+    let status = StatusCode::from_u16(query.status).map_err(|_| StatusCode::BAD_REQUEST)?;
+    *state.failures.lock().unwrap() = (query.count > 0).then_some((query.count, status));
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Routes of the mock, each serving its own recorded requests.
+pub fn router() -> Router {
+    Router::new()
+        .route("/oauth/authorize", get(authorize))
+        .route("/oauth/token", post(token))
+        .route("/graphql", post(anilist_graphql))
+        .route(
+            "/v2/manga/{manga_id}/my_list_status",
+            patch(mal_list_status),
+        )
+        .route("/_mock/requests", get(list_requests).delete(clear_requests))
+        .route("/_mock/fail", post(inject_failures))
+        .with_state(Arc::new(MockState::default()))
+}
@@ -0,0 +1,129 @@
+pub mod anilist;
+pub mod mock;
+pub mod myanimelist;
+pub mod sync;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use thiserror::Error;
+
+use crate::config::TrackersConfig;
+use crate::users::status::ReadingStatus;
+
+#[derive(Error, Debug)]
+pub enum TrackerError {
+    /// The access token was rejected; the user may need to link again.
+    #[error("Tracker rejected the access token")]
+    Unauthorized,
+
+    #[error("Tracker rate limit exceeded")]
+    RateLimited,
+
+    #[error("Tracker API error: {0}")]
+    ApiError(String),
+
+    #[error("Network error: {0}")]
+    NetworkError(#[from] reqwest::Error),
+}
+
+impl TrackerError {
+    /// Maps a non-success HTTP status to an error.
+    fn from_status(status: reqwest::StatusCode, body: String) -> Self {
+        match status {
+            reqwest::StatusCode::UNAUTHORIZED => TrackerError::Unauthorized,
+            reqwest::StatusCode::TOO_MANY_REQUESTS => TrackerError::RateLimited,
+            _ => TrackerError::ApiError(format!("{}: {}", status, body)),
+        }
+    }
+}
+
+/// Tokens returned by a tracker's OAuth token endpoint.
+#[derive(Debug, serde::Deserialize)]
+pub struct TrackerToken {
+    pub access_token: String,
+    #[serde(default)]
+    pub refresh_token: Option<String>,
+    /// Lifetime of the access token in seconds.
+    #[serde(default)]
+    pub expires_in: Option<i64>,
+}
+
+/// What gets written to a user's list entry on the tracker.
+#[derive(Debug, Clone)]
+pub struct ListUpdate {
+    pub chapters_read: u32,
+    pub status: ReadingStatus,
+    /// 1 to 10, if the user has rated the manga.
+    pub score: Option<i16>,
+    pub reread_count: i32,
+}
+
+/// An external list site that reading progress is pushed to.
+#[async_trait]
+pub trait Tracker: Send + Sync {
+    /// Stable identifier stored with linked accounts and queued syncs.
+    fn id(&self) -> &'static str;
+
+    /// Key of this tracker in a source's [`crate::source::MangaSource::tracker_links`].
+    fn link_key(&self) -> &'static str;
+
+    /// Page the user is sent to in order to grant access.
+    fn authorize_url(&self, state: &str, code_verifier: &str) -> String;
+
+    async fn exchange_code(
+        &self,
+        code: &str,
+        code_verifier: &str,
+    ) -> Result<TrackerToken, TrackerError>;
+
+    async fn refresh_token(&self, refresh_token: &str) -> Result<TrackerToken, TrackerError>;
+
+    /// Creates or updates the user's list entry for `remote_id`.
+    async fn update_entry(
+        &self,
+        access_token: &str,
+        remote_id: &str,
+        update: &ListUpdate,
+    ) -> Result<(), TrackerError>;
+}
+
+/// Trackers that have an OAuth client configured.
+#[derive(Default)]
+pub struct TrackerRegistry {
+    trackers: HashMap<&'static str, Arc<dyn Tracker>>,
+}
+
+impl TrackerRegistry {
+    pub fn from_config(config: &TrackersConfig, http: reqwest::Client) -> Self {
+        let mut registry = Self::default();
+        if let Some(client) = &config.anilist {
+            registry.register(Arc::new(anilist::AniList::new(
+                client.clone(),
+                http.clone(),
+            )));
+        }
+        if let Some(client) = &config.myanimelist {
+            registry.register(Arc::new(myanimelist::MyAnimeList::new(
+                client.clone(),
+                http.clone(),
+            )));
+        }
+        registry
+    }
+
+    pub fn register(&mut self, tracker: Arc<dyn Tracker>) {
+        self.trackers.insert(tracker.id(), tracker);
+    }
+
+    pub fn get(&self, id: &str) -> Option<Arc<dyn Tracker>> {
+        self.trackers.get(id).cloned()
+    }
+
+    pub fn ids(&self) -> Vec<&'static str> {
+        let mut ids: Vec<_> = self.trackers.keys().copied().collect();
+        ids.sort_unstable();
+        ids
+    }
+}
@@ -0,0 +1,145 @@
+use async_trait::async_trait;
+
+use super::{ListUpdate, Tracker, TrackerError, TrackerToken};
+use crate::config::TrackerClientConfig;
+use crate::users::status::ReadingStatus;
+
+/// Key of the tracker in MangaDex's `links` attribute.
+pub const LINK_KEY: &str = "mal";
+
+pub const TRACKER_ID: &str = "myanimelist";
+
+/// MyAnimeList, through its v2 REST API. Authorization uses PKCE with the
+/// `plain` method, the only one MAL supports.
+pub struct MyAnimeList {
+    config: TrackerClientConfig,
+    http: reqwest::Client,
+}
+
+impl MyAnimeList {
+    pub fn new(config: TrackerClientConfig, http: reqwest::Client) -> Self {
+        Self { config, http }
+    }
+
+    async fn token_request(&self, form: &[(&str, &str)]) -> Result<TrackerToken, TrackerError> {
+        let response = self
+            .http
+            .post(&self.config.token_url)
+            .form(form)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            return Err(TrackerError::from_status(
+                status,
+                response.text().await.unwrap_or_default(),
+            ));
+        }
+
+        Ok(response.json().await?)
+    }
+}
+
+fn list_status(status: ReadingStatus) -> &'static str {
+    match status {
+        // MAL tracks re-reads with a flag instead of a status
+        ReadingStatus::Reading | ReadingStatus::Rereading => "reading",
+        ReadingStatus::Completed => "completed",
+        ReadingStatus::OnHold => "on_hold",
+        ReadingStatus::Dropped => "dropped",
+        ReadingStatus::PlanToRead => "plan_to_read",
+    }
+}
+
+#[async_trait]
+impl Tracker for MyAnimeList {
+    fn id(&self) -> &'static str {
+        TRACKER_ID
+    }
+
+    fn link_key(&self) -> &'static str {
+        LINK_KEY
+    }
+
+    fn authorize_url(&self, state: &str, code_verifier: &str) -> String {
+        format!(
+            "{}?response_type=code&client_id={}&redirect_uri={}&state={}&code_challenge={}&code_challenge_method=plain",
+            self.config.auth_url,
+            urlencoding::encode(&self.config.client_id),
+            urlencoding::encode(&self.config.redirect_uri),
+            urlencoding::encode(state),
+            urlencoding::encode(code_verifier)
+        )
+    }
+
+    async fn exchange_code(
+        &self,
+        code: &str,
+        code_verifier: &str,
+    ) -> Result<TrackerToken, TrackerError> {
+        self.token_request(&[
+            ("grant_type", "authorization_code"),
+            ("client_id", &self.config.client_id),
+            ("client_secret", &self.config.client_secret),
+            ("redirect_uri", &self.config.redirect_uri),
+            ("code", code),
+            ("code_verifier", code_verifier),
+        ])
+        .await
+    }
+
+    async fn refresh_token(&self, refresh_token: &str) -> Result<TrackerToken, TrackerError> {
+        self.token_request(&[
+            ("grant_type", "refresh_token"),
+            ("client_id", &self.config.client_id),
+            ("client_secret", &self.config.client_secret),
+            ("refresh_token", refresh_token),
+        ])
+        .await
+    }
+
+    async fn update_entry(
+        &self,
+        access_token: &str,
+        remote_id: &str,
+        update: &ListUpdate,
+    ) -> Result<(), TrackerError> {
+        let url = format!(
+            "{}/manga/{}/my_list_status",
+            self.config.api_url,
+            urlencoding::encode(remote_id)
+        );
+
+        let mut form = vec![
+            ("status", list_status(update.status).to_string()),
+            ("num_chapters_read", update.chapters_read.to_string()),
+            (
+                "is_rereading",
+                (update.status == ReadingStatus::Rereading).to_string(),
+            ),
+            ("num_times_reread", update.reread_count.to_string()),
+        ];
+        if let Some(score) = update.score {
+            form.push(("score", score.to_string()));
+        }
+
+        let response = self
+            .http
+            .patch(&url)
+            .bearer_auth(access_token)
+            .form(&form)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            return Err(TrackerError::from_status(
+                status,
+                response.text().await.unwrap_or_default(),
+            ));
+        }
+
+        Ok(())
+    }
+}
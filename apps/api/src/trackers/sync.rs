@@ -0,0 +1,742 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
+
+use super::{ListUpdate, Tracker, TrackerError, TrackerToken};
+use crate::users::status::{MangaStatus, ReadingStatus};
+use crate::AppState;
+
+/// How long a claimed queue row is hidden from other workers.
+const CLAIM_SECS: i64 = 300;
+
+/// Tracker IDs taken from a source are trusted for this long.
+const MAPPING_TTL_DAYS: i64 = 7;
+
+/// Longest wait between retries of a failing push.
+const MAX_BACKOFF_MINUTES: i64 = 360;
+
+#[derive(FromRow)]
+struct QueuedSync {
+    user_id: Uuid,
+    tracker: String,
+    source: String,
+    manga_mangadex_id: String,
+    attempts: i32,
+    /// Last change to the row; a newer value means it was queued again
+    /// while this claim was being pushed.
+    updated_at: DateTime<Utc>,
+}
+
+#[derive(FromRow)]
+struct TrackerAccount {
+    access_token: String,
+    refresh_token: Option<String>,
+    expires_at: Option<DateTime<Utc>>,
+}
+
+enum Outcome {
+    Synced,
+    /// Nothing to push: the account was unlinked, the tracker is no longer
+    /// configured, or the manga isn't listed on it.
+    Skipped,
+}
+
+/// Counts from one pass over the queue.
+#[derive(Debug, Default)]
+pub struct SyncSummary {
+    pub synced: u32,
+    pub skipped: u32,
+    pub failed: u32,
+}
+
+/// Queues a push of the user's progress on a manga to every tracker they
+/// have linked. A newer change to the same manga replaces a pending one.
+pub async fn enqueue(
+    db: &PgPool,
+    user_id: Uuid,
+    source: &str,
+    manga_mangadex_id: &str,
+) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        r#"
+        INSERT INTO tracker_sync_queue (user_id, tracker, source, manga_mangadex_id)
+        SELECT user_id, tracker, $2, $3
+        FROM tracker_accounts
+        WHERE user_id = $1
+        ON CONFLICT (user_id, tracker, source, manga_mangadex_id) DO UPDATE SET
+            attempts = 0,
+            next_attempt_at = NOW(),
+            failed = FALSE,
+            last_error = NULL,
+            updated_at = NOW()
+        "#,
+    )
+    .bind(user_id)
+    .bind(source)
+    .bind(manga_mangadex_id)
+    .execute(db)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Queues a push and tries it straight away, leaving retries to the
+/// scheduled job. Errors are logged; callers have already saved the
+/// progress itself.
+pub async fn enqueue_and_push(
+    state: &AppState,
+    user_id: Uuid,
+    source: &str,
+    manga_mangadex_id: &str,
+) {
+    match enqueue(&state.db_pool, user_id, source, manga_mangadex_id).await {
+        Ok(0) => {}
+        Ok(_) => {
+            let state = state.clone();
+            tokio::spawn(async move {
+                if let Err(e) = process_due(&state, Some(user_id), 10).await {
+                    tracing::warn!("tracker sync for {} failed: {:#}", user_id, e);
+                }
+            });
+        }
+        Err(e) => tracing::warn!("failed to queue tracker sync for {}: {}", user_id, e),
+    }
+}
+
+/// Pushes up to `limit` due queue rows, optionally only for one user.
+pub async fn process_due(
+    state: &AppState,
+    user_id: Option<Uuid>,
+    limit: i64,
+) -> anyhow::Result<SyncSummary> {
+    // Claim rows first so the job and request-triggered pushes never send
+    // the same row twice
+    let claimed = sqlx::query_as::<_, QueuedSync>(
+        r#"
+        UPDATE tracker_sync_queue q
+        SET next_attempt_at = NOW() + make_interval(secs => $3)
+        WHERE (q.user_id, q.tracker, q.source, q.manga_mangadex_id) IN (
+            SELECT user_id, tracker, source, manga_mangadex_id
+            FROM tracker_sync_queue
+            WHERE NOT failed
+                AND next_attempt_at <= NOW()
+                AND ($1::uuid IS NULL OR user_id = $1)
+                -- Accounts pending deletion stay queued in case they're restored
+                AND user_id IN (SELECT id FROM users WHERE deleted_at IS NULL)
+            ORDER BY next_attempt_at
+            LIMIT $2
+            FOR UPDATE SKIP LOCKED
+        )
+        RETURNING q.user_id, q.tracker, q.source, q.manga_mangadex_id, q.attempts, q.updated_at
+        "#,
+    )
+    .bind(user_id)
+    .bind(limit)
+    .bind(CLAIM_SECS as f64)
+    .fetch_all(&state.db_pool)
+    .await
+    .context("Failed to claim tracker syncs")?;
+
+    let mut summary = SyncSummary::default();
+    for item in claimed {
+        match sync_one(state, &item).await {
+            Ok(outcome) => {
+                match outcome {
+                    Outcome::Synced => summary.synced += 1,
+                    Outcome::Skipped => summary.skipped += 1,
+                }
+                // A row queued again during the push still has newer
+                // progress to send
+                sqlx::query(
+                    r#"
+                    DELETE FROM tracker_sync_queue
+                    WHERE user_id = $1 AND tracker = $2 AND source = $3 AND manga_mangadex_id = $4
+                        AND updated_at = $5
+                    "#,
+                )
+                .bind(item.user_id)
+                .bind(&item.tracker)
+                .bind(&item.source)
+                .bind(&item.manga_mangadex_id)
+                .bind(item.updated_at)
+                .execute(&state.db_pool)
+                .await?;
+            }
+            Err(e) => {
+                summary.failed += 1;
+                tracing::warn!(
+                    "push to {} for {} ({}) failed: {:#}",
+                    item.tracker,
+                    item.user_id,
+                    item.manga_mangadex_id,
+                    e
+                );
+                record_failure(state, &item, &e).await?;
+            }
+        }
+    }
+
+    Ok(summary)
+}
+
+/// Minutes to wait before the next try of a push that has failed
+/// `attempts` times.
+fn backoff_minutes(attempts: i32) -> i64 {
+    2_i64
+        .saturating_pow(attempts.max(0) as u32)
+        .min(MAX_BACKOFF_MINUTES)
+}
+
+/// Schedules a retry, unless the row was queued again during the push and
+/// is already due with fresh attempts.
+async fn record_failure(
+    state: &AppState,
+    item: &QueuedSync,
+    error: &anyhow::Error,
+) -> Result<(), sqlx::Error> {
+    let attempts = item.attempts + 1;
+    let failed = attempts >= state.tracker_config.sync_max_attempts;
+
+    sqlx::query(
+        r#"
+        UPDATE tracker_sync_queue
+        SET attempts = $5,
+            next_attempt_at = NOW() + make_interval(mins => $6),
+            failed = $7,
+            last_error = $8,
+            updated_at = NOW()
+        WHERE user_id = $1 AND tracker = $2 AND source = $3 AND manga_mangadex_id = $4
+            AND updated_at = $9
+        "#,
+    )
+    .bind(item.user_id)
+    .bind(&item.tracker)
+    .bind(&item.source)
+    .bind(&item.manga_mangadex_id)
+    .bind(attempts)
+    .bind(backoff_minutes(attempts) as i32)
+    .bind(failed)
+    .bind(format!("{:#}", error))
+    .bind(item.updated_at)
+    .execute(&state.db_pool)
+    .await?;
+
+    Ok(())
+}
+
+async fn sync_one(state: &AppState, item: &QueuedSync) -> anyhow::Result<Outcome> {
+    let Some(tracker) = state.trackers.get(&item.tracker) else {
+        return Ok(Outcome::Skipped);
+    };
+
+    let account = sqlx::query_as::<_, TrackerAccount>(
+        r#"
+        SELECT access_token, refresh_token, expires_at
+        FROM tracker_accounts
+        WHERE user_id = $1 AND tracker = $2
+        "#,
+    )
+    .bind(item.user_id)
+    .bind(&item.tracker)
+    .fetch_optional(&state.db_pool)
+    .await?;
+    let Some(account) = account else {
+        return Ok(Outcome::Skipped);
+    };
+
+    let Some(remote_id) = remote_id(state, &tracker, &item.source, &item.manga_mangadex_id).await?
+    else {
+        return Ok(Outcome::Skipped);
+    };
+
+    let update = list_update(state, item).await?;
+
+    let store = AccountTokens {
+        db: &state.db_pool,
+        user_id: item.user_id,
+        tracker: tracker.id(),
+    };
+    push_update(tracker.as_ref(), account, &remote_id, &update, &store).await?;
+
+    Ok(Outcome::Synced)
+}
+
+/// Writes `update` to the tracker. An access token about to expire is
+/// refreshed first, and one the tracker rejects is refreshed once and the
+/// push retried.
+async fn push_update(
+    tracker: &dyn Tracker,
+    mut account: TrackerAccount,
+    remote_id: &str,
+    update: &ListUpdate,
+    store: &dyn TokenStore,
+) -> anyhow::Result<()> {
+    let expiring = account
+        .expires_at
+        .is_some_and(|expires_at| expires_at <= Utc::now() + Duration::seconds(60));
+    if expiring {
+        account = refresh(tracker, account, store).await?;
+    }
+
+    match tracker
+        .update_entry(&account.access_token, remote_id, update)
+        .await
+    {
+        Err(TrackerError::Unauthorized) if account.refresh_token.is_some() && !expiring => {
+            account = refresh(tracker, account, store).await?;
+            tracker
+                .update_entry(&account.access_token, remote_id, update)
+                .await?;
+        }
+        result => result?,
+    }
+
+    Ok(())
+}
+
+/// Where refreshed tokens are kept. They are stored before first use, since
+/// a refresh may invalidate the old refresh token.
+#[async_trait]
+trait TokenStore: Send + Sync {
+    async fn save(&self, token: &TrackerToken) -> anyhow::Result<()>;
+}
+
+/// The user's row in `tracker_accounts`.
+struct AccountTokens<'a> {
+    db: &'a PgPool,
+    user_id: Uuid,
+    tracker: &'static str,
+}
+
+#[async_trait]
+impl TokenStore for AccountTokens<'_> {
+    async fn save(&self, token: &TrackerToken) -> anyhow::Result<()> {
+        save_token(self.db, self.user_id, self.tracker, token).await?;
+        Ok(())
+    }
+}
+
+/// Exchanges the refresh token for a new access token and stores it.
+async fn refresh(
+    tracker: &dyn Tracker,
+    account: TrackerAccount,
+    store: &dyn TokenStore,
+) -> anyhow::Result<TrackerAccount> {
+    let Some(refresh_token) = account.refresh_token.as_deref() else {
+        return Err(TrackerError::Unauthorized.into());
+    };
+
+    let token = tracker.refresh_token(refresh_token).await?;
+    store.save(&token).await?;
+
+    Ok(TrackerAccount {
+        access_token: token.access_token,
+        refresh_token: token.refresh_token.or(account.refresh_token),
+        expires_at: token
+            .expires_in
+            .map(|secs| Utc::now() + Duration::seconds(secs)),
+    })
+}
+
+/// Stores tokens for a newly linked or refreshed account.
+pub async fn save_token(
+    db: &PgPool,
+    user_id: Uuid,
+    tracker: &str,
+    token: &TrackerToken,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO tracker_accounts (user_id, tracker, access_token, refresh_token, expires_at)
+        VALUES ($1, $2, $3, $4, NOW() + make_interval(secs => $5))
+        ON CONFLICT (user_id, tracker) DO UPDATE SET
+            access_token = EXCLUDED.access_token,
+            refresh_token = COALESCE(EXCLUDED.refresh_token, tracker_accounts.refresh_token),
+            expires_at = EXCLUDED.expires_at,
+            updated_at = NOW()
+        "#,
+    )
+    .bind(user_id)
+    .bind(tracker)
+    .bind(&token.access_token)
+    .bind(&token.refresh_token)
+    .bind(token.expires_in.map(|secs| secs as f64))
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
+
+/// The manga's ID on `tracker`, from the mapping table or else the source's
+/// links. Every configured tracker's mapping is stored from one lookup.
+async fn remote_id(
+    state: &AppState,
+    tracker: &Arc<dyn Tracker>,
+    source: &str,
+    manga_mangadex_id: &str,
+) -> anyhow::Result<Option<String>> {
+    let mapping: Option<(Option<String>,)> = sqlx::query_as(
+        r#"
+        SELECT remote_id
+        FROM tracker_mappings
+        WHERE source = $1
+            AND manga_mangadex_id = $2
+            AND tracker = $3
+            AND resolved_at > NOW() - make_interval(days => $4)
+        "#,
+    )
+    .bind(source)
+    .bind(manga_mangadex_id)
+    .bind(tracker.id())
+    .bind(MAPPING_TTL_DAYS as i32)
+    .fetch_optional(&state.db_pool)
+    .await?;
+
+    if let Some((remote_id,)) = mapping {
+        return Ok(remote_id);
+    }
+
+    let Some(manga_source) = state.sources.get(source) else {
+        return Ok(None);
+    };
+    let links = manga_source
+        .tracker_links(manga_mangadex_id)
+        .await
+        .context("Failed to look up tracker links")?;
+
+    for id in state.trackers.ids() {
+        let Some(other) = state.trackers.get(id) else {
+            continue;
+        };
+        sqlx::query(
+            r#"
+            INSERT INTO tracker_mappings (source, manga_mangadex_id, tracker, remote_id)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (source, manga_mangadex_id, tracker) DO UPDATE SET
+                remote_id = EXCLUDED.remote_id,
+                resolved_at = NOW()
+            "#,
+        )
+        .bind(source)
+        .bind(manga_mangadex_id)
+        .bind(other.id())
+        .bind(links.get(other.link_key()))
+        .execute(&state.db_pool)
+        .await?;
+    }
+
+    Ok(links.get(tracker.link_key()).cloned())
+}
+
+/// The user's current standing on the manga: the furthest whole chapter
+/// they have read, plus their status and score if they have set them.
+async fn list_update(state: &AppState, item: &QueuedSync) -> anyhow::Result<ListUpdate> {
+    let (furthest, latest_chapter): (Option<i64>, Option<String>) = sqlx::query_as(
+        r#"
+        WITH read AS (
+            SELECT chapter_mangadex_id, read_at AS at
+            FROM reading_history
+            WHERE user_id = $1 AND source = $2 AND manga_mangadex_id = $3
+            UNION ALL
+            SELECT chapter_mangadex_id, updated_at AS at
+            FROM user_reading_progress
+            WHERE user_id = $1 AND source = $2 AND manga_mangadex_id = $3
+        )
+        SELECT
+            (
+                SELECT FLOOR(MAX(chapter_number_value(c.chapter_number)))::bigint
+                FROM chapter_cache c
+                JOIN read r ON r.chapter_mangadex_id = c.mangadex_id
+                WHERE c.source = $2
+            ),
+            (SELECT chapter_mangadex_id FROM read ORDER BY at DESC LIMIT 1)
+        "#,
+    )
+    .bind(item.user_id)
+    .bind(&item.source)
+    .bind(&item.manga_mangadex_id)
+    .fetch_one(&state.db_pool)
+    .await?;
+
+    // Chapters read before their list was cached: ask the source
+    let furthest = match (furthest, latest_chapter) {
+        (Some(furthest), _) => Some(furthest),
+        (None, Some(chapter_id)) => match state.sources.get(&item.source) {
+            Some(source) => source
+                .get_chapter(&chapter_id)
+                .await
+                .ok()
+                .and_then(|chapter| chapter.chapter_number)
+                .and_then(|number| number.trim().parse::<f64>().ok())
+                .map(|number| number.floor() as i64),
+            None => None,
+        },
+        (None, None) => None,
+    };
+
+    let status = MangaStatus::load(
+        &state.db_pool,
+        item.user_id,
+        &item.source,
+        &item.manga_mangadex_id,
+    )
+    .await?;
+
+    Ok(ListUpdate {
+        chapters_read: furthest.unwrap_or(0).max(0) as u32,
+        status: status
+            .as_ref()
+            .map(|s| s.status)
+            .unwrap_or(ReadingStatus::Reading),
+        score: status.as_ref().and_then(|s| s.score),
+        reread_count: status.as_ref().map(|s| s.reread_count).unwrap_or(0),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::config::TrackerClientConfig;
+    use crate::trackers::{anilist::AniList, mock, myanimelist::MyAnimeList};
+
+    /// Serves the mock tracker locally and returns its base URL.
+    async fn mock_tracker() -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, mock::router()).await });
+
+        format!("http://{addr}")
+    }
+
+    fn client_config(base: &str, api_path: &str) -> TrackerClientConfig {
+        TrackerClientConfig {
+            client_id: "mock".to_string(),
+            client_secret: "mock".to_string(),
+            redirect_uri: "http://localhost:3000/trackers/callback".to_string(),
+            auth_url: format!("{base}/oauth/authorize"),
+            token_url: format!("{base}/oauth/token"),
+            api_url: format!("{base}{api_path}"),
+        }
+    }
+
+    fn myanimelist(base: &str) -> MyAnimeList {
+        MyAnimeList::new(client_config(base, "/v2"), reqwest::Client::new())
+    }
+
+    fn anilist(base: &str) -> AniList {
+        AniList::new(client_config(base, "/graphql"), reqwest::Client::new())
+    }
+
+    async fn fail_next(base: &str, count: u32, status: u16) {
+        reqwest::Client::new()
+            .post(format!("{base}/_mock/fail?count={count}&status={status}"))
+            .send()
+            .await
+            .unwrap()
+            .error_for_status()
+            .unwrap();
+    }
+
+    async fn recorded(base: &str) -> Vec<serde_json::Value> {
+        reqwest::get(format!("{base}/_mock/requests"))
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap()
+    }
+
+    /// Keeps the access tokens it is handed, in order.
+    #[derive(Default)]
+    struct SavedTokens(Mutex<Vec<String>>);
+
+    #[async_trait]
+    impl TokenStore for SavedTokens {
+        async fn save(&self, token: &TrackerToken) -> anyhow::Result<()> {
+            self.0.lock().unwrap().push(token.access_token.clone());
+            Ok(())
+        }
+    }
+
+    impl SavedTokens {
+        fn tokens(&self) -> Vec<String> {
+            self.0.lock().unwrap().clone()
+        }
+    }
+
+    fn account(expires_at: Option<DateTime<Utc>>) -> TrackerAccount {
+        TrackerAccount {
+            access_token: "stale".to_string(),
+            refresh_token: Some("refresh".to_string()),
+            expires_at,
+        }
+    }
+
+    fn update() -> ListUpdate {
+        ListUpdate {
+            chapters_read: 12,
+            status: ReadingStatus::Reading,
+            score: Some(8),
+            reread_count: 0,
+        }
+    }
+
+    fn tracker_error(error: &anyhow::Error) -> Option<&TrackerError> {
+        error.downcast_ref::<TrackerError>()
+    }
+
+    #[tokio::test]
+    async fn exchanges_codes_for_tokens() {
+        let base = mock_tracker().await;
+
+        let token = myanimelist(&base)
+            .exchange_code("mock-code", "verifier")
+            .await
+            .unwrap();
+        assert_eq!(token.access_token, "mock-access-1");
+        assert_eq!(token.refresh_token.as_deref(), Some("mock-refresh-1"));
+        assert_eq!(token.expires_in, Some(3600));
+
+        let token = anilist(&base)
+            .exchange_code("mock-code", "verifier")
+            .await
+            .unwrap();
+        assert_eq!(token.access_token, "mock-access-2");
+    }
+
+    #[tokio::test]
+    async fn writes_list_entries() {
+        let base = mock_tracker().await;
+
+        myanimelist(&base)
+            .update_entry("token", "42", &update())
+            .await
+            .unwrap();
+        anilist(&base)
+            .update_entry("token", "7", &update())
+            .await
+            .unwrap();
+
+        let requests = recorded(&base).await;
+        assert_eq!(requests.len(), 2);
+
+        assert_eq!(requests[0]["tracker"], "myanimelist");
+        assert_eq!(requests[0]["authorization"], "Bearer token");
+        assert_eq!(requests[0]["media_id"], "42");
+        let body = requests[0]["body"].as_str().unwrap();
+        assert!(body.contains("num_chapters_read=12"));
+        assert!(body.contains("status=reading"));
+        assert!(body.contains("score=8"));
+
+        assert_eq!(requests[1]["tracker"], "anilist");
+        assert_eq!(requests[1]["media_id"], "7");
+        let body: serde_json::Value =
+            serde_json::from_str(requests[1]["body"].as_str().unwrap()).unwrap();
+        assert_eq!(body["variables"]["progress"], 12);
+        assert_eq!(body["variables"]["scoreRaw"], 80);
+    }
+
+    #[tokio::test]
+    async fn refreshes_a_rejected_token_and_retries() {
+        let base = mock_tracker().await;
+        fail_next(&base, 1, 401).await;
+        let store = SavedTokens::default();
+
+        push_update(&myanimelist(&base), account(None), "42", &update(), &store)
+            .await
+            .unwrap();
+
+        assert_eq!(store.tokens(), ["mock-access-1"]);
+        let requests = recorded(&base).await;
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0]["authorization"], "Bearer mock-access-1");
+    }
+
+    #[tokio::test]
+    async fn refreshes_an_expiring_token_before_pushing() {
+        let base = mock_tracker().await;
+        let store = SavedTokens::default();
+        let expires_at = Utc::now() + Duration::seconds(10);
+
+        push_update(
+            &myanimelist(&base),
+            account(Some(expires_at)),
+            "42",
+            &update(),
+            &store,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(store.tokens(), ["mock-access-1"]);
+        assert_eq!(
+            recorded(&base).await[0]["authorization"],
+            "Bearer mock-access-1"
+        );
+    }
+
+    #[tokio::test]
+    async fn refreshes_only_once() {
+        let base = mock_tracker().await;
+        fail_next(&base, 2, 401).await;
+        let store = SavedTokens::default();
+
+        let error = push_update(&myanimelist(&base), account(None), "42", &update(), &store)
+            .await
+            .unwrap_err();
+
+        assert!(matches!(
+            tracker_error(&error),
+            Some(TrackerError::Unauthorized)
+        ));
+        assert_eq!(store.tokens(), ["mock-access-1"]);
+        assert!(recorded(&base).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn leaves_server_errors_to_the_retry_queue() {
+        let base = mock_tracker().await;
+        let store = SavedTokens::default();
+
+        fail_next(&base, 1, 500).await;
+        let error = push_update(&myanimelist(&base), account(None), "42", &update(), &store)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            tracker_error(&error),
+            Some(TrackerError::ApiError(_))
+        ));
+
+        fail_next(&base, 1, 429).await;
+        let error = push_update(&anilist(&base), account(None), "7", &update(), &store)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            tracker_error(&error),
+            Some(TrackerError::RateLimited)
+        ));
+
+        assert!(store.tokens().is_empty());
+
+        // The next try after the backoff goes through
+        push_update(&myanimelist(&base), account(None), "42", &update(), &store)
+            .await
+            .unwrap();
+        assert_eq!(recorded(&base).await.len(), 1);
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        assert_eq!(backoff_minutes(1), 2);
+        assert_eq!(backoff_minutes(3), 8);
+        assert_eq!(backoff_minutes(8), 256);
+        assert_eq!(backoff_minutes(9), MAX_BACKOFF_MINUTES);
+        assert_eq!(backoff_minutes(100), MAX_BACKOFF_MINUTES);
+    }
+}
@@ -0,0 +1,746 @@
+use std::collections::HashMap;
+use std::io::Write;
+
+use chrono::{DateTime, Duration, Utc};
+use prost::Message;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
+
+use crate::auth::jwt::hash_token;
+use crate::mangadex::source::SOURCE_ID as MANGADEX;
+use crate::users::import::tachiyomi::{
+    Backup, BackupCategory, BackupChapter, BackupHistory, BackupManga, MANGADEX_PATH_CHAPTER,
+    MANGADEX_PATH_MANGA, MANGADEX_SOURCE_ID,
+};
+use crate::users::status::MangaStatus;
+use crate::users::UserPreferences;
+use crate::AppState;
+
+/// Version of the JSON archive layout.
+const ARCHIVE_VERSION: u32 = 1;
+
+/// A builder that hasn't finished by then is assumed dead.
+const BUILD_LEASE_MINUTES: i32 = 10;
+
+/// Builds tried this many times without finishing are given up on.
+const MAX_ATTEMPTS: i32 = 3;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "export_format", rename_all = "snake_case")]
+pub enum ExportFormat {
+    /// Everything we store about the user, as gzipped JSON.
+    #[default]
+    Json,
+    /// A `.tachibk` backup Mihon can restore. Only MangaDex manga are
+    /// included since other sources have no matching extension.
+    Tachiyomi,
+}
+
+impl ExportFormat {
+    pub fn file_name(&self, created_at: DateTime<Utc>) -> String {
+        let date = created_at.format("%Y-%m-%d");
+        match self {
+            ExportFormat::Json => format!("account-export-{}.json.gz", date),
+            ExportFormat::Tachiyomi => format!("library-{}.tachibk", date),
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Json => "application/gzip",
+            ExportFormat::Tachiyomi => "application/octet-stream",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "export_status", rename_all = "snake_case")]
+pub enum ExportStatus {
+    Pending,
+    Ready,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct DataExport {
+    pub id: Uuid,
+    pub format: ExportFormat,
+    pub status: ExportStatus,
+    pub size_bytes: Option<i64>,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    /// When the download link stops working.
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+const EXPORT_COLUMNS: &str =
+    "id, format, status, size_bytes, error, created_at, completed_at, expires_at";
+
+impl DataExport {
+    pub async fn load(
+        db: &PgPool,
+        user_id: Uuid,
+        export_id: Uuid,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        sqlx::query_as::<_, Self>(&format!(
+            "SELECT {} FROM data_exports WHERE user_id = $1 AND id = $2",
+            EXPORT_COLUMNS
+        ))
+        .bind(user_id)
+        .bind(export_id)
+        .fetch_optional(db)
+        .await
+    }
+
+    /// The user's export of `format` that is still being built, if any.
+    pub async fn pending(
+        db: &PgPool,
+        user_id: Uuid,
+        format: ExportFormat,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        sqlx::query_as::<_, Self>(&format!(
+            r#"
+            SELECT {} FROM data_exports
+            WHERE user_id = $1 AND format = $2 AND status = 'pending'
+            ORDER BY created_at DESC
+            LIMIT 1
+            "#,
+            EXPORT_COLUMNS
+        ))
+        .bind(user_id)
+        .bind(format)
+        .fetch_optional(db)
+        .await
+    }
+
+    /// Creates a pending export along with its download token. Only the
+    /// token's hash is stored, so this is the one chance to hand it out.
+    pub async fn create(
+        db: &PgPool,
+        user_id: Uuid,
+        format: ExportFormat,
+    ) -> Result<(Self, String), sqlx::Error> {
+        let download_token = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+
+        let export = sqlx::query_as::<_, Self>(&format!(
+            r#"
+            INSERT INTO data_exports (user_id, format, download_token_hash)
+            VALUES ($1, $2, $3)
+            RETURNING {}
+            "#,
+            EXPORT_COLUMNS
+        ))
+        .bind(user_id)
+        .bind(format)
+        .bind(hash_token(&download_token))
+        .fetch_one(db)
+        .await?;
+
+        Ok((export, download_token))
+    }
+}
+
+/// Link an export can be downloaded from without logging in, once it is
+/// ready.
+pub fn download_url(download_token: &str) -> String {
+    format!("/exports/{}", download_token)
+}
+
+/// Exports the user started in the last hour.
+pub async fn recent_count(db: &PgPool, user_id: Uuid) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar(
+        "SELECT COUNT(*) FROM data_exports WHERE user_id = $1 AND created_at > NOW() - INTERVAL '1 hour'",
+    )
+    .bind(user_id)
+    .fetch_one(db)
+    .await
+}
+
+/// Bookmark, progress and history rows, a rough measure of how long an
+/// export will take.
+pub async fn account_size(db: &PgPool, user_id: Uuid) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar(
+        r#"
+        SELECT
+            (SELECT COUNT(*) FROM user_bookmarks WHERE user_id = $1)
+            + (SELECT COUNT(*) FROM user_reading_progress WHERE user_id = $1)
+            + (SELECT COUNT(*) FROM reading_history WHERE user_id = $1)
+        "#,
+    )
+    .bind(user_id)
+    .fetch_one(db)
+    .await
+}
+
+#[derive(Serialize, FromRow)]
+struct Profile {
+    id: Uuid,
+    email: String,
+    username: String,
+    role: String,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, FromRow)]
+struct CategoryRow {
+    name: String,
+    position: i32,
+}
+
+#[derive(Serialize, FromRow)]
+struct BookmarkRow {
+    source: String,
+    manga_id: String,
+    title: Option<String>,
+    added_at: DateTime<Utc>,
+    /// Category names.
+    categories: Vec<String>,
+}
+
+#[derive(Serialize, FromRow)]
+struct ProgressRow {
+    source: String,
+    manga_id: String,
+    chapter_id: String,
+    page_number: i32,
+    updated_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, FromRow)]
+struct HistoryRow {
+    source: String,
+    manga_id: String,
+    chapter_id: String,
+    chapter_number: Option<String>,
+    read_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, FromRow)]
+struct StatusRow {
+    source: String,
+    manga_id: String,
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    status: MangaStatus,
+}
+
+#[derive(Serialize, FromRow)]
+struct TrackerRow {
+    tracker: String,
+    linked_at: DateTime<Utc>,
+}
+
+/// Everything stored about a user except credentials and tokens.
+#[derive(Serialize)]
+struct Archive {
+    version: u32,
+    exported_at: DateTime<Utc>,
+    profile: Profile,
+    preferences: UserPreferences,
+    categories: Vec<CategoryRow>,
+    bookmarks: Vec<BookmarkRow>,
+    progress: Vec<ProgressRow>,
+    history: Vec<HistoryRow>,
+    statuses: Vec<StatusRow>,
+    trackers: Vec<TrackerRow>,
+}
+
+async fn load_archive(db: &PgPool, user_id: Uuid) -> Result<Archive, sqlx::Error> {
+    let profile = sqlx::query_as::<_, Profile>(
+        "SELECT id, email, username, role::text AS role, created_at, updated_at FROM users WHERE id = $1",
+    )
+    .bind(user_id)
+    .fetch_one(db)
+    .await?;
+
+    let preferences = UserPreferences::load(db, user_id).await?;
+
+    let categories = sqlx::query_as::<_, CategoryRow>(
+        "SELECT name, position FROM library_categories WHERE user_id = $1 ORDER BY position, name",
+    )
+    .bind(user_id)
+    .fetch_all(db)
+    .await?;
+
+    let bookmarks = sqlx::query_as::<_, BookmarkRow>(
+        r#"
+        SELECT
+            b.source,
+            b.manga_mangadex_id AS manga_id,
+            m.title,
+            b.created_at AS added_at,
+            ARRAY(
+                SELECT c.name
+                FROM bookmark_categories bc
+                JOIN library_categories c ON c.id = bc.category_id
+                WHERE bc.bookmark_id = b.id
+                ORDER BY c.position, c.name
+            ) AS categories
+        FROM user_bookmarks b
+        LEFT JOIN manga_cache m ON m.source = b.source AND m.mangadex_id = b.manga_mangadex_id
+        WHERE b.user_id = $1
+        ORDER BY b.created_at
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(db)
+    .await?;
+
+    let progress = sqlx::query_as::<_, ProgressRow>(
+        r#"
+        SELECT
+            source,
+            manga_mangadex_id AS manga_id,
+            chapter_mangadex_id AS chapter_id,
+            page_number,
+            updated_at
+        FROM user_reading_progress
+        WHERE user_id = $1
+        ORDER BY updated_at
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(db)
+    .await?;
+
+    let history = sqlx::query_as::<_, HistoryRow>(
+        r#"
+        SELECT
+            h.source,
+            h.manga_mangadex_id AS manga_id,
+            h.chapter_mangadex_id AS chapter_id,
+            c.chapter_number,
+            h.read_at
+        FROM reading_history h
+        LEFT JOIN chapter_cache c ON c.source = h.source AND c.mangadex_id = h.chapter_mangadex_id
+        WHERE h.user_id = $1
+        ORDER BY h.read_at
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(db)
+    .await?;
+
+    let statuses = sqlx::query_as::<_, StatusRow>(
+        r#"
+        SELECT
+            source,
+            manga_mangadex_id AS manga_id,
+            status, score, started_on, finished_on, notes, reread_count, updated_at
+        FROM user_manga_status
+        WHERE user_id = $1
+        ORDER BY updated_at
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(db)
+    .await?;
+
+    let trackers = sqlx::query_as::<_, TrackerRow>(
+        "SELECT tracker, created_at AS linked_at FROM tracker_accounts WHERE user_id = $1 ORDER BY tracker",
+    )
+    .bind(user_id)
+    .fetch_all(db)
+    .await?;
+
+    Ok(Archive {
+        version: ARCHIVE_VERSION,
+        exported_at: Utc::now(),
+        profile,
+        preferences,
+        categories,
+        bookmarks,
+        progress,
+        history,
+        statuses,
+        trackers,
+    })
+}
+
+/// The library as a Mihon backup: bookmarks become favorites, with their
+/// categories, read chapters and history.
+fn tachiyomi_backup(archive: &Archive) -> Backup {
+    let category_order: HashMap<&str, i64> = archive
+        .categories
+        .iter()
+        .map(|category| (category.name.as_str(), category.position as i64))
+        .collect();
+
+    let mut history: HashMap<&str, Vec<&HistoryRow>> = HashMap::new();
+    for row in archive.history.iter().filter(|row| row.source == MANGADEX) {
+        history.entry(row.manga_id.as_str()).or_default().push(row);
+    }
+
+    let backup_manga = archive
+        .bookmarks
+        .iter()
+        .filter(|bookmark| bookmark.source == MANGADEX)
+        .map(|bookmark| {
+            let reads = history
+                .get(bookmark.manga_id.as_str())
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+
+            BackupManga {
+                source: MANGADEX_SOURCE_ID,
+                url: format!("{}{}", MANGADEX_PATH_MANGA, bookmark.manga_id),
+                title: bookmark.title.clone().unwrap_or_default(),
+                date_added: bookmark.added_at.timestamp_millis(),
+                chapters: reads
+                    .iter()
+                    .map(|row| {
+                        let number = row
+                            .chapter_number
+                            .as_deref()
+                            .and_then(|n| n.trim().parse::<f32>().ok());
+                        BackupChapter {
+                            url: format!("{}{}", MANGADEX_PATH_CHAPTER, row.chapter_id),
+                            // Mihon requires a name; it is replaced on the next refresh
+                            name: match number {
+                                Some(n) => format!("Chapter {}", n),
+                                None => "Chapter".to_string(),
+                            },
+                            read: true,
+                            chapter_number: number.unwrap_or(-1.0),
+                        }
+                    })
+                    .collect(),
+                categories: bookmark
+                    .categories
+                    .iter()
+                    .filter_map(|name| category_order.get(name.as_str()).copied())
+                    .collect(),
+                tracking: Vec::new(),
+                favorite: true,
+                history: reads
+                    .iter()
+                    .map(|row| BackupHistory {
+                        url: format!("{}{}", MANGADEX_PATH_CHAPTER, row.chapter_id),
+                        last_read: row.read_at.timestamp_millis(),
+                    })
+                    .collect(),
+            }
+        })
+        .collect();
+
+    Backup {
+        backup_manga,
+        backup_categories: archive
+            .categories
+            .iter()
+            .map(|category| BackupCategory {
+                name: category.name.clone(),
+                order: category.position as i64,
+            })
+            .collect(),
+    }
+}
+
+fn gzip(data: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(data)?;
+    encoder.finish()
+}
+
+async fn build_archive(
+    db: &PgPool,
+    user_id: Uuid,
+    format: ExportFormat,
+) -> anyhow::Result<Vec<u8>> {
+    let archive = load_archive(db, user_id).await?;
+
+    let data = match format {
+        ExportFormat::Json => serde_json::to_vec_pretty(&archive)?,
+        ExportFormat::Tachiyomi => tachiyomi_backup(&archive).encode_to_vec(),
+    };
+    Ok(gzip(&data)?)
+}
+
+#[derive(FromRow)]
+struct ClaimedExport {
+    id: Uuid,
+    user_id: Uuid,
+    format: ExportFormat,
+}
+
+/// Takes pending exports nobody is building, optionally only one of them.
+async fn claim(
+    db: &PgPool,
+    export_id: Option<Uuid>,
+    limit: i64,
+) -> Result<Vec<ClaimedExport>, sqlx::Error> {
+    sqlx::query_as::<_, ClaimedExport>(
+        r#"
+        UPDATE data_exports
+        SET locked_until = NOW() + $3 * INTERVAL '1 minute', attempts = attempts + 1
+        WHERE id IN (
+            SELECT id FROM data_exports
+            WHERE status = 'pending'
+                AND (locked_until IS NULL OR locked_until < NOW())
+                AND attempts < $4
+                AND ($1::uuid IS NULL OR id = $1)
+                -- Accounts pending deletion stay queued in case they're restored
+                AND user_id IN (SELECT id FROM users WHERE deleted_at IS NULL)
+            ORDER BY created_at
+            LIMIT $2
+            FOR UPDATE SKIP LOCKED
+        )
+        RETURNING id, user_id, format
+        "#,
+    )
+    .bind(export_id)
+    .bind(limit)
+    .bind(BUILD_LEASE_MINUTES as f64)
+    .bind(MAX_ATTEMPTS)
+    .fetch_all(db)
+    .await
+}
+
+/// Builds a claimed export and stores the result, or the error.
+async fn build(state: &AppState, export: ClaimedExport) -> Result<bool, sqlx::Error> {
+    let db = &state.db_pool;
+
+    match build_archive(db, export.user_id, export.format).await {
+        Ok(archive) => {
+            let expires_at = Utc::now() + Duration::hours(state.export_config.link_ttl_hours);
+            sqlx::query(
+                r#"
+                UPDATE data_exports
+                SET status = 'ready', archive = $2, size_bytes = $3,
+                    completed_at = NOW(), expires_at = $4, locked_until = NULL
+                WHERE id = $1
+                "#,
+            )
+            .bind(export.id)
+            .bind(&archive)
+            .bind(archive.len() as i64)
+            .bind(expires_at)
+            .execute(db)
+            .await?;
+            Ok(true)
+        }
+        Err(e) => {
+            tracing::warn!("export {} failed: {:#}", export.id, e);
+            sqlx::query(
+                r#"
+                UPDATE data_exports
+                SET status = 'failed', error = $2, completed_at = NOW(), locked_until = NULL
+                WHERE id = $1
+                "#,
+            )
+            .bind(export.id)
+            .bind("Export failed, please try again")
+            .execute(db)
+            .await?;
+            Ok(false)
+        }
+    }
+}
+
+/// Builds one export right away, within the caller's request.
+pub async fn build_now(state: &AppState, export_id: Uuid) -> Result<(), sqlx::Error> {
+    for export in claim(&state.db_pool, Some(export_id), 1).await? {
+        build(state, export).await?;
+    }
+    Ok(())
+}
+
+/// Builds one export in the background. If the process dies first, the
+/// scheduled job picks it up once the lease runs out.
+pub fn spawn_build(state: &AppState, export_id: Uuid) {
+    let state = state.clone();
+    tokio::spawn(async move {
+        if let Err(e) = build_now(&state, export_id).await {
+            tracing::warn!("export {} failed: {}", export_id, e);
+        }
+    });
+}
+
+#[derive(Debug, Default)]
+pub struct ExportSummary {
+    pub built: usize,
+    pub failed: usize,
+    pub abandoned: u64,
+    pub purged: u64,
+}
+
+/// Builds pending exports left behind, gives up on ones that keep dying,
+/// and deletes archives whose link has expired.
+pub async fn process_pending(state: &AppState, limit: i64) -> anyhow::Result<ExportSummary> {
+    let db = &state.db_pool;
+    let mut summary = ExportSummary::default();
+
+    for export in claim(db, None, limit).await? {
+        if build(state, export).await? {
+            summary.built += 1;
+        } else {
+            summary.failed += 1;
+        }
+    }
+
+    summary.abandoned = sqlx::query(
+        r#"
+        UPDATE data_exports
+        SET status = 'failed', error = 'Export failed, please try again', completed_at = NOW()
+        WHERE status = 'pending' AND attempts >= $1 AND locked_until < NOW()
+        "#,
+    )
+    .bind(MAX_ATTEMPTS)
+    .execute(db)
+    .await?
+    .rows_affected();
+
+    summary.purged = sqlx::query(
+        r#"
+        DELETE FROM data_exports
+        WHERE expires_at < NOW()
+            OR (status = 'failed' AND completed_at < NOW() - INTERVAL '1 day')
+        "#,
+    )
+    .execute(db)
+    .await?
+    .rows_affected();
+
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::users::import::tachiyomi;
+
+    const MANGA: &str = "a96676e5-8ae2-425e-b549-7f15dd34a6d8";
+    const CHAPTER: &str = "0e6b8f4a-1c55-4b3a-9d6e-2f0b7c1e9a11";
+
+    fn archive() -> Archive {
+        let now = Utc::now();
+        Archive {
+            version: ARCHIVE_VERSION,
+            exported_at: now,
+            profile: Profile {
+                id: Uuid::new_v4(),
+                email: "reader@example.com".into(),
+                username: "reader".into(),
+                role: "user".into(),
+                created_at: now,
+                updated_at: now,
+            },
+            preferences: UserPreferences::default(),
+            categories: vec![
+                CategoryRow {
+                    name: "Reading".into(),
+                    position: 0,
+                },
+                CategoryRow {
+                    name: "Later".into(),
+                    position: 1,
+                },
+            ],
+            bookmarks: vec![
+                BookmarkRow {
+                    source: MANGADEX.into(),
+                    manga_id: MANGA.into(),
+                    title: Some("Frieren".into()),
+                    added_at: now,
+                    categories: vec!["Later".into()],
+                },
+                BookmarkRow {
+                    source: "other".into(),
+                    manga_id: "elsewhere".into(),
+                    title: None,
+                    added_at: now,
+                    categories: Vec::new(),
+                },
+            ],
+            progress: Vec::new(),
+            history: vec![HistoryRow {
+                source: MANGADEX.into(),
+                manga_id: MANGA.into(),
+                chapter_id: CHAPTER.into(),
+                chapter_number: Some("12.5".into()),
+                read_at: now,
+            }],
+            statuses: Vec::new(),
+            trackers: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn tachiyomi_backup_holds_only_mangadex_favorites() {
+        let archive = archive();
+        let backup = tachiyomi_backup(&archive);
+
+        assert_eq!(backup.backup_categories.len(), 2);
+        assert_eq!(backup.backup_manga.len(), 1);
+
+        let manga = &backup.backup_manga[0];
+        assert_eq!(manga.source, MANGADEX_SOURCE_ID);
+        assert_eq!(manga.url, format!("/manga/{}", MANGA));
+        assert!(manga.favorite);
+        assert_eq!(manga.categories, vec![1]);
+        assert_eq!(manga.chapters.len(), 1);
+        assert_eq!(manga.chapters[0].chapter_number, 12.5);
+        assert_eq!(manga.chapters[0].name, "Chapter 12.5");
+        assert_eq!(
+            manga.history[0].last_read,
+            archive.history[0].read_at.timestamp_millis()
+        );
+    }
+
+    #[test]
+    fn tachiyomi_backup_imports_back() {
+        let backup = tachiyomi_backup(&archive()).encode_to_vec();
+        let entries = tachiyomi::parse(gzip(&backup).unwrap()).unwrap();
+
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].mangadex_id.as_deref(), Some(MANGA));
+        assert_eq!(entries[0].title, "Frieren");
+        assert_eq!(entries[0].categories, vec!["Later".to_string()]);
+        assert_eq!(entries[0].read_chapter_ids, vec![CHAPTER.to_string()]);
+    }
+
+    #[tokio::test]
+    async fn load_archive_gathers_the_account() {
+        let Some(db) = crate::db::test_pool().await else {
+            return;
+        };
+        let user_id = Uuid::new_v4();
+        let setup = [
+            "INSERT INTO users (id, email, username, password) VALUES ($1, $1::text || '@export.test', 'export-' || $1::text, 'secret-hash')",
+            "INSERT INTO library_categories (user_id, name, position) VALUES ($1, 'Later', 0)",
+            "INSERT INTO user_bookmarks (user_id, source, manga_mangadex_id) VALUES ($1, 'mangadex', 'manga-1')",
+            "INSERT INTO bookmark_categories (bookmark_id, category_id) SELECT b.id, c.id FROM user_bookmarks b, library_categories c WHERE b.user_id = $1 AND c.user_id = $1",
+            "INSERT INTO user_reading_progress (user_id, source, manga_mangadex_id, chapter_mangadex_id, page_number) VALUES ($1, 'mangadex', 'manga-1', 'chapter-2', 7)",
+            "INSERT INTO reading_history (user_id, source, manga_mangadex_id, chapter_mangadex_id) VALUES ($1, 'mangadex', 'manga-1', 'chapter-1')",
+            "INSERT INTO user_manga_status (user_id, source, manga_mangadex_id, status, score) VALUES ($1, 'mangadex', 'manga-1', 'reading', 8)",
+        ];
+        for query in setup {
+            sqlx::query(query).bind(user_id).execute(&db).await.unwrap();
+        }
+
+        let archive = load_archive(&db, user_id).await.unwrap();
+
+        assert_eq!(archive.profile.id, user_id);
+        assert_eq!(archive.categories.len(), 1);
+        assert_eq!(archive.bookmarks.len(), 1);
+        assert_eq!(archive.bookmarks[0].categories, vec!["Later".to_string()]);
+        assert_eq!(archive.progress[0].page_number, 7);
+        assert_eq!(archive.history[0].chapter_id, "chapter-1");
+        assert_eq!(archive.statuses[0].status.score, Some(8));
+
+        // Credentials never leave the server
+        let json = serde_json::to_string(&archive).unwrap();
+        assert!(!json.contains("secret-hash"));
+
+        sqlx::query("DELETE FROM users WHERE id = $1")
+            .bind(user_id)
+            .execute(&db)
+            .await
+            .unwrap();
+    }
+}
@@ -0,0 +1,74 @@
+use super::ImportedManga;
+use crate::mangadex::{Manga, MangaDexClient, MangaDexError};
+use crate::users::status::ReadingStatus;
+
+/// Follows are listed this many at a time; read markers take as many IDs.
+const PAGE_SIZE: u32 = 100;
+
+fn reading_status(status: &str) -> Option<ReadingStatus> {
+    match status {
+        "reading" => Some(ReadingStatus::Reading),
+        "completed" => Some(ReadingStatus::Completed),
+        "on_hold" => Some(ReadingStatus::OnHold),
+        "dropped" => Some(ReadingStatus::Dropped),
+        "plan_to_read" => Some(ReadingStatus::PlanToRead),
+        "re_reading" => Some(ReadingStatus::Rereading),
+        _ => None,
+    }
+}
+
+/// Reads the follows, reading statuses and read markers of the MangaDex
+/// account that `user_token` belongs to.
+pub async fn fetch_follows(
+    client: &MangaDexClient,
+    user_token: &str,
+) -> Result<Vec<ImportedManga>, MangaDexError> {
+    let mut follows = Vec::new();
+    let mut offset = 0;
+    loop {
+        let page = client
+            .get_followed_manga(user_token, PAGE_SIZE, offset)
+            .await?;
+        let count = page.data.len() as u32;
+        for manga in page.data {
+            follows.push(Manga::try_from(manga)?);
+        }
+
+        offset += count;
+        if count < PAGE_SIZE || page.total.is_some_and(|total| offset >= total) {
+            break;
+        }
+        if follows.len() >= super::MAX_ENTRIES {
+            break;
+        }
+    }
+
+    let statuses = client.get_reading_statuses(user_token).await?;
+
+    let mut entries: Vec<ImportedManga> = follows
+        .into_iter()
+        .map(|manga| ImportedManga {
+            status: statuses
+                .get(&manga.mangadex_id)
+                .and_then(|status| reading_status(status)),
+            title: manga.title,
+            mangadex_id: Some(manga.mangadex_id),
+            ..Default::default()
+        })
+        .collect();
+
+    for chunk in entries.chunks_mut(PAGE_SIZE as usize) {
+        let ids: Vec<String> = chunk
+            .iter()
+            .filter_map(|entry| entry.mangadex_id.clone())
+            .collect();
+        let mut read = client.get_read_markers(user_token, &ids).await?;
+        for entry in chunk {
+            if let Some(chapters) = entry.mangadex_id.as_ref().and_then(|id| read.remove(id)) {
+                entry.read_chapter_ids = chapters;
+            }
+        }
+    }
+
+    Ok(entries)
+}
@@ -0,0 +1,932 @@
+pub mod mangadex;
+pub mod myanimelist;
+pub mod tachiyomi;
+
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::io::Read;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
+
+use crate::mangadex::cache::{get_chapters_with_cache, get_mangas_with_cache};
+use crate::mangadex::MangaDexError;
+use crate::source::{ContentRating, MangaSource, SearchFilters};
+use crate::trackers::{self, anilist};
+use crate::users::status::ReadingStatus;
+use crate::users::UserPreferences;
+use crate::AppState;
+
+/// Entries accepted in one import.
+pub const MAX_ENTRIES: usize = 5000;
+
+/// Search results offered as suggestions for an unmatched entry.
+const MAX_CANDIDATES: usize = 5;
+
+/// Most bytes a compressed upload may inflate to.
+const MAX_DECOMPRESSED_BYTES: u64 = 128 * 1024 * 1024;
+
+/// Entries matched and applied between progress updates.
+const CHUNK_SIZE: usize = 20;
+
+/// A pending import whose progress hasn't moved for this long lost its
+/// worker, e.g. to a restart.
+const STALL_MINUTES: i32 = 15;
+
+/// Times an entry waits out a MangaDex rate limit before it is reported as
+/// failed.
+const RATE_LIMIT_RETRIES: u32 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "import_format", rename_all = "snake_case")]
+pub enum ImportFormat {
+    /// Follows of a MangaDex account, read with the user's MangaDex token.
+    Mangadex,
+    /// A Tachiyomi or Mihon `.tachibk` backup.
+    Tachiyomi,
+    /// A MyAnimeList XML list export.
+    Myanimelist,
+}
+
+/// One library entry read from an export, before it is matched to MangaDex.
+#[derive(Debug, Default)]
+pub struct ImportedManga {
+    pub title: String,
+    /// Known when the export came from MangaDex itself.
+    pub mangadex_id: Option<String>,
+    /// IDs on trackers, keyed by tracker ID (`anilist`, `myanimelist`).
+    pub tracker_ids: Vec<(&'static str, String)>,
+    /// MangaDex chapters known to be read.
+    pub read_chapter_ids: Vec<String>,
+    /// Chapter numbers known to be read, for exports without MangaDex IDs.
+    pub read_chapter_numbers: Vec<f64>,
+    /// Every chapter up to and including this number counts as read.
+    pub chapters_read: Option<f64>,
+    pub status: Option<ReadingStatus>,
+    pub score: Option<i16>,
+    pub started_on: Option<NaiveDate>,
+    pub finished_on: Option<NaiveDate>,
+    pub reread_count: i32,
+    /// Library category names.
+    pub categories: Vec<String>,
+    pub added_at: Option<DateTime<Utc>>,
+    pub last_read_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportCandidate {
+    pub id: String,
+    pub title: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UnmatchedEntry {
+    pub title: String,
+    pub reason: &'static str,
+    /// Close search results the user may want to bookmark by hand.
+    pub candidates: Vec<ImportCandidate>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportReport {
+    pub format: ImportFormat,
+    pub total: usize,
+    /// Entries newly added to the library.
+    pub imported: usize,
+    /// Matched entries that were bookmarked already; their history and
+    /// status are still merged in.
+    pub already_in_library: usize,
+    pub chapters_marked_read: u64,
+    pub unmatched: Vec<UnmatchedEntry>,
+}
+
+/// Undoes gzip compression, which both Tachiyomi backups and MyAnimeList
+/// exports usually come with.
+pub fn decompress(data: Vec<u8>) -> std::io::Result<Vec<u8>> {
+    decompress_limited(data, MAX_DECOMPRESSED_BYTES)
+}
+
+/// [`decompress`], refusing output over `limit` bytes so that a small
+/// upload can't inflate into gigabytes.
+fn decompress_limited(data: Vec<u8>, limit: u64) -> std::io::Result<Vec<u8>> {
+    if !data.starts_with(&[0x1f, 0x8b]) {
+        return Ok(data);
+    }
+
+    let mut out = Vec::new();
+    flate2::read::GzDecoder::new(&data[..])
+        .take(limit + 1)
+        .read_to_end(&mut out)?;
+    if out.len() as u64 > limit {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "Decompressed file is too large",
+        ));
+    }
+    Ok(out)
+}
+
+/// Title reduced to lowercase letters and digits, for comparing titles
+/// across sites.
+fn normalize_title(title: &str) -> String {
+    title
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+enum Resolution {
+    Found(String),
+    Unmatched {
+        reason: &'static str,
+        candidates: Vec<ImportCandidate>,
+    },
+}
+
+/// Finds the MangaDex ID of an imported entry: from the export, from a
+/// known tracker mapping, or by title search.
+async fn resolve(
+    state: &AppState,
+    source: &Arc<dyn MangaSource>,
+    entry: &ImportedManga,
+) -> anyhow::Result<Resolution> {
+    if let Some(id) = &entry.mangadex_id {
+        return Ok(Resolution::Found(id.clone()));
+    }
+
+    for (tracker, remote_id) in &entry.tracker_ids {
+        let mapped: Option<String> = sqlx::query_scalar(
+            r#"
+            SELECT manga_mangadex_id
+            FROM tracker_mappings
+            WHERE source = $1 AND tracker = $2 AND remote_id = $3
+            LIMIT 1
+            "#,
+        )
+        .bind(source.id())
+        .bind(tracker)
+        .bind(remote_id)
+        .fetch_optional(&state.db_pool)
+        .await?;
+
+        if let Some(id) = mapped {
+            return Ok(Resolution::Found(id));
+        }
+    }
+
+    let wanted = normalize_title(&entry.title);
+    if wanted.is_empty() {
+        return Ok(Resolution::Unmatched {
+            reason: "no_title",
+            candidates: Vec::new(),
+        });
+    }
+
+    let filters = SearchFilters {
+        title: Some(entry.title.clone()),
+        content_rating: ContentRating::ALL.to_vec(),
+        ..Default::default()
+    };
+    let results = source.search(&filters, 10, 0).await?.data;
+
+    let exact = results.iter().find(|manga| {
+        std::iter::once(&manga.title)
+            .chain(&manga.alt_titles)
+            .any(|title| normalize_title(title) == wanted)
+    });
+    if let Some(manga) = exact {
+        return Ok(Resolution::Found(manga.mangadex_id.clone()));
+    }
+
+    // Titles differ between sites more often than tracker IDs do
+    if !entry.tracker_ids.is_empty() {
+        for manga in results.iter().take(3) {
+            let links = source.tracker_links(&manga.mangadex_id).await?;
+            let linked = entry.tracker_ids.iter().any(|(tracker, remote_id)| {
+                link_key(tracker).and_then(|key| links.get(key)) == Some(remote_id)
+            });
+            if linked {
+                return Ok(Resolution::Found(manga.mangadex_id.clone()));
+            }
+        }
+    }
+
+    Ok(Resolution::Unmatched {
+        reason: if results.is_empty() {
+            "not_found"
+        } else {
+            "ambiguous"
+        },
+        candidates: results
+            .into_iter()
+            .take(MAX_CANDIDATES)
+            .map(|manga| ImportCandidate {
+                id: manga.mangadex_id,
+                title: manga.title,
+            })
+            .collect(),
+    })
+}
+
+/// MangaDex link key of a tracker, whether or not it is configured here.
+fn link_key(tracker: &str) -> Option<&'static str> {
+    match tracker {
+        anilist::TRACKER_ID => Some(anilist::LINK_KEY),
+        trackers::myanimelist::TRACKER_ID => Some(trackers::myanimelist::LINK_KEY),
+        _ => None,
+    }
+}
+
+struct Applied {
+    new_bookmark: bool,
+    chapters_marked_read: u64,
+}
+
+/// Bookmarks the manga and merges in the entry's categories, status and
+/// reading history. Existing progress and status are never overwritten.
+async fn apply(
+    state: &AppState,
+    source: &Arc<dyn MangaSource>,
+    user_id: Uuid,
+    manga_id: &str,
+    entry: &ImportedManga,
+    categories: &HashMap<String, Uuid>,
+    lang: &str,
+) -> anyhow::Result<Applied> {
+    let inserted: Option<Uuid> = sqlx::query_scalar(
+        r#"
+        INSERT INTO user_bookmarks (user_id, source, manga_mangadex_id, created_at)
+        VALUES ($1, $2, $3, COALESCE($4, NOW()))
+        ON CONFLICT (user_id, source, manga_mangadex_id) DO NOTHING
+        RETURNING id
+        "#,
+    )
+    .bind(user_id)
+    .bind(source.id())
+    .bind(manga_id)
+    .bind(entry.added_at)
+    .fetch_optional(&state.db_pool)
+    .await?;
+
+    let new_bookmark = inserted.is_some();
+    let bookmark_id = match inserted {
+        Some(id) => id,
+        None => {
+            sqlx::query_scalar(
+                r#"
+                SELECT id FROM user_bookmarks
+                WHERE user_id = $1 AND source = $2 AND manga_mangadex_id = $3
+                "#,
+            )
+            .bind(user_id)
+            .bind(source.id())
+            .bind(manga_id)
+            .fetch_one(&state.db_pool)
+            .await?
+        }
+    };
+
+    let category_ids: Vec<Uuid> = entry
+        .categories
+        .iter()
+        .filter_map(|name| categories.get(name).copied())
+        .collect();
+    if !category_ids.is_empty() {
+        sqlx::query(
+            r#"
+            INSERT INTO bookmark_categories (bookmark_id, category_id)
+            SELECT $1, UNNEST($2::uuid[])
+            ON CONFLICT (bookmark_id, category_id) DO NOTHING
+            "#,
+        )
+        .bind(bookmark_id)
+        .bind(&category_ids)
+        .execute(&state.db_pool)
+        .await?;
+    }
+
+    if let Some(status) = entry.status {
+        sqlx::query(
+            r#"
+            INSERT INTO user_manga_status (
+                user_id, source, manga_mangadex_id, status, score,
+                started_on, finished_on, reread_count
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            ON CONFLICT (user_id, source, manga_mangadex_id) DO NOTHING
+            "#,
+        )
+        .bind(user_id)
+        .bind(source.id())
+        .bind(manga_id)
+        .bind(status)
+        .bind(entry.score.filter(|score| (1..=10).contains(score)))
+        .bind(entry.started_on)
+        .bind(entry.finished_on)
+        .bind(entry.reread_count.max(0))
+        .execute(&state.db_pool)
+        .await?;
+    }
+
+    let chapters_marked_read = import_reads(state, source, user_id, manga_id, entry, lang).await?;
+
+    Ok(Applied {
+        new_bookmark,
+        chapters_marked_read,
+    })
+}
+
+/// Writes reading history for the entry's read chapters and, if the user
+/// has none yet, progress at the furthest of them.
+async fn import_reads(
+    state: &AppState,
+    source: &Arc<dyn MangaSource>,
+    user_id: Uuid,
+    manga_id: &str,
+    entry: &ImportedManga,
+    lang: &str,
+) -> anyhow::Result<u64> {
+    let has_reads = !entry.read_chapter_ids.is_empty()
+        || !entry.read_chapter_numbers.is_empty()
+        || entry.chapters_read.is_some_and(|n| n > 0.0);
+    if !has_reads {
+        return Ok(0);
+    }
+
+    let chapters = get_chapters_with_cache(
+        manga_id,
+        lang,
+        &state.db_pool,
+        &state.hot_cache,
+        source,
+        &state.mangadex_config,
+    )
+    .await?
+    .into_inner();
+
+    let read_numbers: Vec<f64> = entry.read_chapter_numbers.clone();
+    let mut seen_numbers = HashSet::new();
+    let mut read_ids: HashSet<String> = entry.read_chapter_ids.iter().cloned().collect();
+    let mut furthest: Option<(f64, &crate::mangadex::Chapter)> = None;
+
+    for chapter in &chapters {
+        let number = chapter
+            .chapter_number
+            .as_deref()
+            .and_then(|n| n.trim().parse::<f64>().ok());
+
+        let read = read_ids.contains(&chapter.mangadex_id)
+            || number.is_some_and(|n| {
+                entry.chapters_read.is_some_and(|upto| n <= upto)
+                    || read_numbers.iter().any(|r| (r - n).abs() < f64::EPSILON)
+            });
+        if !read {
+            continue;
+        }
+
+        // One release per chapter number is enough to count it as read
+        if let Some(n) = number {
+            if !seen_numbers.insert(n.to_bits()) && !read_ids.contains(&chapter.mangadex_id) {
+                continue;
+            }
+            if furthest.is_none_or(|(best, _)| n > best) {
+                furthest = Some((n, chapter));
+            }
+        }
+        read_ids.insert(chapter.mangadex_id.clone());
+    }
+
+    let read_ids: Vec<String> = read_ids.into_iter().collect();
+    let result = sqlx::query(
+        r#"
+        INSERT INTO reading_history (user_id, source, manga_mangadex_id, chapter_mangadex_id, read_at)
+        SELECT $1, $2, $3, UNNEST($4::text[]), COALESCE($5, NOW())
+        ON CONFLICT (user_id, source, chapter_mangadex_id) DO NOTHING
+        "#,
+    )
+    .bind(user_id)
+    .bind(source.id())
+    .bind(manga_id)
+    .bind(&read_ids)
+    .bind(entry.last_read_at)
+    .execute(&state.db_pool)
+    .await?;
+
+    if let Some((_, chapter)) = furthest {
+        sqlx::query(
+            r#"
+            INSERT INTO user_reading_progress (user_id, source, manga_mangadex_id, chapter_mangadex_id, page_number)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (user_id, source, manga_mangadex_id) DO NOTHING
+            "#,
+        )
+        .bind(user_id)
+        .bind(source.id())
+        .bind(manga_id)
+        .bind(&chapter.mangadex_id)
+        // Finished: the chapter's last page
+        .bind(chapter.page_count.saturating_sub(1) as i32)
+        .execute(&state.db_pool)
+        .await?;
+    }
+
+    Ok(result.rows_affected())
+}
+
+/// Creates any library categories the entries use that the user doesn't
+/// have yet, returning every one of them by name.
+async fn ensure_categories(
+    state: &AppState,
+    user_id: Uuid,
+    entries: &[ImportedManga],
+) -> Result<HashMap<String, Uuid>, sqlx::Error> {
+    let mut names: Vec<String> = Vec::new();
+    for name in entries.iter().flat_map(|e| &e.categories) {
+        let name = name.trim();
+        if !name.is_empty() && name.len() <= 64 && !names.iter().any(|n| n == name) {
+            names.push(name.to_string());
+        }
+    }
+    if names.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    sqlx::query(
+        r#"
+        INSERT INTO library_categories (user_id, name, position)
+        SELECT
+            $1,
+            name,
+            (SELECT COALESCE(MAX(position) + 1, 0) FROM library_categories WHERE user_id = $1) + ord
+        FROM UNNEST($2::text[]) WITH ORDINALITY AS n(name, ord)
+        ON CONFLICT (user_id, name) DO NOTHING
+        "#,
+    )
+    .bind(user_id)
+    .bind(&names)
+    .execute(&state.db_pool)
+    .await?;
+
+    let rows: Vec<(String, Uuid)> = sqlx::query_as(
+        "SELECT name, id FROM library_categories WHERE user_id = $1 AND name = ANY($2)",
+    )
+    .bind(user_id)
+    .bind(&names)
+    .fetch_all(&state.db_pool)
+    .await?;
+
+    Ok(rows.into_iter().collect())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "import_status", rename_all = "snake_case")]
+pub enum ImportStatus {
+    Pending,
+    Done,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct LibraryImport {
+    pub id: Uuid,
+    pub format: ImportFormat,
+    pub status: ImportStatus,
+    /// Entries read from the export; 0 until they have been read.
+    pub total: i32,
+    pub processed: i32,
+    /// The [`ImportReport`], once the import is done.
+    pub report: Option<serde_json::Value>,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+const IMPORT_COLUMNS: &str =
+    "id, format, status, total, processed, report, error, created_at, completed_at";
+
+impl LibraryImport {
+    pub async fn load(
+        db: &PgPool,
+        user_id: Uuid,
+        import_id: Uuid,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        fail_stalled(db, user_id).await?;
+
+        sqlx::query_as::<_, Self>(&format!(
+            "SELECT {} FROM library_imports WHERE user_id = $1 AND id = $2",
+            IMPORT_COLUMNS
+        ))
+        .bind(user_id)
+        .bind(import_id)
+        .fetch_optional(db)
+        .await
+    }
+
+    /// The user's import that is still running, if any.
+    pub async fn pending(db: &PgPool, user_id: Uuid) -> Result<Option<Self>, sqlx::Error> {
+        fail_stalled(db, user_id).await?;
+
+        sqlx::query_as::<_, Self>(&format!(
+            r#"
+            SELECT {} FROM library_imports
+            WHERE user_id = $1 AND status = 'pending'
+            ORDER BY created_at DESC
+            LIMIT 1
+            "#,
+            IMPORT_COLUMNS
+        ))
+        .bind(user_id)
+        .fetch_optional(db)
+        .await
+    }
+
+    pub async fn create(
+        db: &PgPool,
+        user_id: Uuid,
+        format: ImportFormat,
+    ) -> Result<Self, sqlx::Error> {
+        sqlx::query_as::<_, Self>(&format!(
+            r#"
+            INSERT INTO library_imports (user_id, format)
+            VALUES ($1, $2)
+            RETURNING {}
+            "#,
+            IMPORT_COLUMNS
+        ))
+        .bind(user_id)
+        .bind(format)
+        .fetch_one(db)
+        .await
+    }
+}
+
+/// Fails the user's pending imports whose worker stopped making progress.
+async fn fail_stalled(db: &PgPool, user_id: Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE library_imports
+        SET status = 'failed', error = 'Import was interrupted, please try again',
+            completed_at = NOW()
+        WHERE user_id = $1
+            AND status = 'pending'
+            AND updated_at < NOW() - make_interval(mins => $2)
+        "#,
+    )
+    .bind(user_id)
+    .bind(STALL_MINUTES)
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
+
+async fn record_progress(
+    db: &PgPool,
+    import_id: Uuid,
+    total: usize,
+    processed: usize,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "UPDATE library_imports SET total = $2, processed = $3, updated_at = NOW() WHERE id = $1",
+    )
+    .bind(import_id)
+    .bind(total as i32)
+    .bind(processed as i32)
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
+
+/// Stores the report, or the error shown to the user.
+async fn finish(
+    db: &PgPool,
+    import_id: Uuid,
+    result: Result<ImportReport, &'static str>,
+) -> Result<(), sqlx::Error> {
+    match result {
+        Ok(report) => {
+            sqlx::query(
+                r#"
+                UPDATE library_imports
+                SET status = 'done', report = $2, processed = total,
+                    updated_at = NOW(), completed_at = NOW()
+                WHERE id = $1
+                "#,
+            )
+            .bind(import_id)
+            .bind(serde_json::to_value(&report).ok())
+            .execute(db)
+            .await?;
+        }
+        Err(error) => {
+            sqlx::query(
+                r#"
+                UPDATE library_imports
+                SET status = 'failed', error = $2, updated_at = NOW(), completed_at = NOW()
+                WHERE id = $1
+                "#,
+            )
+            .bind(import_id)
+            .bind(error)
+            .execute(db)
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Where an import's entries come from.
+pub enum ImportInput {
+    /// Entries parsed from an uploaded file.
+    Parsed(Vec<ImportedManga>),
+    /// A MangaDex access token to read the account's follows with.
+    Mangadex(String),
+}
+
+/// Runs an import in the background. Its row in `library_imports` tracks
+/// progress and ends up holding the report.
+pub fn spawn_import(state: &AppState, import: &LibraryImport, user_id: Uuid, input: ImportInput) {
+    let state = state.clone();
+    let (import_id, format) = (import.id, import.format);
+
+    tokio::spawn(async move {
+        let entries = match input {
+            ImportInput::Parsed(entries) => Ok(entries),
+            ImportInput::Mangadex(token) => mangadex::fetch_follows(&state.mangadex_client, &token)
+                .await
+                .map_err(|e| {
+                    tracing::warn!("Failed to read MangaDex follows: {}", e);
+                    "Failed to read your MangaDex follows"
+                }),
+        };
+
+        let result = match entries {
+            Ok(entries) => run_import(&state, import_id, user_id, format, entries)
+                .await
+                .map_err(|e| {
+                    tracing::error!("Library import {} failed: {:#}", import_id, e);
+                    "Import failed, please try again"
+                }),
+            Err(error) => Err(error),
+        };
+
+        if let Err(e) = finish(&state.db_pool, import_id, result).await {
+            tracing::error!("Failed to record library import {}: {}", import_id, e);
+        }
+    });
+}
+
+fn is_rate_limited(error: &anyhow::Error) -> bool {
+    matches!(
+        error.downcast_ref::<MangaDexError>(),
+        Some(MangaDexError::RateLimited)
+    )
+}
+
+/// Runs `attempt`, waiting out MangaDex rate limits with growing pauses
+/// rather than failing the entry.
+async fn retry_rate_limited<T, F, Fut>(mut attempt: F) -> anyhow::Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = anyhow::Result<T>>,
+{
+    let mut wait = Duration::from_secs(2);
+    for _ in 0..RATE_LIMIT_RETRIES {
+        match attempt().await {
+            Err(e) if is_rate_limited(&e) => {
+                tokio::time::sleep(wait).await;
+                wait *= 2;
+            }
+            result => return result,
+        }
+    }
+    attempt().await
+}
+
+/// Matches every entry to MangaDex and adds the matches to the user's
+/// library, recording progress on the import as it goes. Entries that fail
+/// on their own are reported, not fatal.
+async fn run_import(
+    state: &AppState,
+    import_id: Uuid,
+    user_id: Uuid,
+    format: ImportFormat,
+    mut entries: Vec<ImportedManga>,
+) -> anyhow::Result<ImportReport> {
+    entries.truncate(MAX_ENTRIES);
+
+    let source = state.sources.default_source();
+    let preferences = UserPreferences::load(&state.db_pool, user_id).await?;
+    let allowed =
+        preferences.allowed_content_ratings(&state.mangadex_config.default_content_ratings);
+    let lang = preferences
+        .translated_languages
+        .into_iter()
+        .next()
+        .unwrap_or_else(|| crate::users::preferences::DEFAULT_LANGUAGE.to_string());
+    for entry in &mut entries {
+        entry.categories = entry
+            .categories
+            .iter()
+            .map(|name| name.trim().to_string())
+            .collect();
+    }
+    let categories = ensure_categories(state, user_id, &entries).await?;
+
+    let total = entries.len();
+    let mut report = ImportReport {
+        format,
+        total,
+        imported: 0,
+        already_in_library: 0,
+        chapters_marked_read: 0,
+        unmatched: Vec::new(),
+    };
+    record_progress(&state.db_pool, import_id, total, 0).await?;
+
+    let mut processed = 0;
+    let mut entries = entries.into_iter().peekable();
+    while entries.peek().is_some() {
+        let chunk: Vec<ImportedManga> = entries.by_ref().take(CHUNK_SIZE).collect();
+        processed += chunk.len();
+
+        let mut resolved = Vec::new();
+        for entry in chunk {
+            match retry_rate_limited(|| resolve(state, &source, &entry)).await {
+                Ok(Resolution::Found(id)) => resolved.push((id, entry)),
+                Ok(Resolution::Unmatched { reason, candidates }) => {
+                    report.unmatched.push(UnmatchedEntry {
+                        title: entry.title,
+                        reason,
+                        candidates,
+                    })
+                }
+                Err(e) => {
+                    tracing::warn!("import lookup for {:?} failed: {:#}", entry.title, e);
+                    report.unmatched.push(UnmatchedEntry {
+                        title: entry.title,
+                        reason: "lookup_failed",
+                        candidates: Vec::new(),
+                    });
+                }
+            }
+        }
+
+        // IDs from exports may point at deleted manga; this also warms the cache
+        let ids: Vec<String> = resolved.iter().map(|(id, _)| id.clone()).collect();
+        let known = get_mangas_with_cache(
+            &ids,
+            &state.db_pool,
+            &state.hot_cache,
+            &source,
+            &state.mangadex_config,
+        )
+        .await?;
+
+        for (manga_id, entry) in resolved {
+            // Matching searches every rating; only bookmark what the user
+            // could have bookmarked by hand
+            let reason = match known.get(&manga_id) {
+                None => Some("not_found"),
+                Some(manga) if !ContentRating::is_allowed(&manga.content_rating, &allowed) => {
+                    Some("content_rating")
+                }
+                Some(_) => None,
+            };
+            if let Some(reason) = reason {
+                report.unmatched.push(UnmatchedEntry {
+                    title: entry.title,
+                    reason,
+                    candidates: Vec::new(),
+                });
+                continue;
+            }
+
+            let applied = retry_rate_limited(|| {
+                apply(
+                    state,
+                    &source,
+                    user_id,
+                    &manga_id,
+                    &entry,
+                    &categories,
+                    &lang,
+                )
+            })
+            .await;
+            match applied {
+                Ok(applied) => {
+                    if applied.new_bookmark {
+                        report.imported += 1;
+                    } else {
+                        report.already_in_library += 1;
+                    }
+                    report.chapters_marked_read += applied.chapters_marked_read;
+                }
+                Err(e) => {
+                    tracing::warn!("import of {} failed: {:#}", manga_id, e);
+                    report.unmatched.push(UnmatchedEntry {
+                        title: entry.title,
+                        reason: "import_failed",
+                        candidates: Vec::new(),
+                    });
+                }
+            }
+        }
+
+        record_progress(&state.db_pool, import_id, total, processed).await?;
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn decompress_passes_plain_data_through() {
+        let data = b"<myanimelist></myanimelist>".to_vec();
+        assert_eq!(decompress(data.clone()).unwrap(), data);
+    }
+
+    #[test]
+    fn decompress_inflates_gzip() {
+        let data = b"backup".repeat(100);
+        assert_eq!(decompress(gzip(&data)).unwrap(), data);
+    }
+
+    #[test]
+    fn decompress_rejects_oversized_output() {
+        let data = vec![0u8; 4096];
+        assert_eq!(decompress_limited(gzip(&data), 4096).unwrap(), data);
+
+        let error = decompress_limited(gzip(&data), 4095).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[tokio::test]
+    async fn only_one_import_per_user_can_be_pending() {
+        let Some(db) = crate::db::test_pool().await else {
+            return;
+        };
+        let user_id = Uuid::new_v4();
+        sqlx::query("INSERT INTO users (id, email, username, password) VALUES ($1, $2, $3, 'x')")
+            .bind(user_id)
+            .bind(format!("{}@import.test", user_id))
+            .bind(format!("import-{}", user_id.simple()))
+            .execute(&db)
+            .await
+            .unwrap();
+
+        let first = LibraryImport::create(&db, user_id, ImportFormat::Tachiyomi)
+            .await
+            .unwrap();
+        let second = LibraryImport::create(&db, user_id, ImportFormat::Myanimelist).await;
+        assert!(matches!(
+            second,
+            Err(sqlx::Error::Database(e)) if e.is_unique_violation()
+        ));
+
+        // Once the first one is done another may start
+        finish(&db, first.id, Err("done")).await.unwrap();
+        LibraryImport::create(&db, user_id, ImportFormat::Myanimelist)
+            .await
+            .unwrap();
+
+        sqlx::query("DELETE FROM users WHERE id = $1")
+            .bind(user_id)
+            .execute(&db)
+            .await
+            .unwrap();
+    }
+
+    #[test]
+    fn normalize_title_keeps_lowercase_letters_and_digits() {
+        assert_eq!(normalize_title("Chainsaw Man"), "chainsawman");
+        assert_eq!(
+            normalize_title("  JoJo's Bizarre Adventure: Part 7 "),
+            "jojosbizarreadventurepart7"
+        );
+        assert_eq!(normalize_title("Ōkami-san"), "ōkamisan");
+        assert_eq!(normalize_title("進撃の巨人"), "進撃の巨人");
+        assert_eq!(normalize_title("?!"), "");
+    }
+}
@@ -0,0 +1,240 @@
+//! MyAnimeList XML manga list exports: one `<manga>` element per entry.
+
+use anyhow::Context;
+use chrono::NaiveDate;
+use quick_xml::escape::resolve_predefined_entity;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+
+use super::{decompress, ImportedManga, MAX_ENTRIES};
+use crate::trackers::myanimelist;
+use crate::users::status::ReadingStatus;
+
+fn reading_status(status: &str) -> Option<ReadingStatus> {
+    match status {
+        "Reading" => Some(ReadingStatus::Reading),
+        "Completed" => Some(ReadingStatus::Completed),
+        "On-Hold" => Some(ReadingStatus::OnHold),
+        "Dropped" => Some(ReadingStatus::Dropped),
+        "Plan to Read" => Some(ReadingStatus::PlanToRead),
+        _ => None,
+    }
+}
+
+/// Dates are `YYYY-MM-DD`, with `0000-00-00` for none.
+fn date(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()
+}
+
+/// Collects the fields of one `<manga>` element.
+#[derive(Default)]
+struct MalEntry {
+    id: String,
+    title: String,
+    read_chapters: String,
+    score: String,
+    status: String,
+    times_read: String,
+    start_date: String,
+    finish_date: String,
+    rereading: String,
+}
+
+impl MalEntry {
+    fn field(&mut self, name: &[u8]) -> Option<&mut String> {
+        match name {
+            b"manga_mangadb_id" => Some(&mut self.id),
+            b"manga_title" => Some(&mut self.title),
+            b"my_read_chapters" => Some(&mut self.read_chapters),
+            b"my_score" => Some(&mut self.score),
+            b"my_status" => Some(&mut self.status),
+            b"my_times_read" => Some(&mut self.times_read),
+            b"my_start_date" => Some(&mut self.start_date),
+            b"my_finish_date" => Some(&mut self.finish_date),
+            b"my_rereading" => Some(&mut self.rereading),
+            _ => None,
+        }
+    }
+
+    fn into_imported(self) -> ImportedManga {
+        let rereading = self.rereading.trim().eq_ignore_ascii_case("yes");
+        let id = self.id.trim().to_string();
+
+        ImportedManga {
+            title: self.title.trim().to_string(),
+            tracker_ids: if id.is_empty() || id == "0" {
+                Vec::new()
+            } else {
+                vec![(myanimelist::TRACKER_ID, id)]
+            },
+            chapters_read: self
+                .read_chapters
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|n| *n > 0.0),
+            status: if rereading {
+                Some(ReadingStatus::Rereading)
+            } else {
+                reading_status(self.status.trim())
+            },
+            // 0 means unscored
+            score: self
+                .score
+                .trim()
+                .parse::<i16>()
+                .ok()
+                .filter(|score| (1..=10).contains(score)),
+            started_on: date(self.start_date.trim()),
+            finished_on: date(self.finish_date.trim()),
+            reread_count: self.times_read.trim().parse().unwrap_or(0),
+            ..Default::default()
+        }
+    }
+}
+
+/// Appends text to the field being read, if it is one the import uses.
+fn push(current: &mut Option<MalEntry>, field: &Option<Vec<u8>>, text: &str) {
+    if let (Some(entry), Some(name)) = (current.as_mut(), field.as_deref()) {
+        if let Some(value) = entry.field(name) {
+            value.push_str(text);
+        }
+    }
+}
+
+/// Reads the `<manga>` entries out of an export file.
+pub fn parse(data: Vec<u8>) -> anyhow::Result<Vec<ImportedManga>> {
+    let data = decompress(data)?;
+    let mut reader = Reader::from_reader(&data[..]);
+
+    let mut entries = Vec::new();
+    let mut current: Option<MalEntry> = None;
+    let mut field: Option<Vec<u8>> = None;
+    let mut buf = Vec::new();
+
+    loop {
+        match reader
+            .read_event_into(&mut buf)
+            .context("Invalid MyAnimeList export")?
+        {
+            Event::Start(e) if e.name().as_ref() == b"manga" => {
+                current = Some(MalEntry::default());
+            }
+            Event::Start(e) => {
+                field = Some(e.name().as_ref().to_vec());
+            }
+            Event::End(e) if e.name().as_ref() == b"manga" => {
+                if let Some(entry) = current.take() {
+                    entries.push(entry.into_imported());
+                }
+                field = None;
+                // The rest would be dropped by the import anyway
+                if entries.len() >= MAX_ENTRIES {
+                    break;
+                }
+            }
+            Event::End(_) => field = None,
+            Event::Text(text) => push(&mut current, &field, &text.decode()?),
+            Event::CData(text) => push(&mut current, &field, &String::from_utf8_lossy(&text)),
+            Event::GeneralRef(entity) => {
+                let resolved = match entity.resolve_char_ref()? {
+                    Some(c) => c.to_string(),
+                    None => resolve_predefined_entity(&entity.decode()?)
+                        .unwrap_or_default()
+                        .to_string(),
+                };
+                push(&mut current, &field, &resolved);
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    if entries.is_empty()
+        && !data
+            .windows(b"<myanimelist".len())
+            .any(|w| w == b"<myanimelist")
+    {
+        anyhow::bail!("Not a MyAnimeList export");
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPORT: &str = r#"<?xml version="1.0" encoding="UTF-8" ?>
+<myanimelist>
+    <myinfo>
+        <user_name>reader</user_name>
+    </myinfo>
+    <manga>
+        <manga_mangadb_id>2</manga_mangadb_id>
+        <manga_title><![CDATA[Berserk]]></manga_title>
+        <my_read_chapters>364</my_read_chapters>
+        <my_start_date>2019-04-01</my_start_date>
+        <my_finish_date>0000-00-00</my_finish_date>
+        <my_score>10</my_score>
+        <my_status>Reading</my_status>
+        <my_times_read>1</my_times_read>
+        <my_rereading>NO</my_rereading>
+    </manga>
+    <manga>
+        <manga_mangadb_id>0</manga_mangadb_id>
+        <manga_title>Kaguya-sama &amp; Friends &#9829;</manga_title>
+        <my_read_chapters>0</my_read_chapters>
+        <my_score>0</my_score>
+        <my_status>Completed</my_status>
+        <my_rereading>YES</my_rereading>
+    </manga>
+    <manga>
+        <manga_mangadb_id>13</manga_mangadb_id>
+        <manga_title>One Piece</manga_title>
+        <my_status>Plan to Read</my_status>
+    </manga>
+</myanimelist>
+"#;
+
+    #[test]
+    fn reads_list_entries() {
+        let entries = parse(EXPORT.as_bytes().to_vec()).unwrap();
+        assert_eq!(entries.len(), 3);
+
+        let berserk = &entries[0];
+        assert_eq!(berserk.title, "Berserk");
+        assert_eq!(
+            berserk.tracker_ids,
+            [(myanimelist::TRACKER_ID, "2".to_string())]
+        );
+        assert_eq!(berserk.chapters_read, Some(364.0));
+        assert_eq!(berserk.status, Some(ReadingStatus::Reading));
+        assert_eq!(berserk.score, Some(10));
+        assert_eq!(berserk.started_on, NaiveDate::from_ymd_opt(2019, 4, 1));
+        assert_eq!(berserk.finished_on, None);
+        assert_eq!(berserk.reread_count, 1);
+
+        let kaguya = &entries[1];
+        assert_eq!(kaguya.title, "Kaguya-sama & Friends \u{2665}");
+        assert!(kaguya.tracker_ids.is_empty());
+        assert_eq!(kaguya.chapters_read, None);
+        assert_eq!(kaguya.score, None);
+        assert_eq!(kaguya.status, Some(ReadingStatus::Rereading));
+
+        assert_eq!(entries[2].status, Some(ReadingStatus::PlanToRead));
+    }
+
+    #[test]
+    fn accepts_an_empty_list() {
+        let entries = parse(b"<myanimelist><myinfo/></myanimelist>".to_vec()).unwrap();
+        assert!(entries.is_empty());
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        assert!(parse(b"<html><body>Not found</body></html>".to_vec()).is_err());
+        assert!(parse(b"not xml at all".to_vec()).is_err());
+    }
+}
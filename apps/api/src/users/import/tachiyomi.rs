@@ -0,0 +1,323 @@
+//! Tachiyomi and Mihon `.tachibk` backups: gzipped protobuf. Only the fields
+//! the import and export use are declared; protobuf skips the rest.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use prost::Message;
+
+use super::{decompress, ImportedManga};
+use crate::trackers::{anilist, myanimelist};
+
+/// Source ID of the MangaDex (English) extension.
+pub(crate) const MANGADEX_SOURCE_ID: i64 = 2499283573021220255;
+
+/// URL prefixes the MangaDex extension stores manga and chapters under.
+pub(crate) const MANGADEX_PATH_MANGA: &str = "/manga/";
+pub(crate) const MANGADEX_PATH_CHAPTER: &str = "/chapter/";
+
+/// Tracker IDs in the backup's `syncId` field.
+const SYNC_MYANIMELIST: i32 = 1;
+const SYNC_ANILIST: i32 = 2;
+
+#[derive(Clone, PartialEq, Message)]
+pub(crate) struct Backup {
+    #[prost(message, repeated, tag = "1")]
+    pub(crate) backup_manga: Vec<BackupManga>,
+    #[prost(message, repeated, tag = "2")]
+    pub(crate) backup_categories: Vec<BackupCategory>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub(crate) struct BackupManga {
+    #[prost(int64, tag = "1")]
+    pub(crate) source: i64,
+    #[prost(string, tag = "2")]
+    pub(crate) url: String,
+    #[prost(string, tag = "3")]
+    pub(crate) title: String,
+    #[prost(int64, tag = "13")]
+    pub(crate) date_added: i64,
+    #[prost(message, repeated, tag = "16")]
+    pub(crate) chapters: Vec<BackupChapter>,
+    #[prost(int64, repeated, tag = "17")]
+    pub(crate) categories: Vec<i64>,
+    #[prost(message, repeated, tag = "18")]
+    pub(crate) tracking: Vec<BackupTracking>,
+    #[prost(bool, tag = "100")]
+    pub(crate) favorite: bool,
+    #[prost(message, repeated, tag = "104")]
+    pub(crate) history: Vec<BackupHistory>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub(crate) struct BackupChapter {
+    #[prost(string, tag = "1")]
+    pub(crate) url: String,
+    #[prost(string, tag = "2")]
+    pub(crate) name: String,
+    #[prost(bool, tag = "4")]
+    pub(crate) read: bool,
+    #[prost(float, tag = "9")]
+    pub(crate) chapter_number: f32,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub(crate) struct BackupHistory {
+    #[prost(string, tag = "1")]
+    pub(crate) url: String,
+    #[prost(int64, tag = "2")]
+    pub(crate) last_read: i64,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub(crate) struct BackupCategory {
+    #[prost(string, tag = "1")]
+    pub(crate) name: String,
+    #[prost(int64, tag = "2")]
+    pub(crate) order: i64,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub(crate) struct BackupTracking {
+    #[prost(int32, tag = "1")]
+    pub(crate) sync_id: i32,
+    #[prost(int32, tag = "3")]
+    pub(crate) media_id_int: i32,
+    #[prost(float, tag = "6")]
+    pub(crate) last_chapter_read: f32,
+    #[prost(int64, tag = "100")]
+    pub(crate) media_id: i64,
+}
+
+/// MangaDex UUID at the end of a Tachiyomi MangaDex URL such as
+/// `/manga/<uuid>` or `/chapter/<uuid>`.
+fn uuid_after(url: &str, prefix: &str) -> Option<String> {
+    let id = url.strip_prefix(prefix)?.trim_end_matches('/');
+    uuid::Uuid::parse_str(id).ok().map(|id| id.to_string())
+}
+
+fn timestamp(millis: i64) -> Option<DateTime<Utc>> {
+    (millis > 0)
+        .then(|| DateTime::from_timestamp_millis(millis))
+        .flatten()
+}
+
+/// Reads the library (favorites) out of a backup file.
+pub fn parse(data: Vec<u8>) -> anyhow::Result<Vec<ImportedManga>> {
+    let data = decompress(data)?;
+    let backup = Backup::decode(&data[..])?;
+
+    // Manga refer to categories by their order, not their name
+    let categories: HashMap<i64, String> = backup
+        .backup_categories
+        .into_iter()
+        .map(|category| (category.order, category.name))
+        .collect();
+
+    let entries = backup
+        .backup_manga
+        .into_iter()
+        .filter(|manga| manga.favorite)
+        .map(|manga| {
+            let mangadex_id = uuid_after(&manga.url, MANGADEX_PATH_MANGA);
+            let from_mangadex = mangadex_id.is_some();
+
+            let mut entry = ImportedManga {
+                title: manga.title,
+                mangadex_id,
+                categories: manga
+                    .categories
+                    .iter()
+                    .filter_map(|order| categories.get(order).cloned())
+                    .collect(),
+                added_at: timestamp(manga.date_added),
+                last_read_at: manga
+                    .history
+                    .iter()
+                    .filter_map(|history| timestamp(history.last_read))
+                    .max(),
+                ..Default::default()
+            };
+
+            for chapter in manga.chapters.iter().filter(|chapter| chapter.read) {
+                let id = from_mangadex
+                    .then(|| uuid_after(&chapter.url, MANGADEX_PATH_CHAPTER))
+                    .flatten();
+                match id {
+                    Some(id) => entry.read_chapter_ids.push(id),
+                    None if chapter.chapter_number >= 0.0 => entry
+                        .read_chapter_numbers
+                        .push(chapter.chapter_number as f64),
+                    None => {}
+                }
+            }
+
+            for track in &manga.tracking {
+                let tracker = match track.sync_id {
+                    SYNC_MYANIMELIST => myanimelist::TRACKER_ID,
+                    SYNC_ANILIST => anilist::TRACKER_ID,
+                    _ => continue,
+                };
+                // Newer backups moved the ID to a 64-bit field
+                let remote_id = if track.media_id != 0 {
+                    track.media_id
+                } else {
+                    track.media_id_int as i64
+                };
+                if remote_id > 0 {
+                    entry.tracker_ids.push((tracker, remote_id.to_string()));
+                }
+                if track.last_chapter_read > 0.0 {
+                    let read = track.last_chapter_read as f64;
+                    entry.chapters_read = Some(entry.chapters_read.map_or(read, |n| n.max(read)));
+                }
+            }
+
+            entry
+        })
+        .collect();
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    const MANGA_ID: &str = "a1c7c817-4e59-43b7-9365-09675a149a6f";
+    const CHAPTER_ID: &str = "0f3b1a8e-7c2d-4d6e-9a5b-1c2d3e4f5a6b";
+
+    fn backup_file(backup: &Backup) -> Vec<u8> {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&backup.encode_to_vec()).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn chapter(url: &str, read: bool, number: f32) -> BackupChapter {
+        BackupChapter {
+            url: url.to_string(),
+            name: String::new(),
+            read,
+            chapter_number: number,
+        }
+    }
+
+    fn tracking(sync_id: i32, media_id_int: i32, media_id: i64, read: f32) -> BackupTracking {
+        BackupTracking {
+            sync_id,
+            media_id_int,
+            last_chapter_read: read,
+            media_id,
+        }
+    }
+
+    fn backup() -> Backup {
+        Backup {
+            backup_manga: vec![
+                BackupManga {
+                    source: MANGADEX_SOURCE_ID,
+                    url: format!("{}{}", MANGADEX_PATH_MANGA, MANGA_ID),
+                    title: "Frieren".to_string(),
+                    date_added: 1_700_000_000_000,
+                    chapters: vec![
+                        chapter(
+                            &format!("{}{}", MANGADEX_PATH_CHAPTER, CHAPTER_ID),
+                            true,
+                            1.0,
+                        ),
+                        chapter("/chapter/not-a-uuid", true, 2.0),
+                        chapter("/chapter/unread", false, 3.0),
+                    ],
+                    categories: vec![1],
+                    tracking: vec![
+                        tracking(SYNC_ANILIST, 118586, 0, 4.0),
+                        tracking(SYNC_MYANIMELIST, 0, 126287, 6.0),
+                        tracking(99, 1, 0, 50.0),
+                    ],
+                    favorite: true,
+                    history: vec![
+                        BackupHistory {
+                            url: String::new(),
+                            last_read: 1_700_000_100_000,
+                        },
+                        BackupHistory {
+                            url: String::new(),
+                            last_read: 1_700_000_200_000,
+                        },
+                    ],
+                },
+                BackupManga {
+                    source: 1234,
+                    url: "/series/some-title".to_string(),
+                    title: "From another source".to_string(),
+                    chapters: vec![
+                        chapter("/read/1", true, 7.5),
+                        chapter("/read/x", true, -1.0),
+                    ],
+                    favorite: true,
+                    ..Default::default()
+                },
+                BackupManga {
+                    title: "Not in the library".to_string(),
+                    favorite: false,
+                    ..Default::default()
+                },
+            ],
+            backup_categories: vec![BackupCategory {
+                name: "Reading".to_string(),
+                order: 1,
+            }],
+        }
+    }
+
+    #[test]
+    fn reads_favorites_with_their_history() {
+        let entries = parse(backup_file(&backup())).unwrap();
+        assert_eq!(entries.len(), 2);
+
+        let frieren = &entries[0];
+        assert_eq!(frieren.title, "Frieren");
+        assert_eq!(frieren.mangadex_id.as_deref(), Some(MANGA_ID));
+        assert_eq!(frieren.categories, ["Reading"]);
+        assert_eq!(
+            frieren.added_at,
+            DateTime::from_timestamp_millis(1_700_000_000_000)
+        );
+        assert_eq!(
+            frieren.last_read_at,
+            DateTime::from_timestamp_millis(1_700_000_200_000)
+        );
+        // Chapters whose URL has no MangaDex ID fall back to their number
+        assert_eq!(frieren.read_chapter_ids, [CHAPTER_ID]);
+        assert_eq!(frieren.read_chapter_numbers, [2.0]);
+        assert_eq!(
+            frieren.tracker_ids,
+            [
+                (anilist::TRACKER_ID, "118586".to_string()),
+                (myanimelist::TRACKER_ID, "126287".to_string()),
+            ]
+        );
+        assert_eq!(frieren.chapters_read, Some(6.0));
+
+        let other = &entries[1];
+        assert_eq!(other.mangadex_id, None);
+        assert!(other.read_chapter_ids.is_empty());
+        assert_eq!(other.read_chapter_numbers, [7.5]);
+        assert_eq!(other.added_at, None);
+        assert_eq!(other.chapters_read, None);
+    }
+
+    #[test]
+    fn reads_uncompressed_backups() {
+        let entries = parse(backup().encode_to_vec()).unwrap();
+        assert_eq!(entries.len(), 2);
+    }
+
+    #[test]
+    fn rejects_files_that_are_not_backups() {
+        assert!(parse(b"\xff\xff\xff\xff".to_vec()).is_err());
+    }
+}
@@ -0,0 +1,102 @@
+use sqlx::{Postgres, QueryBuilder};
+use uuid::Uuid;
+
+/// Everything after the user ID bind in [`push_library_cte`].
+///
+/// One row per bookmark with the values the library sorts and filters on.
+/// Unread chapters are the distinct chapter numbers, in the user's preferred
+/// languages, above the furthest chapter they have read or have progress in.
+const LIBRARY_CTE_BODY: &str = r#"::uuid AS user_id
+),
+prefs AS (
+    SELECT
+        me.user_id,
+        COALESCE(p.translated_languages, ARRAY['en']) AS languages
+    FROM me
+    LEFT JOIN user_preferences p ON p.user_id = me.user_id
+),
+library AS (
+    SELECT
+        b.id AS bookmark_id,
+        b.source,
+        b.manga_mangadex_id,
+        b.created_at AS added_at,
+        b.created_at::text AS bookmark_created_at,
+        p.chapter_mangadex_id,
+        p.page_number,
+        p.updated_at::text AS progress_updated_at,
+        GREATEST(p.updated_at, h.last_read_at, b.created_at) AS last_read_at,
+        COALESCE(u.latest_upload, 'epoch'::timestamptz) AS latest_upload,
+        COALESCE(m.title, '') AS title,
+        m.status::text AS status,
+        m.content_rating,
+        m.id IS NOT NULL AS cached,
+        m.tags,
+        COALESCE(u.unread_count, 0) AS unread_count,
+        (p.user_id IS NOT NULL OR h.last_read_at IS NOT NULL) AS started,
+        ARRAY(
+            SELECT bc.category_id FROM bookmark_categories bc WHERE bc.bookmark_id = b.id
+        ) AS category_ids,
+        s.status AS reading_status,
+        s.score,
+        s.started_on,
+        s.finished_on,
+        s.notes,
+        s.reread_count,
+        s.updated_at AS status_updated_at
+    FROM user_bookmarks b
+    JOIN prefs ON prefs.user_id = b.user_id
+    LEFT JOIN user_reading_progress p
+        ON p.user_id = b.user_id
+        AND p.source = b.source
+        AND p.manga_mangadex_id = b.manga_mangadex_id
+    LEFT JOIN user_manga_status s
+        ON s.user_id = b.user_id
+        AND s.source = b.source
+        AND s.manga_mangadex_id = b.manga_mangadex_id
+    LEFT JOIN manga_cache m
+        ON m.source = b.source
+        AND m.mangadex_id = b.manga_mangadex_id
+    LEFT JOIN LATERAL (
+        SELECT MAX(rh.read_at) AS last_read_at
+        FROM reading_history rh
+        WHERE rh.user_id = b.user_id
+            AND rh.source = b.source
+            AND rh.manga_mangadex_id = b.manga_mangadex_id
+    ) h ON TRUE
+    LEFT JOIN LATERAL (
+        SELECT MAX(chapter_number_value(c.chapter_number)) AS furthest
+        FROM chapter_cache c
+        WHERE c.source = b.source
+            AND (
+                c.mangadex_id = p.chapter_mangadex_id
+                OR EXISTS (
+                    SELECT 1 FROM reading_history rh
+                    WHERE rh.user_id = b.user_id
+                        AND rh.source = c.source
+                        AND rh.chapter_mangadex_id = c.mangadex_id
+                )
+            )
+            AND c.manga_mangadex_id = b.manga_mangadex_id
+    ) r ON TRUE
+    LEFT JOIN LATERAL (
+        SELECT
+            MAX(c.published_at) AS latest_upload,
+            COUNT(DISTINCT chapter_number_value(c.chapter_number)) FILTER (
+                WHERE chapter_number_value(c.chapter_number) > COALESCE(r.furthest, -1)
+            ) AS unread_count
+        FROM chapter_cache c
+        WHERE c.source = b.source
+            AND c.manga_mangadex_id = b.manga_mangadex_id
+            AND c.language = ANY(prefs.languages)
+    ) u ON TRUE
+)
+"#;
+
+/// Pushes a `WITH` clause defining a `library` relation for `user_id`.
+/// Callers follow it with their own `SELECT ... FROM library`.
+pub fn push_library_cte(query: &mut QueryBuilder<'_, Postgres>, user_id: Uuid) {
+    query.push("WITH me AS (\n    SELECT ");
+    query.push_bind(user_id);
+    query.push(LIBRARY_CTE_BODY);
+}
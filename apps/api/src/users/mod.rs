@@ -0,0 +1,8 @@
+pub mod export;
+pub mod import;
+pub mod library;
+pub mod notifications;
+pub mod preferences;
+pub mod status;
+
+pub use preferences::{is_language_code, ReadingDirection, UserPreferences};
@@ -0,0 +1,168 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use sqlx::{FromRow, PgPool};
+
+use crate::mangadex::cache::{refresh_chapters, HotCaches};
+use crate::mangadex::MangaDexError;
+use crate::source::{MangaSource, SourceRegistry};
+
+/// A bookmarked manga in one of its readers' languages.
+#[derive(FromRow)]
+struct WatchedManga {
+    source: String,
+    manga_mangadex_id: String,
+    language: String,
+}
+
+/// Refreshes the chapter list of every manga bookmarked by an account not
+/// pending deletion and records a notification for each chapter published
+/// after its watermark in `notification_watermarks`. Returns the number of
+/// notifications created.
+pub async fn poll_bookmarked_chapters(
+    db: &PgPool,
+    hot: &Arc<HotCaches>,
+    sources: &SourceRegistry,
+) -> Result<u64, MangaDexError> {
+    let watched = sqlx::query_as::<_, WatchedManga>(
+        r#"
+        SELECT DISTINCT b.source, b.manga_mangadex_id, lang AS language
+        FROM user_bookmarks b
+        JOIN users u ON u.id = b.user_id AND u.deleted_at IS NULL
+        LEFT JOIN user_preferences p ON p.user_id = b.user_id
+        CROSS JOIN LATERAL unnest(COALESCE(p.translated_languages, ARRAY['en'])) AS lang
+        "#,
+    )
+    .fetch_all(db)
+    .await
+    .map_err(|e| MangaDexError::Internal(anyhow::anyhow!("Database error: {}", e)))?;
+
+    let mut created = 0;
+    for manga in watched {
+        let Some(source) = sources.get(&manga.source) else {
+            continue;
+        };
+
+        match poll_manga(db, hot, &source, &manga).await {
+            Ok(count) => created += count,
+            // Back off until the next tick rather than hammering the source
+            Err(MangaDexError::RateLimited) => return Err(MangaDexError::RateLimited),
+            // One broken manga shouldn't stop the rest of the run
+            Err(e) => tracing::debug!(
+                "Failed to poll {}:{} ({}): {}",
+                manga.source,
+                manga.manga_mangadex_id,
+                manga.language,
+                e
+            ),
+        }
+    }
+
+    Ok(created)
+}
+
+async fn poll_manga(
+    db: &PgPool,
+    hot: &Arc<HotCaches>,
+    source: &Arc<dyn MangaSource>,
+    manga: &WatchedManga,
+) -> Result<u64, MangaDexError> {
+    let db_error =
+        |e: sqlx::Error| MangaDexError::Internal(anyhow::anyhow!("Database error: {}", e));
+
+    // `None` when never polled; `Some(None)` when polled but nothing was dated
+    let watermark: Option<Option<DateTime<Utc>>> = sqlx::query_scalar(
+        r#"
+        SELECT last_published_at
+        FROM notification_watermarks
+        WHERE source = $1 AND manga_mangadex_id = $2 AND language = $3
+        "#,
+    )
+    .bind(&manga.source)
+    .bind(&manga.manga_mangadex_id)
+    .bind(&manga.language)
+    .fetch_optional(db)
+    .await
+    .map_err(db_error)?;
+
+    let chapters =
+        refresh_chapters(&manga.manga_mangadex_id, &manga.language, db, hot, source).await?;
+
+    let published: Vec<(String, DateTime<Utc>)> = chapters
+        .into_iter()
+        .filter_map(|chapter| {
+            let published_at = chapter
+                .published_at
+                .as_deref()
+                .and_then(|p| DateTime::parse_from_rfc3339(p).ok())?;
+            Some((chapter.mangadex_id, published_at.with_timezone(&Utc)))
+        })
+        .collect();
+    let newest = published.iter().map(|(_, at)| *at).max();
+
+    // The first poll only sets the watermark; everything would look new otherwise
+    let new_chapter_ids: Vec<String> = match watermark {
+        None => Vec::new(),
+        Some(last_seen) => published
+            .into_iter()
+            .filter(|(_, at)| last_seen.is_none_or(|last_seen| *at > last_seen))
+            .map(|(id, _)| id)
+            .collect(),
+    };
+
+    let mut tx = db.begin().await.map_err(db_error)?;
+
+    let created = if new_chapter_ids.is_empty() {
+        0
+    } else {
+        sqlx::query(
+            r#"
+            INSERT INTO notifications (user_id, source, manga_mangadex_id, chapter_mangadex_id)
+            SELECT b.user_id, b.source, b.manga_mangadex_id, chapter_id
+            FROM user_bookmarks b
+            JOIN users u ON u.id = b.user_id AND u.deleted_at IS NULL
+            LEFT JOIN user_preferences p ON p.user_id = b.user_id
+            CROSS JOIN unnest($4::text[]) AS chapter_id
+            WHERE b.source = $1
+                AND b.manga_mangadex_id = $2
+                AND $3 = ANY(COALESCE(p.translated_languages, ARRAY['en']))
+            ON CONFLICT (user_id, source, chapter_mangadex_id) DO NOTHING
+            "#,
+        )
+        .bind(&manga.source)
+        .bind(&manga.manga_mangadex_id)
+        .bind(&manga.language)
+        .bind(&new_chapter_ids)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            MangaDexError::Internal(anyhow::anyhow!("Failed to create notifications: {}", e))
+        })?
+        .rows_affected()
+    };
+
+    // Never moves backwards, e.g. when a chapter is pulled upstream
+    sqlx::query(
+        r#"
+        INSERT INTO notification_watermarks (source, manga_mangadex_id, language, last_published_at)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (source, manga_mangadex_id, language) DO UPDATE SET
+            last_published_at = GREATEST(
+                notification_watermarks.last_published_at,
+                EXCLUDED.last_published_at
+            ),
+            polled_at = NOW()
+        "#,
+    )
+    .bind(&manga.source)
+    .bind(&manga.manga_mangadex_id)
+    .bind(&manga.language)
+    .bind(newest)
+    .execute(&mut *tx)
+    .await
+    .map_err(db_error)?;
+
+    tx.commit().await.map_err(db_error)?;
+
+    Ok(created)
+}
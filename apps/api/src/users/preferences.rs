@@ -0,0 +1,209 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
+
+use crate::source::filters::ContentRating;
+
+/// Language used when a user has not chosen any.
+pub const DEFAULT_LANGUAGE: &str = "en";
+
+lazy_static::lazy_static! {
+    static ref LANGUAGE_CODE_REGEX: regex::Regex =
+        regex::Regex::new(r"^[a-z]{2}(-[a-z]{2,4})?$").unwrap();
+}
+
+/// Whether `code` looks like a MangaDex language code, e.g. `en` or `pt-br`.
+pub fn is_language_code(code: &str) -> bool {
+    LANGUAGE_CODE_REGEX.is_match(code)
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "reading_direction", rename_all = "lowercase")]
+pub enum ReadingDirection {
+    Ltr,
+    #[default]
+    Rtl,
+    Vertical,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UserPreferences {
+    /// Translated languages in order of preference.
+    pub translated_languages: Vec<String>,
+    /// Scanlation group IDs in order of preference.
+    pub scanlation_groups: Vec<String>,
+    /// Highest content rating the user wants to see, if they have set one.
+    pub content_rating_ceiling: Option<ContentRating>,
+    /// Whether the user has confirmed they are an adult and want ratings
+    /// beyond the server default.
+    pub mature_content: bool,
+    pub data_saver: bool,
+    pub reading_direction: ReadingDirection,
+}
+
+impl Default for UserPreferences {
+    fn default() -> Self {
+        Self {
+            translated_languages: vec![DEFAULT_LANGUAGE.to_string()],
+            scanlation_groups: Vec::new(),
+            content_rating_ceiling: None,
+            mature_content: false,
+            data_saver: false,
+            reading_direction: ReadingDirection::default(),
+        }
+    }
+}
+
+#[derive(FromRow)]
+struct UserPreferencesRow {
+    translated_languages: Vec<String>,
+    scanlation_groups: Vec<String>,
+    content_rating_ceiling: Option<String>,
+    mature_content: bool,
+    data_saver: bool,
+    reading_direction: ReadingDirection,
+}
+
+impl From<UserPreferencesRow> for UserPreferences {
+    fn from(row: UserPreferencesRow) -> Self {
+        let content_rating_ceiling = row
+            .content_rating_ceiling
+            .as_deref()
+            .and_then(ContentRating::parse);
+
+        // Rows saved before codes were validated may hold anything
+        let translated_languages: Vec<String> = row
+            .translated_languages
+            .into_iter()
+            .filter(|lang| is_language_code(lang))
+            .collect();
+        let translated_languages = if translated_languages.is_empty() {
+            vec![DEFAULT_LANGUAGE.to_string()]
+        } else {
+            translated_languages
+        };
+
+        Self {
+            translated_languages,
+            scanlation_groups: row.scanlation_groups,
+            content_rating_ceiling,
+            mature_content: row.mature_content,
+            data_saver: row.data_saver,
+            reading_direction: row.reading_direction,
+        }
+    }
+}
+
+impl UserPreferences {
+    /// Loads a user's preferences, falling back to defaults when they have
+    /// never saved any.
+    pub async fn load(db: &PgPool, user_id: Uuid) -> Result<Self, sqlx::Error> {
+        let row = sqlx::query_as::<_, UserPreferencesRow>(
+            r#"
+            SELECT
+                translated_languages,
+                scanlation_groups,
+                content_rating_ceiling,
+                mature_content_opted_in_at IS NOT NULL AS mature_content,
+                data_saver,
+                reading_direction
+            FROM user_preferences
+            WHERE user_id = $1
+            "#,
+        )
+        .bind(user_id)
+        .fetch_optional(db)
+        .await?;
+
+        Ok(row.map(Into::into).unwrap_or_default())
+    }
+
+    /// Loads preferences for an optional user; anonymous visitors get defaults.
+    pub async fn load_optional(db: &PgPool, user_id: Option<Uuid>) -> Result<Self, sqlx::Error> {
+        match user_id {
+            Some(user_id) => Self::load(db, user_id).await,
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub async fn save(&self, db: &PgPool, user_id: Uuid) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO user_preferences (
+                user_id, translated_languages, scanlation_groups,
+                content_rating_ceiling, data_saver, reading_direction,
+                mature_content_opted_in_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, CASE WHEN $7 THEN NOW() END)
+            ON CONFLICT (user_id) DO UPDATE SET
+                translated_languages = EXCLUDED.translated_languages,
+                scanlation_groups = EXCLUDED.scanlation_groups,
+                content_rating_ceiling = EXCLUDED.content_rating_ceiling,
+                mature_content_opted_in_at = CASE
+                    WHEN $7 THEN COALESCE(user_preferences.mature_content_opted_in_at, NOW())
+                END,
+                data_saver = EXCLUDED.data_saver,
+                reading_direction = EXCLUDED.reading_direction,
+                updated_at = NOW()
+            "#,
+        )
+        .bind(user_id)
+        .bind(&self.translated_languages)
+        .bind(&self.scanlation_groups)
+        .bind(self.content_rating_ceiling.map(|rating| rating.as_str()))
+        .bind(self.data_saver)
+        .bind(self.reading_direction)
+        .bind(self.mature_content)
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Content ratings this user may see. Anything beyond the server default
+    /// requires the mature content opt-in; the ceiling can only narrow it.
+    pub fn allowed_content_ratings(&self, server_default: &[ContentRating]) -> Vec<ContentRating> {
+        let base = if self.mature_content {
+            &ContentRating::ALL[..]
+        } else {
+            server_default
+        };
+
+        base.iter()
+            .copied()
+            .filter(|rating| {
+                self.content_rating_ceiling
+                    .is_none_or(|ceiling| *rating <= ceiling)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_mangadex_language_codes() {
+        for code in ["en", "ja", "pt-br", "es-la", "zh-hk", "ja-ro", "zh-hant"] {
+            assert!(is_language_code(code), "{code}");
+        }
+    }
+
+    #[test]
+    fn rejects_anything_else() {
+        for code in [
+            "",
+            "e",
+            "EN",
+            "eng",
+            "pt-b",
+            "en&contentRating[]=pornographic",
+            "en ",
+            "en-",
+        ] {
+            assert!(!is_language_code(code), "{code}");
+        }
+    }
+}
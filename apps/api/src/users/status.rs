@@ -0,0 +1,106 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "reading_status", rename_all = "snake_case")]
+pub enum ReadingStatus {
+    Reading,
+    Completed,
+    OnHold,
+    Dropped,
+    PlanToRead,
+    Rereading,
+}
+
+impl ReadingStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReadingStatus::Reading => "reading",
+            ReadingStatus::Completed => "completed",
+            ReadingStatus::OnHold => "on_hold",
+            ReadingStatus::Dropped => "dropped",
+            ReadingStatus::PlanToRead => "plan_to_read",
+            ReadingStatus::Rereading => "rereading",
+        }
+    }
+}
+
+/// A user's own record of a manga: where they are with it and what they
+/// thought of it.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct MangaStatus {
+    pub status: ReadingStatus,
+    /// 1 to 10.
+    pub score: Option<i16>,
+    pub started_on: Option<NaiveDate>,
+    pub finished_on: Option<NaiveDate>,
+    /// Private; never shown to other users.
+    pub notes: Option<String>,
+    pub reread_count: i32,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl MangaStatus {
+    pub async fn load(
+        db: &PgPool,
+        user_id: Uuid,
+        source: &str,
+        manga_mangadex_id: &str,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        sqlx::query_as::<_, Self>(
+            r#"
+            SELECT status, score, started_on, finished_on, notes, reread_count, updated_at
+            FROM user_manga_status
+            WHERE user_id = $1 AND source = $2 AND manga_mangadex_id = $3
+            "#,
+        )
+        .bind(user_id)
+        .bind(source)
+        .bind(manga_mangadex_id)
+        .fetch_optional(db)
+        .await
+    }
+
+    /// Inserts or replaces the record, returning it with its new
+    /// `updated_at`.
+    pub async fn save(
+        &self,
+        db: &PgPool,
+        user_id: Uuid,
+        source: &str,
+        manga_mangadex_id: &str,
+    ) -> Result<Self, sqlx::Error> {
+        sqlx::query_as::<_, Self>(
+            r#"
+            INSERT INTO user_manga_status (
+                user_id, source, manga_mangadex_id, status, score,
+                started_on, finished_on, notes, reread_count
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            ON CONFLICT (user_id, source, manga_mangadex_id) DO UPDATE SET
+                status = EXCLUDED.status,
+                score = EXCLUDED.score,
+                started_on = EXCLUDED.started_on,
+                finished_on = EXCLUDED.finished_on,
+                notes = EXCLUDED.notes,
+                reread_count = EXCLUDED.reread_count,
+                updated_at = NOW()
+            RETURNING status, score, started_on, finished_on, notes, reread_count, updated_at
+            "#,
+        )
+        .bind(user_id)
+        .bind(source)
+        .bind(manga_mangadex_id)
+        .bind(self.status)
+        .bind(self.score)
+        .bind(self.started_on)
+        .bind(self.finished_on)
+        .bind(&self.notes)
+        .bind(self.reread_count)
+        .fetch_one(db)
+        .await
+    }
+}